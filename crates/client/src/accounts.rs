@@ -0,0 +1,229 @@
+//! Decoding of program accounts
+//!
+//! Only current layouts decode. Accounts from before versioning fail with
+//! `ClientError::OutdatedAccount` and must go through `migrate_account`
+//! first.
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use privacy_layer::state::*;
+use std::mem::size_of;
+
+use crate::{ClientError, Result};
+
+/// A program account that can be decoded from raw account data
+pub trait DecodeAccount: Sized {
+    /// Layout version this client decodes
+    const VERSION: u8;
+
+    /// Decode account data, discriminator included
+    fn decode(data: &[u8]) -> Result<Self>;
+}
+
+/// Check the discriminator and version shared by every layout
+fn check_header<T: Discriminator>(data: &[u8], current_version: u8) -> Result<()> {
+    let discriminator = data.get(..8).ok_or(ClientError::InvalidAccountData)?;
+    if discriminator != T::DISCRIMINATOR {
+        return Err(ClientError::InvalidAccountData);
+    }
+    match data.get(8) {
+        Some(&version) if version == current_version => Ok(()),
+        Some(_) => Err(ClientError::OutdatedAccount),
+        None => Err(ClientError::InvalidAccountData),
+    }
+}
+
+macro_rules! borsh_account {
+    ($($account:ty),+) => {$(
+        impl DecodeAccount for $account {
+            const VERSION: u8 = <$account>::VERSION;
+
+            fn decode(data: &[u8]) -> Result<Self> {
+                check_header::<Self>(data, Self::VERSION)?;
+                Self::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccountData)
+            }
+        }
+    )+};
+}
+
+macro_rules! zero_copy_account {
+    ($($account:ty),+) => {$(
+        impl DecodeAccount for $account {
+            const VERSION: u8 = <$account>::VERSION;
+
+            fn decode(data: &[u8]) -> Result<Self> {
+                check_header::<Self>(data, Self::VERSION)?;
+                let body = data
+                    .get(8..<$account>::LEN)
+                    .ok_or(ClientError::InvalidAccountData)?;
+                Ok(bytemuck::pod_read_unaligned(body))
+            }
+        }
+    )+};
+}
+
+borsh_account!(
+    PrivateElection,
+    EncryptedVote,
+    NullifierRecord,
+    TallyResult,
+    ElectionResults,
+    GovConfig
+);
+zero_copy_account!(EligibilityVerifyingKey);
+
+/// An `EncryptedTally` header with the aggregate that follows it
+#[derive(Clone)]
+pub struct EncryptedTallyAccount {
+    pub header: EncryptedTally,
+    pub aggregate: Vec<BallotCiphertext>,
+}
+
+impl EncryptedTallyAccount {
+    /// Borrow as the program does, e.g. to check a decryption the way
+    /// `submit_tally_result` will
+    pub fn as_ref(&self) -> EncryptedTallyRef<'_> {
+        EncryptedTallyRef {
+            header: &self.header,
+            aggregate: &self.aggregate,
+        }
+    }
+}
+
+impl DecodeAccount for EncryptedTallyAccount {
+    const VERSION: u8 = EncryptedTally::VERSION;
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        check_header::<EncryptedTally>(data, Self::VERSION)?;
+        let header: EncryptedTally = bytemuck::pod_read_unaligned(
+            data.get(8..EncryptedTally::HEADER_LEN)
+                .ok_or(ClientError::InvalidAccountData)?,
+        );
+        let aggregate = data
+            .get(EncryptedTally::HEADER_LEN..EncryptedTally::space(header.num_options))
+            .ok_or(ClientError::InvalidAccountData)?
+            .chunks_exact(size_of::<BallotCiphertext>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect();
+        Ok(Self { header, aggregate })
+    }
+}
+
+/// A `BallotBuffer` header with the ballot written to it so far
+///
+/// `header.options_written` and `header.totals_written` say where to pick
+/// up an interrupted `write_ballot_buffer`.
+#[derive(Clone)]
+pub struct BallotBufferAccount {
+    pub header: BallotBuffer,
+    pub ciphertexts: Vec<BallotCiphertext>,
+    pub challenges: Vec<[u8; 32]>,
+    pub responses: Vec<[u8; 32]>,
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl DecodeAccount for BallotBufferAccount {
+    const VERSION: u8 = BallotBuffer::VERSION;
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        check_header::<BallotBuffer>(data, Self::VERSION)?;
+        let header: BallotBuffer = bytemuck::pod_read_unaligned(
+            data.get(8..BallotBuffer::HEADER_LEN)
+                .ok_or(ClientError::InvalidAccountData)?,
+        );
+        let mut rest = &data[BallotBuffer::HEADER_LEN..];
+        Ok(Self {
+            header,
+            ciphertexts: read_unaligned(&mut rest, header.num_options as usize)?,
+            challenges: read_unaligned(&mut rest, header.num_totals as usize)?,
+            responses: read_unaligned(&mut rest, header.num_totals as usize)?,
+            siblings: read_unaligned(&mut rest, header.num_siblings as usize)?,
+        })
+    }
+}
+
+/// Read `count` values off the front of `data`, which needn't be aligned
+fn read_unaligned<T: bytemuck::Pod>(data: &mut &[u8], count: usize) -> Result<Vec<T>> {
+    let len = count * size_of::<T>();
+    let bytes = data.get(..len).ok_or(ClientError::InvalidAccountData)?;
+    *data = &data[len..];
+    Ok(bytes.chunks_exact(size_of::<T>()).map(bytemuck::pod_read_unaligned).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use privacy_layer::migration::migrate;
+
+    const PRIVATE_ELECTION_V1: &[u8] =
+        include_bytes!("../../../test-vectors/accounts/v1/private_election.bin");
+    const ENCRYPTED_VOTE_V1: &[u8] =
+        include_bytes!("../../../test-vectors/accounts/v1/encrypted_vote.bin");
+
+    /// The v1 election fixture, ended so that it can be migrated
+    fn migrated_election() -> Vec<u8> {
+        let mut data = PRIVATE_ELECTION_V1.to_vec();
+        *data.last_mut().unwrap() = ElectionStatus::Ended as u8;
+        migrate(&data).unwrap().unwrap()
+    }
+
+    /// A current tally account, added after v1
+    fn tally_data() -> Vec<u8> {
+        EncryptedTally::account_data(255, Pubkey::new_unique(), 2)
+    }
+
+    #[test]
+    fn test_decode_requires_current_layout() {
+        assert_eq!(
+            PrivateElection::decode(PRIVATE_ELECTION_V1).err(),
+            Some(ClientError::OutdatedAccount)
+        );
+        assert_eq!(
+            EncryptedVote::decode(ENCRYPTED_VOTE_V1).err(),
+            Some(ClientError::OutdatedAccount)
+        );
+
+        let election = PrivateElection::decode(&migrated_election()).unwrap();
+        assert_eq!(election.version, PrivateElection::VERSION);
+        assert_eq!(election.num_options, 3);
+
+        let tally = EncryptedTallyAccount::decode(&tally_data()).unwrap();
+        assert_eq!(tally.header.version, EncryptedTally::VERSION);
+        assert_eq!(tally.header.num_options, 2);
+        assert_eq!(tally.aggregate.len(), 2);
+
+        let data = BallotBuffer::account_data(255, Pubkey::new_unique(), [1; 32], 3, BallotRule::SingleChoice, 5);
+        let buffer = BallotBufferAccount::decode(&data).unwrap();
+        assert_eq!((buffer.header.options_written, buffer.header.totals_written), (0, 0));
+        assert_eq!((buffer.ciphertexts.len(), buffer.challenges.len()), (3, 1));
+        assert_eq!(buffer.siblings.len(), 5);
+        // Decoded wherever the data sits in memory
+        let mut shifted = vec![0; data.len() + 1];
+        shifted[1..].copy_from_slice(&data);
+        assert_eq!(BallotBufferAccount::decode(&shifted[1..]).unwrap().siblings.len(), 5);
+    }
+
+    #[test]
+    fn test_decode_rejects_other_accounts() {
+        let data = migrated_election();
+
+        assert_eq!(
+            EncryptedVote::decode(&data).err(),
+            Some(ClientError::InvalidAccountData)
+        );
+        assert_eq!(
+            PrivateElection::decode(&data[..8]).err(),
+            Some(ClientError::InvalidAccountData)
+        );
+        assert_eq!(
+            PrivateElection::decode(&data[..20]).err(),
+            Some(ClientError::InvalidAccountData)
+        );
+
+        let data = tally_data();
+        assert_eq!(
+            EncryptedTallyAccount::decode(&data[..data.len() - 1]).err(),
+            Some(ClientError::InvalidAccountData)
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! End-to-end ballot preparation
+//!
+//! `prepare_ballot` encrypts a voter's choices under the election key,
+//! proves the ballot follows the election's rule and computes the
+//! commitment the program checks, leaving only eligibility to prove.
+
+use anchor_lang::prelude::Pubkey;
+use privacy_crypto::{
+    ballot_signal, commit_ballot, compute_poseidon_nullifier, compute_public_nullifier,
+    encrypt_ballot, weighted_leaf, ElGamalPublicKey, MerkleProof,
+};
+use privacy_layer::groth16::Groth16Proof;
+use privacy_layer::instructions::{vote_proof_context, BallotProofArgs};
+use privacy_layer::state::{BallotCiphertext, EligibilityMode, PrivateElection};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{ClientError, Result};
+
+/// An encrypted ballot ready to cast, minus its eligibility proof
+#[derive(Clone)]
+pub struct PreparedBallot {
+    /// The voter's nullifier for this election
+    pub nullifier: [u8; 32],
+
+    /// `commit_ballot(election_id, nullifier, ciphertexts)`
+    pub commitment: [u8; 32],
+
+    /// One ciphertext per option
+    pub ciphertexts: Vec<BallotCiphertext>,
+
+    /// Weight the ballot is cast with
+    pub weight: u64,
+
+    /// Proof that every option encrypts 0 or 1 and the ballot follows the
+    /// election's rule
+    pub proof: BallotProofArgs,
+}
+
+impl PreparedBallot {
+    /// Signal an anonymous eligibility proof must be bound to
+    ///
+    /// `revision` is 0 for a first ballot and the next revision for a
+    /// re-vote; `submitter` is the signer (the voter or a relayer) and
+    /// `fee` what a relayer is paid.
+    pub fn signal(&self, revision: u32, submitter: &Pubkey, fee: u64) -> [u8; 32] {
+        ballot_signal(&self.commitment, revision, &submitter.to_bytes(), fee)
+    }
+}
+
+/// How the voter proves they are on the election's voter tree
+#[derive(Clone)]
+pub enum Eligibility {
+    /// Inclusion proof of `eligibility_leaf`, for `EligibilityMode::Public`
+    Public(MerkleProof),
+
+    /// Groth16 proof over `PreparedBallot::signal`, for
+    /// `EligibilityMode::Anonymous`
+    Anonymous(Box<Groth16Proof>),
+}
+
+impl Eligibility {
+    /// The mode this proof is for
+    pub fn mode(&self) -> EligibilityMode {
+        match self {
+            Eligibility::Public(_) => EligibilityMode::Public,
+            Eligibility::Anonymous(_) => EligibilityMode::Anonymous,
+        }
+    }
+
+    /// The Merkle proof to write to the ballot buffer, if public
+    pub fn merkle_proof(&self) -> Option<&MerkleProof> {
+        match self {
+            Eligibility::Public(proof) => Some(proof),
+            Eligibility::Anonymous(_) => None,
+        }
+    }
+}
+
+/// Nullifier of a wallet in a public-eligibility election
+pub fn public_nullifier(election: &PrivateElection, voter: &Pubkey) -> [u8; 32] {
+    compute_public_nullifier(&voter.to_bytes(), &election.election_id)
+}
+
+/// Nullifier of a voter secret in an anonymous election, as the
+/// eligibility circuit computes it
+pub fn anonymous_nullifier(election: &PrivateElection, voter_secret: &[u8; 32]) -> [u8; 32] {
+    compute_poseidon_nullifier(voter_secret, &election.election_id)
+}
+
+/// Voter tree leaf of a wallet in a public-eligibility election
+///
+/// Weighted elections commit each voter's weight in their leaf.
+pub fn eligibility_leaf(election: &PrivateElection, voter: &Pubkey, weight: u64) -> [u8; 32] {
+    if election.vote_weighting.is_weighted() {
+        weighted_leaf(&voter.to_bytes(), weight)
+    } else {
+        voter.to_bytes()
+    }
+}
+
+/// Encrypt and prove a ballot for `private_election`
+///
+/// `election` is the decoded account at that address. `choices` holds 0
+/// or 1 per option and must follow the election's ballot rule; `weight`
+/// must be 1 unless the election is weighted.
+pub fn prepare_ballot<R: RngCore + CryptoRng>(
+    private_election: &Pubkey,
+    election: &PrivateElection,
+    nullifier: [u8; 32],
+    choices: &[u64],
+    weight: u64,
+    rng: &mut R,
+) -> Result<PreparedBallot> {
+    if choices.len() != election.num_options as usize {
+        return Err(ClientError::InvalidChoices);
+    }
+    election
+        .vote_weighting
+        .check_weight(weight)
+        .map_err(|_| ClientError::InvalidWeight)?;
+
+    let public_key = ElGamalPublicKey::from_bytes(election.mpc_public_key)?;
+    let randomness: Vec<[u8; 32]> = choices.iter().map(|_| random_bytes(rng)).collect();
+    let (ballot, proof) = encrypt_ballot(
+        &public_key,
+        choices,
+        &randomness,
+        &random_bytes(rng),
+        election.ballot_rule.into(),
+        &vote_proof_context(private_election, &nullifier),
+    )?;
+
+    Ok(PreparedBallot {
+        nullifier,
+        commitment: commit_ballot(&election.election_id, &nullifier, &ballot.ciphertexts),
+        ciphertexts: ballot.ciphertexts.into_iter().map(Into::into).collect(),
+        weight,
+        proof: BallotProofArgs {
+            slot_proofs: proof.slot_proofs.iter().map(|proof| proof.to_bytes()).collect(),
+            total_challenges: proof.total_proof.challenges,
+            total_responses: proof.total_proof.responses,
+        },
+    })
+}
+
+fn random_bytes<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use privacy_crypto::{BallotProof, ElGamalKeypair, ElGamalSecretKey, EncryptedBallot, MerkleTree};
+    use privacy_layer::instructions::verify_public_eligibility;
+    use privacy_layer::state::{
+        BallotRule, ElectionStatus, EncryptedTally, EncryptedTallyMut, VoteWeighting, MAX_OPTIONS,
+    };
+
+    /// Three options, approval of up to two, public eligibility,
+    /// token-weighted, keyed to `keypair()`
+    fn election() -> PrivateElection {
+        PrivateElection {
+            version: PrivateElection::VERSION,
+            bump: 254,
+            authority: Pubkey::new_unique(),
+            pending_authority: None,
+            tally_submitter: Pubkey::new_unique(),
+            guardian: Pubkey::new_unique(),
+            election: Pubkey::new_unique(),
+            mpc_public_key: keypair().public.point,
+            voter_merkle_root: [6; 32],
+            election_id: [7; 32],
+            total_encrypted_votes: 0,
+            open_vote_accounts: 0,
+            tally_requested: false,
+            tally_finalized: false,
+            created_at: 1_700_000_000,
+            starts_at: 1_700_000_100,
+            ends_at: 1_700_086_500,
+            num_options: 3,
+            ballot_rule: BallotRule::Approval { max_choices: 2 },
+            eligibility_mode: EligibilityMode::Public,
+            allow_revoting: true,
+            vote_weighting: VoteWeighting::TokenSnapshot {
+                mint: Pubkey::new_unique(),
+                slot: 123_456,
+            },
+            audit_period: 86_400,
+            max_relayer_fee: 5_000,
+            status: ElectionStatus::Active,
+        }
+    }
+
+    fn keypair() -> ElGamalKeypair {
+        ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]))
+    }
+
+    #[test]
+    fn test_prepared_ballots_verify_and_tally() {
+        let mut rng = rand::thread_rng();
+        let private_election = Pubkey::new_unique();
+        let voters = [(Pubkey::new_unique(), 25u64), (Pubkey::new_unique(), 10)];
+
+        // As many options as an election may have
+        let mut election = election();
+        election.num_options = MAX_OPTIONS;
+        election.ballot_rule = BallotRule::Approval { max_choices: 1 };
+        let leaves: Vec<[u8; 32]> = voters
+            .iter()
+            .map(|(voter, weight)| eligibility_leaf(&election, voter, *weight))
+            .collect();
+        let tree = MerkleTree::<privacy_crypto::KeccakHasher>::from_leaves(4, &leaves).unwrap();
+        election.voter_merkle_root = tree.root();
+
+        let mut tally_data = EncryptedTally::account_data(0, election.election, election.num_options);
+        let mut tally = EncryptedTallyMut::from_account_data(&mut tally_data).unwrap();
+
+        let num_options = MAX_OPTIONS as usize;
+        let mut first = vec![0; num_options];
+        first[0] = 1;
+        let mut last = vec![0; num_options];
+        last[num_options - 1] = 1;
+
+        for (index, ((voter, weight), choices)) in voters.iter().zip([first, last]).enumerate() {
+            let nullifier = public_nullifier(&election, voter);
+            let ballot =
+                prepare_ballot(&private_election, &election, nullifier, &choices, *weight, &mut rng).unwrap();
+
+            // The same checks `cast_encrypted_vote` makes
+            let eligibility = Eligibility::Public(tree.proof(index as u32).unwrap());
+            verify_public_eligibility(&election, voter, *weight, &nullifier, eligibility.merkle_proof()).unwrap();
+            let encrypted = EncryptedBallot {
+                ciphertexts: ballot.ciphertexts.iter().map(|&ct| ct.into()).collect(),
+            };
+            BallotProof::from(ballot.proof.clone())
+                .verify(
+                    &keypair().public,
+                    &encrypted,
+                    election.ballot_rule.into(),
+                    &vote_proof_context(&private_election, &nullifier),
+                )
+                .unwrap();
+            assert_eq!(
+                ballot.commitment,
+                commit_ballot(&election.election_id, &nullifier, &encrypted.ciphertexts)
+            );
+
+            tally.accumulate(&ballot.ciphertexts, ballot.weight).unwrap();
+        }
+
+        let counts: Vec<u64> = tally
+            .aggregate
+            .iter()
+            .map(|&ct| keypair().secret.decrypt(&ct.into()).unwrap())
+            .collect();
+        let mut expected = vec![0; num_options];
+        (expected[0], expected[num_options - 1]) = (25, 10);
+        assert_eq!(counts, expected);
+        assert_eq!(tally.header.total_weight, 35);
+    }
+
+    #[test]
+    fn test_prepare_rejects_invalid_ballots() {
+        let mut rng = rand::thread_rng();
+        let election = election();
+        let private_election = Pubkey::new_unique();
+
+        let prepare = |choices: &[u64], weight| {
+            prepare_ballot(&private_election, &election, [1; 32], choices, weight, &mut rand::thread_rng())
+                .err()
+        };
+        assert_eq!(prepare(&[1, 0], 1), Some(ClientError::InvalidChoices));
+        assert_eq!(prepare(&[1, 0, 0], 0), Some(ClientError::InvalidWeight));
+        // Approval of at most two options
+        assert!(matches!(prepare(&[1, 1, 1], 1), Some(ClientError::Crypto(_))));
+        assert!(prepare_ballot(&private_election, &election, [1; 32], &[1, 1, 0], 1, &mut rng).is_ok());
+    }
+
+    #[test]
+    fn test_signal_binds_submitter_and_fee() {
+        let election = election();
+        let ballot = prepare_ballot(
+            &Pubkey::new_unique(),
+            &election,
+            anonymous_nullifier(&election, &[9; 32]),
+            &[0, 0, 1],
+            1,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        let relayer = Pubkey::new_unique();
+
+        assert_eq!(
+            ballot.signal(0, &relayer, 5_000),
+            ballot_signal(&ballot.commitment, 0, &relayer.to_bytes(), 5_000)
+        );
+        assert_ne!(ballot.signal(0, &relayer, 5_000), ballot.signal(0, &relayer, 0));
+        assert_ne!(ballot.signal(0, &relayer, 0), ballot.signal(1, &relayer, 0));
+        assert_ne!(
+            ballot.signal(0, &relayer, 0),
+            ballot.signal(0, &Pubkey::new_unique(), 0)
+        );
+    }
+
+    /// `test-vectors/ballots.json` drives the TypeScript tests against a
+    /// local validator; every ballot and tally proof in it must pass the
+    /// program's checks for its fixture election
+    #[test]
+    fn test_ballot_vectors_verify() {
+        const VECTORS: &str = include_str!("../../../test-vectors/ballots.json");
+        let vectors: serde_json::Value = serde_json::from_str(VECTORS).unwrap();
+        let bytes = |value: &serde_json::Value| hex::decode(value.as_str().unwrap()).unwrap();
+        let hex32 = |value: &serde_json::Value| -> [u8; 32] { bytes(value).try_into().unwrap() };
+        let pubkey = |value: &serde_json::Value| value.as_str().unwrap().parse::<Pubkey>().unwrap();
+        let ciphertexts = |ballot: &serde_json::Value| -> Vec<BallotCiphertext> {
+            ballot["ciphertexts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|ct| BallotCiphertext { c1: hex32(&ct["c1"]), c2: hex32(&ct["c2"]) })
+                .collect()
+        };
+
+        assert_eq!(pubkey(&vectors["program_id"]), crate::PROGRAM_ID);
+
+        for fixture in vectors["elections"].as_object().unwrap().values() {
+            let private_election = crate::pda::find_private_election(&pubkey(&fixture["election"])).0;
+            let mut election = election();
+            // The program derives the id; the fixture records it for the
+            // TypeScript tests
+            election.election_id = PrivateElection::derive_election_id(&private_election);
+            assert_eq!(hex32(&fixture["election_id"]), election.election_id);
+            election.mpc_public_key = hex32(&fixture["mpc_public_key"]);
+            election.voter_merkle_root = hex32(&fixture["voter_merkle_root"]);
+            election.num_options = fixture["num_options"].as_u64().unwrap() as u8;
+            election.ballot_rule = privacy_layer::state::BallotRule::SingleChoice;
+            election.vote_weighting = privacy_layer::state::VoteWeighting::Equal;
+            let public_key = ElGamalPublicKey::from_bytes(election.mpc_public_key).unwrap();
+
+            let voters = fixture["voters"].as_array().unwrap();
+            for voter in voters {
+                let wallet = pubkey(&voter["pubkey"]);
+                assert_eq!(hex32(&voter["nullifier"]), public_nullifier(&election, &wallet));
+                let proof = MerkleProof {
+                    leaf_index: voter["leaf_index"].as_u64().unwrap() as u32,
+                    siblings: voter["siblings"].as_array().unwrap().iter().map(hex32).collect(),
+                };
+                verify_public_eligibility(&election, &wallet, 1, &hex32(&voter["nullifier"]), Some(&proof))
+                    .unwrap();
+            }
+
+            let mut tally_data = EncryptedTally::account_data(0, private_election, election.num_options);
+            let mut tally = EncryptedTallyMut::from_account_data(&mut tally_data).unwrap();
+            let mut cast: Vec<Option<Vec<BallotCiphertext>>> = vec![None; voters.len()];
+            let ballots = fixture["ballots"].as_array().unwrap().iter().map(|ballot| (ballot, false));
+            let recasts = fixture["recasts"].as_array().unwrap().iter().map(|ballot| (ballot, true));
+
+            for (ballot, recast) in ballots.chain(recasts) {
+                let voter = ballot["voter"].as_u64().unwrap() as usize;
+                let nullifier = hex32(&voters[voter]["nullifier"]);
+                let ciphertexts = ciphertexts(ballot);
+                let encrypted = EncryptedBallot {
+                    ciphertexts: ciphertexts.iter().map(|&ct| ct.into()).collect(),
+                };
+                let proof = BallotProofArgs {
+                    slot_proofs: ballot["slot_proofs"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|proof| bytes(proof).try_into().unwrap())
+                        .collect(),
+                    total_challenges: ballot["total_challenges"].as_array().unwrap().iter().map(hex32).collect(),
+                    total_responses: ballot["total_responses"].as_array().unwrap().iter().map(hex32).collect(),
+                };
+
+                BallotProof::from(proof)
+                    .verify(
+                        &public_key,
+                        &encrypted,
+                        election.ballot_rule.into(),
+                        &vote_proof_context(&private_election, &nullifier),
+                    )
+                    .unwrap();
+                assert_eq!(
+                    hex32(&ballot["commitment"]),
+                    commit_ballot(&election.election_id, &nullifier, &encrypted.ciphertexts)
+                );
+
+                match cast[voter].replace(ciphertexts.clone()) {
+                    Some(old) if recast => tally.replace(&old, 1, &ciphertexts, 1).unwrap(),
+                    None if !recast => tally.accumulate(&ciphertexts, 1).unwrap(),
+                    _ => panic!("ballot {voter} cast out of order"),
+                }
+            }
+
+            let tally_vectors = &fixture["tally"];
+            for ((count, proof), &ciphertext) in tally_vectors["counts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .zip(tally_vectors["proofs"].as_array().unwrap())
+                .zip(tally.aggregate.iter())
+            {
+                let proof = privacy_crypto::DleqProof {
+                    challenge: hex32(&proof["challenge"]),
+                    response: hex32(&proof["response"]),
+                };
+                public_key
+                    .verify_decryption(&ciphertext.into(), count.as_u64().unwrap(), &proof)
+                    .unwrap();
+            }
+        }
+    }
+}
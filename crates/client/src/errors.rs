@@ -0,0 +1,36 @@
+use core::fmt;
+use privacy_crypto::CryptoError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientError {
+    /// A `privacy-crypto` operation failed
+    Crypto(CryptoError),
+    /// The data isn't an account of the expected type
+    InvalidAccountData,
+    /// The account predates the current layout; run `migrate_account`
+    OutdatedAccount,
+    /// The choices don't match the election's options
+    InvalidChoices,
+    /// The weight isn't allowed by the election's weighting
+    InvalidWeight,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Crypto(err) => write!(f, "Crypto error: {}", err),
+            ClientError::InvalidAccountData => write!(f, "Invalid account data"),
+            ClientError::OutdatedAccount => write!(f, "Account layout is outdated"),
+            ClientError::InvalidChoices => write!(f, "Invalid ballot choices"),
+            ClientError::InvalidWeight => write!(f, "Invalid vote weight"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<CryptoError> for ClientError {
+    fn from(err: CryptoError) -> Self {
+        ClientError::Crypto(err)
+    }
+}
@@ -0,0 +1,647 @@
+//! Instruction builders
+//!
+//! Built from the program's own Anchor instruction and account types, so
+//! argument and account order can't drift from the program.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use privacy_crypto::MerkleProof;
+use privacy_layer::groth16::Groth16Proof;
+use privacy_layer::instructions::{BallotChunk, DecryptionProofArgs};
+use privacy_layer::state::{BallotRule, EligibilityMode, VoteWeighting};
+use privacy_layer::{accounts, instruction};
+
+use crate::ballot::{Eligibility, PreparedBallot};
+use crate::pda::*;
+
+/// Arguments of `initialize_private_election`
+#[derive(Clone, Copy)]
+pub struct ElectionConfig {
+    /// ElGamal public key ballots are encrypted under
+    pub mpc_public_key: [u8; 32],
+
+    /// Root of the voter Merkle tree
+    pub voter_merkle_root: [u8; 32],
+
+    /// Timestamp when voting opens
+    pub starts_at: i64,
+
+    /// Timestamp when voting ends
+    pub ends_at: i64,
+
+    /// Number of vote options
+    pub num_options: u8,
+
+    /// How many options a ballot may select
+    pub ballot_rule: BallotRule,
+
+    /// How voters prove eligibility
+    pub eligibility_mode: EligibilityMode,
+
+    /// Whether ballots may be replaced until `ends_at`
+    pub allow_revoting: bool,
+
+    /// Where ballot weights come from
+    pub vote_weighting: VoteWeighting,
+
+    /// Seconds after finalization before rent can be reclaimed
+    pub audit_period: i64,
+
+    /// Most lamports a relayer may be paid per ballot
+    pub max_relayer_fee: u64,
+}
+
+/// Create the private election for an mpl-gov-micro `election`
+///
+/// `election_authority` is the public election's authority and must sign;
+/// `authority` becomes the election admin and doesn't sign; `payer` signs
+/// and pays rent.
+pub fn initialize_private_election(
+    election: &Pubkey,
+    election_authority: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    config: &ElectionConfig,
+) -> Instruction {
+    Instruction {
+        program_id: privacy_layer::ID,
+        accounts: accounts::InitializePrivateElection {
+            private_election: find_private_election(election).0,
+            encrypted_tally: find_encrypted_tally(election).0,
+            gov_config: find_gov_config().0,
+            election: *election,
+            election_authority: *election_authority,
+            authority: *authority,
+            payer: *payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializePrivateElection {
+            mpc_public_key: config.mpc_public_key,
+            voter_merkle_root: config.voter_merkle_root,
+            starts_at: config.starts_at,
+            ends_at: config.ends_at,
+            num_options: config.num_options,
+            ballot_rule: config.ballot_rule,
+            eligibility_mode: config.eligibility_mode,
+            allow_revoting: config.allow_revoting,
+            vote_weighting: config.vote_weighting,
+            audit_period: config.audit_period,
+            max_relayer_fee: config.max_relayer_fee,
+        }
+        .data(),
+    }
+}
+
+/// Options written per `write_ballot_buffer` instruction, each with its
+/// 0/1 proof
+pub const OPTIONS_PER_WRITE: usize = 4;
+
+/// Total proof entries written per `write_ballot_buffer` instruction
+pub const TOTALS_PER_WRITE: usize = 14;
+
+/// Merkle proof siblings written per `write_ballot_buffer` instruction
+pub const SIBLINGS_PER_WRITE: usize = 28;
+
+/// Create the buffer `submitter` writes a prepared ballot to before
+/// casting, relaying or recasting it
+///
+/// `submitter` signs and pays rent until the ballot is submitted.
+/// `merkle_proof` is the voter's inclusion proof in a public election
+/// (`Eligibility::merkle_proof`), which sizes the buffer.
+pub fn create_ballot_buffer(
+    election: &Pubkey,
+    submitter: &Pubkey,
+    nullifier: &[u8; 32],
+    merkle_proof: Option<&MerkleProof>,
+) -> Instruction {
+    let private_election = find_private_election(election).0;
+
+    Instruction {
+        program_id: privacy_layer::ID,
+        accounts: accounts::CreateBallotBuffer {
+            private_election,
+            ballot_buffer: find_ballot_buffer(&private_election, submitter, nullifier).0,
+            submitter: *submitter,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateBallotBuffer {
+            nullifier: *nullifier,
+            tree_depth: merkle_proof.map_or(0, |proof| proof.siblings.len() as u8),
+        }
+        .data(),
+    }
+}
+
+/// Write a prepared ballot to `submitter`'s buffer, created with
+/// `create_ballot_buffer`
+///
+/// One instruction per transaction, sent in order: the options
+/// `OPTIONS_PER_WRITE` at a time, the total proof `TOTALS_PER_WRITE`
+/// entries at a time, then `merkle_proof`, the one the buffer was created
+/// for, `SIBLINGS_PER_WRITE` siblings at a time.
+pub fn write_ballot_buffer(
+    election: &Pubkey,
+    submitter: &Pubkey,
+    ballot: &PreparedBallot,
+    merkle_proof: Option<&MerkleProof>,
+) -> Vec<Instruction> {
+    let private_election = find_private_election(election).0;
+    let metas = accounts::WriteBallotBuffer {
+        private_election,
+        ballot_buffer: find_ballot_buffer(&private_election, submitter, &ballot.nullifier).0,
+        submitter: *submitter,
+    }
+    .to_account_metas(None);
+
+    let options = (0..ballot.ciphertexts.len()).step_by(OPTIONS_PER_WRITE).map(|first| {
+        let end = (first + OPTIONS_PER_WRITE).min(ballot.ciphertexts.len());
+        BallotChunk::Options {
+            first: first as u8,
+            ciphertexts: ballot.ciphertexts[first..end].to_vec(),
+            slot_proofs: ballot.proof.slot_proofs[first..end].to_vec(),
+        }
+    });
+    let totals = (0..ballot.proof.total_challenges.len()).step_by(TOTALS_PER_WRITE).map(|first| {
+        let end = (first + TOTALS_PER_WRITE).min(ballot.proof.total_challenges.len());
+        BallotChunk::TotalProof {
+            first: first as u8,
+            challenges: ballot.proof.total_challenges[first..end].to_vec(),
+            responses: ballot.proof.total_responses[first..end].to_vec(),
+        }
+    });
+
+    let siblings = merkle_proof.into_iter().flat_map(|proof| {
+        proof
+            .siblings
+            .chunks(SIBLINGS_PER_WRITE)
+            .enumerate()
+            .map(|(chunk, siblings)| BallotChunk::MerkleProof {
+                leaf_index: proof.leaf_index,
+                first: (chunk * SIBLINGS_PER_WRITE) as u8,
+                siblings: siblings.to_vec(),
+            })
+    });
+
+    options
+        .chain(totals)
+        .chain(siblings)
+        .map(|chunk| Instruction {
+            program_id: privacy_layer::ID,
+            accounts: metas.clone(),
+            data: instruction::WriteBallotBuffer {
+                nullifier: ballot.nullifier,
+                chunk,
+            }
+            .data(),
+        })
+        .collect()
+}
+
+/// Close `submitter`'s ballot buffer for `nullifier` without submitting
+/// it, refunding its rent
+pub fn close_ballot_buffer(election: &Pubkey, submitter: &Pubkey, nullifier: &[u8; 32]) -> Instruction {
+    let private_election = find_private_election(election).0;
+
+    Instruction {
+        program_id: privacy_layer::ID,
+        accounts: accounts::CloseBallotBuffer {
+            ballot_buffer: find_ballot_buffer(&private_election, submitter, nullifier).0,
+            submitter: *submitter,
+        }
+        .to_account_metas(None),
+        data: instruction::CloseBallotBuffer {}.data(),
+    }
+}
+
+/// Cast a prepared ballot in the private election of `election`
+///
+/// `voter` signs and pays rent, and must have written the ballot to their
+/// buffer with `write_ballot_buffer`, along with their Merkle proof in a
+/// public election. An anonymous proof must be over
+/// `ballot.signal(0, voter, 0)`.
+pub fn cast_encrypted_vote(
+    election: &Pubkey,
+    voter: &Pubkey,
+    ballot: &PreparedBallot,
+    eligibility: &Eligibility,
+) -> Instruction {
+    let private_election = find_private_election(election).0;
+    let (zk_proof_a, zk_proof_b, zk_proof_c) = match eligibility {
+        Eligibility::Anonymous(proof) => (Some(proof.a), Some(proof.b), Some(proof.c)),
+        Eligibility::Public(_) => (None, None, None),
+    };
+
+    Instruction {
+        program_id: privacy_layer::ID,
+        accounts: accounts::CastEncryptedVote {
+            private_election,
+            nullifier_record: find_nullifier_record(&private_election, &ballot.nullifier).0,
+            encrypted_vote: find_encrypted_vote(&private_election, &ballot.nullifier).0,
+            ballot_buffer: find_ballot_buffer(&private_election, voter, &ballot.nullifier).0,
+            encrypted_tally: find_encrypted_tally(election).0,
+            verifying_key: (eligibility.mode() == EligibilityMode::Anonymous)
+                .then(|| find_verifying_key(&private_election).0),
+            voter: *voter,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CastEncryptedVote {
+            nullifier: ballot.nullifier,
+            commitment: ballot.commitment,
+            weight: ballot.weight,
+            zk_proof_a,
+            zk_proof_b,
+            zk_proof_c,
+        }
+        .data(),
+    }
+}
+
+/// Replace a voter's ballot with a prepared one in the private election of
+/// `election`
+///
+/// `voter` signs, and must have written the new ballot to a fresh buffer
+/// with `write_ballot_buffer`, as for `cast_encrypted_vote`. An anonymous
+/// proof must be over
+/// `ballot.signal(revision, voter, 0)`, `revision` being one past the
+/// stored ballot's.
+pub fn recast_encrypted_vote(
+    election: &Pubkey,
+    voter: &Pubkey,
+    ballot: &PreparedBallot,
+    eligibility: &Eligibility,
+) -> Instruction {
+    let private_election = find_private_election(election).0;
+    let (zk_proof_a, zk_proof_b, zk_proof_c) = match eligibility {
+        Eligibility::Anonymous(proof) => (Some(proof.a), Some(proof.b), Some(proof.c)),
+        Eligibility::Public(_) => (None, None, None),
+    };
+
+    Instruction {
+        program_id: privacy_layer::ID,
+        accounts: accounts::RecastEncryptedVote {
+            private_election,
+            encrypted_vote: find_encrypted_vote(&private_election, &ballot.nullifier).0,
+            ballot_buffer: find_ballot_buffer(&private_election, voter, &ballot.nullifier).0,
+            encrypted_tally: find_encrypted_tally(election).0,
+            verifying_key: (eligibility.mode() == EligibilityMode::Anonymous)
+                .then(|| find_verifying_key(&private_election).0),
+            voter: *voter,
+        }
+        .to_account_metas(None),
+        data: instruction::RecastEncryptedVote {
+            nullifier: ballot.nullifier,
+            commitment: ballot.commitment,
+            weight: ballot.weight,
+            zk_proof_a,
+            zk_proof_b,
+            zk_proof_c,
+        }
+        .data(),
+    }
+}
+
+/// Relay an anonymous voter's prepared ballot in the private election of
+/// `election`
+///
+/// `relayer` signs, pays rent and is paid `relayer_fee` from the fee vault,
+/// and must have written the ballot to its buffer with
+/// `write_ballot_buffer`. `proof` must be over `ballot.signal(0, relayer, relayer_fee)`.
+pub fn relay_encrypted_vote(
+    election: &Pubkey,
+    relayer: &Pubkey,
+    ballot: &PreparedBallot,
+    proof: &Groth16Proof,
+    relayer_fee: u64,
+) -> Instruction {
+    let private_election = find_private_election(election).0;
+
+    Instruction {
+        program_id: privacy_layer::ID,
+        accounts: accounts::RelayEncryptedVote {
+            private_election,
+            nullifier_record: find_nullifier_record(&private_election, &ballot.nullifier).0,
+            encrypted_vote: find_encrypted_vote(&private_election, &ballot.nullifier).0,
+            ballot_buffer: find_ballot_buffer(&private_election, relayer, &ballot.nullifier).0,
+            encrypted_tally: find_encrypted_tally(election).0,
+            verifying_key: find_verifying_key(&private_election).0,
+            relayer_fee_vault: find_relayer_fee_vault(&private_election).0,
+            relayer: *relayer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RelayEncryptedVote {
+            nullifier: ballot.nullifier,
+            commitment: ballot.commitment,
+            weight: ballot.weight,
+            zk_proof_a: proof.a,
+            zk_proof_b: proof.b,
+            zk_proof_c: proof.c,
+            relayer_fee,
+        }
+        .data(),
+    }
+}
+
+/// Counts submitted per `submit_tally_result` or `append_tally_result`
+/// instruction, each with its decryption proof
+pub const COUNTS_PER_SUBMIT: usize = 13;
+
+/// Submit the decrypted tally of `election`, one proof per count
+///
+/// `submitter` is the election's tally submitter; it signs and pays rent
+/// for the tally result. One instruction per transaction, sent in order:
+/// `submit_tally_result` with the first `COUNTS_PER_SUBMIT` counts, then
+/// `append_tally_result` with the rest.
+pub fn submit_tally_result(
+    election: &Pubkey,
+    submitter: &Pubkey,
+    counts: &[u64],
+    proofs: &[DecryptionProofArgs],
+) -> Vec<Instruction> {
+    let private_election = find_private_election(election).0;
+    let encrypted_tally = find_encrypted_tally(election).0;
+    let tally_result = find_tally_result(&private_election).0;
+
+    counts
+        .chunks(COUNTS_PER_SUBMIT)
+        .zip(proofs.chunks(COUNTS_PER_SUBMIT))
+        .enumerate()
+        .map(|(index, (counts, proofs))| {
+            let (counts, proofs) = (counts.to_vec(), proofs.to_vec());
+            if index == 0 {
+                Instruction {
+                    program_id: privacy_layer::ID,
+                    accounts: accounts::SubmitTallyResult {
+                        private_election,
+                        encrypted_tally,
+                        tally_result,
+                        submitter: *submitter,
+                        system_program: system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    data: instruction::SubmitTallyResult { counts, proofs }.data(),
+                }
+            } else {
+                Instruction {
+                    program_id: privacy_layer::ID,
+                    accounts: accounts::AppendTallyResult {
+                        private_election,
+                        encrypted_tally,
+                        tally_result,
+                        submitter: *submitter,
+                    }
+                    .to_account_metas(None),
+                    data: instruction::AppendTallyResult { counts, proofs }.data(),
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AnchorDeserialize, Discriminator};
+    use privacy_crypto::MerkleProof;
+    use privacy_layer::instructions::BallotProofArgs;
+    use privacy_layer::state::BallotCiphertext;
+
+    fn config() -> ElectionConfig {
+        ElectionConfig {
+            mpc_public_key: [2; 32],
+            voter_merkle_root: [3; 32],
+            starts_at: 100,
+            ends_at: 200,
+            num_options: 2,
+            ballot_rule: BallotRule::SingleChoice,
+            eligibility_mode: EligibilityMode::Anonymous,
+            allow_revoting: true,
+            vote_weighting: VoteWeighting::Equal,
+            audit_period: 0,
+            max_relayer_fee: 5_000,
+        }
+    }
+
+    fn ballot() -> PreparedBallot {
+        PreparedBallot {
+            nullifier: [4; 32],
+            commitment: [5; 32],
+            ciphertexts: vec![BallotCiphertext::default(); 2],
+            weight: 1,
+            proof: BallotProofArgs {
+                slot_proofs: vec![[6; 128]; 2],
+                total_challenges: vec![[7; 32]],
+                total_responses: vec![[8; 32]],
+            },
+        }
+    }
+
+    #[test]
+    fn test_initialize_accounts_and_data() {
+        let (election, election_authority, authority, payer) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = initialize_private_election(&election, &election_authority, &authority, &payer, &config());
+
+        assert_eq!(ix.program_id, privacy_layer::ID);
+        let keys: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(
+            keys,
+            vec![
+                find_private_election(&election).0,
+                find_encrypted_tally(&election).0,
+                find_gov_config().0,
+                election,
+                election_authority,
+                authority,
+                payer,
+                system_program::ID,
+            ]
+        );
+        let signers: Vec<_> = ix.accounts.iter().filter(|meta| meta.is_signer).map(|meta| meta.pubkey).collect();
+        assert_eq!(signers, vec![election_authority, payer]);
+
+        let (discriminator, args) = ix.data.split_at(8);
+        assert_eq!(discriminator, instruction::InitializePrivateElection::DISCRIMINATOR);
+        let args = instruction::InitializePrivateElection::deserialize(&mut &args[..]).unwrap();
+        assert_eq!(args.mpc_public_key, [2; 32]);
+        assert_eq!((args.starts_at, args.ends_at, args.num_options), (100, 200, 2));
+        assert!(args.allow_revoting);
+        assert_eq!(args.max_relayer_fee, 5_000);
+    }
+
+    #[test]
+    fn test_cast_public_ballot() {
+        let (election, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let private_election = find_private_election(&election).0;
+        let proof = MerkleProof {
+            leaf_index: 3,
+            siblings: vec![[9; 32]; 4],
+        };
+        let ix = cast_encrypted_vote(&election, &voter, &ballot(), &Eligibility::Public(proof));
+
+        assert_eq!(ix.accounts[1].pubkey, find_nullifier_record(&private_election, &[4; 32]).0);
+        assert_eq!(ix.accounts[2].pubkey, find_encrypted_vote(&private_election, &[4; 32]).0);
+        assert_eq!(ix.accounts[3].pubkey, find_ballot_buffer(&private_election, &voter, &[4; 32]).0);
+        assert!(ix.accounts[3].is_writable);
+        // No verifying key: Anchor passes the program ID for a missing
+        // optional account
+        assert_eq!(ix.accounts[5].pubkey, privacy_layer::ID);
+        assert!(ix.accounts[6].is_signer && ix.accounts[6].is_writable);
+
+        // The Merkle proof is in the buffer
+        let args = instruction::CastEncryptedVote::deserialize(&mut &ix.data[8..]).unwrap();
+        assert!(args.zk_proof_a.is_none() && args.zk_proof_b.is_none() && args.zk_proof_c.is_none());
+    }
+
+    #[test]
+    fn test_cast_anonymous_ballot() {
+        let (election, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let eligibility = Eligibility::Anonymous(Box::new(Groth16Proof {
+            a: [1; 64],
+            b: [2; 128],
+            c: [3; 64],
+        }));
+        let ix = cast_encrypted_vote(&election, &voter, &ballot(), &eligibility);
+
+        assert_eq!(
+            ix.accounts[5].pubkey,
+            find_verifying_key(&find_private_election(&election).0).0
+        );
+        let args = instruction::CastEncryptedVote::deserialize(&mut &ix.data[8..]).unwrap();
+        assert_eq!(args.zk_proof_b, Some([2; 128]));
+    }
+
+    #[test]
+    fn test_recast_public_ballot() {
+        let (election, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let private_election = find_private_election(&election).0;
+        let proof = MerkleProof {
+            leaf_index: 3,
+            siblings: vec![[9; 32]; 4],
+        };
+        let ix = recast_encrypted_vote(&election, &voter, &ballot(), &Eligibility::Public(proof));
+
+        assert_eq!(ix.accounts[1].pubkey, find_encrypted_vote(&private_election, &[4; 32]).0);
+        assert!(ix.accounts[1].is_writable);
+        assert_eq!(ix.accounts[2].pubkey, find_ballot_buffer(&private_election, &voter, &[4; 32]).0);
+        assert_eq!(ix.accounts[4].pubkey, privacy_layer::ID);
+        // Refunded the buffer's rent
+        assert!(ix.accounts[5].is_signer && ix.accounts[5].is_writable && ix.accounts[5].pubkey == voter);
+
+        let args = instruction::RecastEncryptedVote::deserialize(&mut &ix.data[8..]).unwrap();
+        assert_eq!(args.commitment, [5; 32]);
+        assert!(args.zk_proof_a.is_none());
+    }
+
+    #[test]
+    fn test_relay_ballot() {
+        let (election, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let private_election = find_private_election(&election).0;
+        let proof = Groth16Proof {
+            a: [1; 64],
+            b: [2; 128],
+            c: [3; 64],
+        };
+        let ix = relay_encrypted_vote(&election, &relayer, &ballot(), &proof, 5_000);
+
+        assert_eq!(ix.accounts[3].pubkey, find_ballot_buffer(&private_election, &relayer, &[4; 32]).0);
+        assert_eq!(ix.accounts[6].pubkey, find_relayer_fee_vault(&private_election).0);
+        assert!(ix.accounts[6].is_writable && !ix.accounts[6].is_signer);
+        assert!(ix.accounts[7].is_signer && ix.accounts[7].pubkey == relayer);
+
+        let args = instruction::RelayEncryptedVote::deserialize(&mut &ix.data[8..]).unwrap();
+        assert_eq!(args.zk_proof_c, [3; 64]);
+        assert_eq!(args.relayer_fee, 5_000);
+    }
+
+    #[test]
+    fn test_ballot_written_in_chunks() {
+        let (election, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let private_election = find_private_election(&election).0;
+        let buffer = find_ballot_buffer(&private_election, &voter, &[4; 32]).0;
+        let mut ballot = ballot();
+        ballot.ciphertexts = vec![BallotCiphertext::default(); OPTIONS_PER_WRITE + 1];
+        ballot.proof.slot_proofs = vec![[6; 128]; OPTIONS_PER_WRITE + 1];
+        ballot.proof.total_challenges = vec![[7; 32]; OPTIONS_PER_WRITE + 2];
+        ballot.proof.total_responses = vec![[8; 32]; OPTIONS_PER_WRITE + 2];
+        let proof = MerkleProof {
+            leaf_index: 3,
+            siblings: vec![[9; 32]; SIBLINGS_PER_WRITE + 1],
+        };
+
+        let create = create_ballot_buffer(&election, &voter, &[4; 32], Some(&proof));
+        assert_eq!(create.accounts[1].pubkey, buffer);
+        assert!(create.accounts[2].is_signer && create.accounts[2].is_writable);
+        let args = instruction::CreateBallotBuffer::deserialize(&mut &create.data[8..]).unwrap();
+        assert_eq!(args.tree_depth as usize, SIBLINGS_PER_WRITE + 1);
+
+        let writes = write_ballot_buffer(&election, &voter, &ballot, Some(&proof));
+        let chunks: Vec<_> = writes
+            .iter()
+            .map(|ix| {
+                assert_eq!(ix.accounts[1].pubkey, buffer);
+                let args = instruction::WriteBallotBuffer::deserialize(&mut &ix.data[8..]).unwrap();
+                assert_eq!(args.nullifier, [4; 32]);
+                match args.chunk {
+                    BallotChunk::Options { first, ciphertexts, slot_proofs } => {
+                        assert_eq!(ciphertexts.len(), slot_proofs.len());
+                        ("options", first, ciphertexts.len())
+                    }
+                    BallotChunk::TotalProof { first, challenges, responses } => {
+                        assert_eq!(challenges.len(), responses.len());
+                        ("totals", first, challenges.len())
+                    }
+                    BallotChunk::MerkleProof { leaf_index, first, siblings } => {
+                        assert_eq!(leaf_index, 3);
+                        ("siblings", first, siblings.len())
+                    }
+                }
+            })
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                ("options", 0, OPTIONS_PER_WRITE),
+                ("options", OPTIONS_PER_WRITE as u8, 1),
+                ("totals", 0, OPTIONS_PER_WRITE + 2),
+                ("siblings", 0, SIBLINGS_PER_WRITE),
+                ("siblings", SIBLINGS_PER_WRITE as u8, 1),
+            ]
+        );
+
+        let close = close_ballot_buffer(&election, &voter, &[4; 32]);
+        assert_eq!(close.accounts[0].pubkey, buffer);
+    }
+
+    #[test]
+    fn test_tally_submitted_in_chunks() {
+        let (election, submitter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let counts: Vec<u64> = (0..COUNTS_PER_SUBMIT as u64 + 3).collect();
+        let proofs = vec![
+            DecryptionProofArgs {
+                challenge: [1; 32],
+                response: [2; 32],
+            };
+            counts.len()
+        ];
+        let ixs = submit_tally_result(&election, &submitter, &counts, &proofs);
+        assert_eq!(ixs.len(), 2);
+
+        let first = instruction::SubmitTallyResult::deserialize(&mut &ixs[0].data[8..]).unwrap();
+        assert_eq!(&ixs[0].data[..8], instruction::SubmitTallyResult::DISCRIMINATOR);
+        assert_eq!(first.counts, counts[..COUNTS_PER_SUBMIT]);
+        // Only the first pays for the tally result
+        assert!(ixs[0].accounts[3].is_writable);
+
+        let rest = instruction::AppendTallyResult::deserialize(&mut &ixs[1].data[8..]).unwrap();
+        assert_eq!(&ixs[1].data[..8], instruction::AppendTallyResult::DISCRIMINATOR);
+        assert_eq!(rest.counts, counts[COUNTS_PER_SUBMIT..]);
+        assert_eq!(rest.proofs.len(), 3);
+        assert_eq!(ixs[1].accounts[2].pubkey, find_tally_result(&find_private_election(&election).0).0);
+        assert!(ixs[1].accounts[3].is_signer && !ixs[1].accounts[3].is_writable);
+    }
+}
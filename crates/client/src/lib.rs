@@ -0,0 +1,33 @@
+//! Rust client for the privacy layer program
+//!
+//! Everything a backend needs to talk to the program without a network
+//! round trip of its own:
+//! - PDA derivation for every program account
+//! - Instruction builders from typed arguments
+//! - Decoding of every account type, current layouts only
+//! - End-to-end ballot preparation with `privacy-crypto`
+//!
+//! Sending transactions and fetching accounts is left to whichever RPC
+//! client the caller already uses.
+
+pub mod pda;
+pub mod instructions;
+pub mod accounts;
+pub mod ballot;
+pub mod errors;
+
+pub use accounts::{BallotBufferAccount, DecodeAccount, EncryptedTallyAccount};
+pub use ballot::{
+    anonymous_nullifier, eligibility_leaf, prepare_ballot, public_nullifier, Eligibility,
+    PreparedBallot,
+};
+pub use errors::ClientError;
+pub use instructions::{
+    cast_encrypted_vote, close_ballot_buffer, create_ballot_buffer, initialize_private_election,
+    recast_encrypted_vote, relay_encrypted_vote, submit_tally_result, write_ballot_buffer,
+    ElectionConfig,
+};
+pub use privacy_layer::ID as PROGRAM_ID;
+
+/// Result type for client operations
+pub type Result<T> = core::result::Result<T, ClientError>;
@@ -0,0 +1,117 @@
+//! Program-derived addresses
+//!
+//! Seeds mirror the `seeds` constraints in the program's instructions.
+//! Each function returns the address and its canonical bump.
+
+use anchor_lang::prelude::Pubkey;
+use privacy_layer::state::NullifierRecord;
+
+/// `PrivateElection` shadowing an mpl-gov-micro `election`
+pub fn find_private_election(election: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"private_election", election.as_ref()], &privacy_layer::ID)
+}
+
+/// `EncryptedTally` of an mpl-gov-micro `election`
+pub fn find_encrypted_tally(election: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"encrypted_tally", election.as_ref()], &privacy_layer::ID)
+}
+
+/// `NullifierRecord` marking `nullifier` as spent
+///
+/// One record per nullifier rather than a shared set: the record existing
+/// is what makes a second ballot with the same nullifier fail.
+pub fn find_nullifier_record(private_election: &Pubkey, nullifier: &[u8; 32]) -> (Pubkey, u8) {
+    NullifierRecord::find_address(private_election, nullifier)
+}
+
+/// Baseline `NullifierSet` of an mpl-gov-micro `election`
+///
+/// Only found on elections created before nullifier records;
+/// `migrate_nullifier_set` empties it into records and closes it.
+pub fn find_nullifier_set(election: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"nullifier_set", election.as_ref()], &privacy_layer::ID)
+}
+
+/// `EncryptedVote` stored under `nullifier`
+pub fn find_encrypted_vote(private_election: &Pubkey, nullifier: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"encrypted_vote", private_election.as_ref(), nullifier.as_ref()],
+        &privacy_layer::ID,
+    )
+}
+
+/// `BallotBuffer` `submitter` writes the ballot of `nullifier` to
+pub fn find_ballot_buffer(private_election: &Pubkey, submitter: &Pubkey, nullifier: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"ballot_buffer",
+            private_election.as_ref(),
+            submitter.as_ref(),
+            nullifier.as_ref(),
+        ],
+        &privacy_layer::ID,
+    )
+}
+
+/// `EligibilityVerifyingKey` of an anonymous election
+pub fn find_verifying_key(private_election: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"verifying_key", private_election.as_ref()], &privacy_layer::ID)
+}
+
+/// `TallyResult` of a finalized election
+pub fn find_tally_result(private_election: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tally_result", private_election.as_ref()], &privacy_layer::ID)
+}
+
+/// `ElectionResults` left behind by `close_election_accounts`
+pub fn find_election_results(election: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"election_results", election.as_ref()], &privacy_layer::ID)
+}
+
+/// System account relayer fees are paid from
+pub fn find_relayer_fee_vault(private_election: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"relayer_fee_vault", private_election.as_ref()], &privacy_layer::ID)
+}
+
+/// The program's `GovConfig` singleton
+pub fn find_gov_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"gov_config"], &privacy_layer::ID)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses_are_scoped() {
+        let election = Pubkey::new_unique();
+        let (private_election, _) = find_private_election(&election);
+
+        assert_eq!(find_private_election(&election).0, private_election);
+        assert_ne!(find_private_election(&Pubkey::new_unique()).0, private_election);
+        // Same seed input, different account types
+        assert_ne!(find_encrypted_tally(&election).0, private_election);
+        assert_ne!(
+            find_encrypted_vote(&private_election, &[1; 32]).0,
+            find_nullifier_record(&private_election, &[1; 32]).0
+        );
+        assert_ne!(
+            find_encrypted_vote(&private_election, &[1; 32]).0,
+            find_encrypted_vote(&private_election, &[2; 32]).0
+        );
+    }
+
+    #[test]
+    fn test_bumps_are_canonical() {
+        let election = Pubkey::new_unique();
+        let (private_election, bump) = find_private_election(&election);
+
+        assert_eq!(
+            Pubkey::create_program_address(
+                &[b"private_election", election.as_ref(), &[bump]],
+                &privacy_layer::ID
+            ),
+            Ok(private_election)
+        );
+    }
+}
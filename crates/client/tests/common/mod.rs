@@ -0,0 +1,374 @@
+//! Mollusk harness shared by the tests that run the built program
+//!
+//! These need the SBF build of the program, so they are ignored by
+//! default. Build it first and point mollusk at it:
+//!
+//! ```text
+//! anchor build
+//! SBF_OUT_DIR=target/deploy cargo test -p privacy-layer-client -- --ignored --nocapture
+//! ```
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::instruction::error::InstructionError;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, AccountSerialize, Event, InstructionData, ToAccountMetas};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use mollusk_svm::{Mollusk, MolluskContext};
+use privacy_crypto::merkle::MAX_MERKLE_DEPTH;
+use privacy_crypto::{DiscreteLog, ElGamalKeypair, ElGamalSecretKey, KeccakHasher, MerkleProof, MerkleTree};
+use privacy_layer::errors::PrivacyError;
+use privacy_layer::gov::ELECTION_DISCRIMINATOR;
+use privacy_layer::groth16::{eligibility_public_inputs, test_prover::TestProver};
+use privacy_layer::instructions::DecryptionProofArgs;
+use privacy_layer::state::{
+    BallotRule, EligibilityMode, GovConfig, PrivateElection, VoteWeighting,
+};
+use privacy_layer_client::pda::*;
+use privacy_layer_client::{
+    anonymous_nullifier, cast_encrypted_vote, create_ballot_buffer, initialize_private_election,
+    prepare_ballot, public_nullifier, recast_encrypted_vote, relay_encrypted_vote,
+    submit_tally_result, write_ballot_buffer, DecodeAccount, Eligibility, ElectionConfig,
+    EncryptedTallyAccount, PreparedBallot, PROGRAM_ID,
+};
+use solana_account::Account;
+use solana_log_collector::LogCollector;
+
+/// Cluster time at the start of every run
+pub const NOW: i64 = 1_700_000_000;
+
+/// Compute units a transaction may request
+pub const MAX_COMPUTE_UNITS: u64 = 1_400_000;
+
+/// Depth of the voter tree: the deepest there is, so every public cast
+/// checks the longest Merkle proof
+pub const TREE_DEPTH: usize = MAX_MERKLE_DEPTH;
+
+/// An equal-weight election opened for voting, with funded voters
+pub struct Bench {
+    pub context: MolluskContext<HashMap<Pubkey, Account>>,
+    pub election: Pubkey,
+    pub private_election: Pubkey,
+    pub authority: Pubkey,
+    pub state: PrivateElection,
+    pub voters: Vec<Pubkey>,
+    pub tree: MerkleTree<KeccakHasher>,
+}
+
+impl Bench {
+    /// A public election
+    pub fn new(num_options: u8, ballot_rule: BallotRule, num_voters: usize) -> Self {
+        Self::setup(num_options, ballot_rule, num_voters, EligibilityMode::Public, 0, None)
+    }
+
+    /// An anonymous single choice election between two options, proving
+    /// eligibility with `prover`
+    pub fn anonymous(num_voters: usize, max_relayer_fee: u64, prover: &TestProver) -> Self {
+        let mode = EligibilityMode::Anonymous;
+        Self::setup(2, BallotRule::SingleChoice, num_voters, mode, max_relayer_fee, Some(prover))
+    }
+
+    fn setup(
+        num_options: u8,
+        ballot_rule: BallotRule,
+        num_voters: usize,
+        eligibility_mode: EligibilityMode,
+        max_relayer_fee: u64,
+        prover: Option<&TestProver>,
+    ) -> Self {
+        let mut mollusk = Mollusk::new(&PROGRAM_ID, "privacy_layer");
+        mollusk.compute_budget.compute_unit_limit = MAX_COMPUTE_UNITS;
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+        mollusk.logger = Some(LogCollector::new_ref_with_limit(None));
+
+        let gov_program = Pubkey::new_unique();
+        let election = Pubkey::new_unique();
+        let election_authority = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let voters: Vec<Pubkey> = (0..num_voters).map(|_| Pubkey::new_unique()).collect();
+
+        let mut store = HashMap::new();
+        let (gov_config, bump) = find_gov_config();
+        store.insert(
+            gov_config,
+            program_account(&GovConfig {
+                version: GovConfig::VERSION,
+                bump,
+                gov_program,
+            }),
+        );
+        let mut election_data = ELECTION_DISCRIMINATOR.to_vec();
+        election_data.extend_from_slice(election_authority.as_ref());
+        election_data.extend_from_slice(&[0u8; 64]);
+        store.insert(election, account(election_data, &gov_program));
+        for wallet in voters.iter().chain([&authority]) {
+            store.insert(*wallet, Account::new(100_000_000_000, 0, &system_program::ID));
+        }
+
+        let mut tree = MerkleTree::new(TREE_DEPTH).unwrap();
+        for voter in &voters {
+            tree.append(voter.to_bytes()).unwrap();
+        }
+
+        let mut context = mollusk.with_context(store);
+        let config = ElectionConfig {
+            mpc_public_key: keypair().public.point,
+            voter_merkle_root: tree.root(),
+            starts_at: NOW + 10,
+            ends_at: NOW + 86_400,
+            num_options,
+            ballot_rule,
+            eligibility_mode,
+            allow_revoting: true,
+            vote_weighting: VoteWeighting::Equal,
+            audit_period: 0,
+            max_relayer_fee,
+        };
+        let init = initialize_private_election(&election, &election_authority, &authority, &authority, &config);
+        assert!(context.process_instruction(&init).raw_result.is_ok());
+
+        let private_election = find_private_election(&election).0;
+        if let Some(prover) = prover {
+            let key = prover.verifying_key();
+            let set_key = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: privacy_layer::accounts::SetVerifyingKey {
+                    private_election,
+                    verifying_key: find_verifying_key(&private_election).0,
+                    authority,
+                    payer: authority,
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: privacy_layer::instruction::SetVerifyingKey {
+                    alpha_g1: key.alpha_g1,
+                    beta_g2: key.beta_g2,
+                    gamma_g2: key.gamma_g2,
+                    delta_g2: key.delta_g2,
+                    ic: key.ic.to_vec(),
+                }
+                .data(),
+            };
+            assert!(context.process_instruction(&set_key).raw_result.is_ok());
+        }
+
+        let open = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: privacy_layer::accounts::OpenElection {
+                private_election,
+                authority,
+            }
+            .to_account_metas(None),
+            data: privacy_layer::instruction::OpenElection { starts_at: NOW + 10 }.data(),
+        };
+        assert!(context.process_instruction(&open).raw_result.is_ok());
+
+        let state = PrivateElection::decode(&context.account_store.borrow()[&private_election].data).unwrap();
+        assert_eq!(state.election_id, PrivateElection::derive_election_id(&private_election));
+        context.mollusk.sysvars.clock.unix_timestamp = NOW + 10;
+
+        Bench {
+            context,
+            election,
+            private_election,
+            authority,
+            state,
+            voters,
+            tree,
+        }
+    }
+
+    pub fn load_election(&self) -> PrivateElection {
+        let store = self.context.account_store.borrow();
+        PrivateElection::decode(&store[&self.private_election].data).unwrap()
+    }
+
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.context.account_store.borrow().get(address).map_or(0, |account| account.lamports)
+    }
+
+    /// Set the balance of system account `address`
+    pub fn fund(&self, address: &Pubkey, lamports: u64) {
+        let account = Account::new(lamports, 0, &system_program::ID);
+        self.context.account_store.borrow_mut().insert(*address, account);
+    }
+
+    /// A fresh ballot of voter `index` for `choices`
+    pub fn ballot(&self, index: usize, choices: &[u64]) -> PreparedBallot {
+        let nullifier = match self.state.eligibility_mode {
+            EligibilityMode::Public => public_nullifier(&self.state, &self.voters[index]),
+            EligibilityMode::Anonymous => anonymous_nullifier(&self.state, &voter_secret(index)),
+        };
+        prepare_ballot(&self.private_election, &self.state, nullifier, choices, 1, &mut rand::thread_rng())
+            .unwrap()
+    }
+
+    /// Write `ballot` and `merkle_proof` to a new buffer of `submitter`,
+    /// unless it already has one for the ballot's nullifier; returns the
+    /// compute units of the costliest write
+    pub fn write_ballot(
+        &self,
+        submitter: &Pubkey,
+        ballot: &PreparedBallot,
+        merkle_proof: Option<&MerkleProof>,
+    ) -> u64 {
+        let buffer = find_ballot_buffer(&self.private_election, submitter, &ballot.nullifier).0;
+        if self.lamports(&buffer) > 0 {
+            return 0;
+        }
+
+        let create = create_ballot_buffer(&self.election, submitter, &ballot.nullifier, merkle_proof);
+        assert!(self.context.process_instruction(&create).raw_result.is_ok());
+        write_ballot_buffer(&self.election, submitter, ballot, merkle_proof)
+            .iter()
+            .map(|ix| {
+                let result = self.context.process_instruction(ix);
+                assert!(result.raw_result.is_ok(), "write failed: {:?}", result.raw_result);
+                result.compute_units_consumed
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Cast voter `index`'s ballot and return the compute units it used
+    ///
+    /// The ballot is written to the voter's buffer first.
+    pub fn cast(&self, index: usize, ballot: &PreparedBallot) -> u64 {
+        let eligibility = Eligibility::Public(self.tree.proof(index as u32).unwrap());
+        self.write_ballot(&self.voters[index], ballot, eligibility.merkle_proof());
+        let ix = cast_encrypted_vote(&self.election, &self.voters[index], ballot, &eligibility);
+        let result = self.context.process_instruction(&ix);
+        assert!(result.raw_result.is_ok(), "cast failed: {:?}", result.raw_result);
+        result.compute_units_consumed
+    }
+
+    /// Replace voter `index`'s ballot and return the compute units it used
+    pub fn recast(&self, index: usize, ballot: &PreparedBallot) -> u64 {
+        let eligibility = Eligibility::Public(self.tree.proof(index as u32).unwrap());
+        self.write_ballot(&self.voters[index], ballot, eligibility.merkle_proof());
+        let ix = recast_encrypted_vote(&self.election, &self.voters[index], ballot, &eligibility);
+        let result = self.context.process_instruction(&ix);
+        assert!(result.raw_result.is_ok(), "recast failed: {:?}", result.raw_result);
+        result.compute_units_consumed
+    }
+
+    /// Relay an anonymous ballot from `relayer` for `relayer_fee`, with an
+    /// eligibility proof from `prover`
+    pub fn relay(
+        &self,
+        prover: &TestProver,
+        relayer: &Pubkey,
+        ballot: &PreparedBallot,
+        relayer_fee: u64,
+    ) -> Result<(), InstructionError> {
+        let public_inputs = eligibility_public_inputs(
+            &self.state.voter_merkle_root,
+            &ballot.nullifier,
+            &self.state.election_id,
+            &ballot.signal(0, relayer, relayer_fee),
+            ballot.weight,
+        )
+        .unwrap();
+        let proof = prover.prove(&public_inputs);
+        self.write_ballot(relayer, ballot, None);
+        let ix = relay_encrypted_vote(&self.election, relayer, ballot, &proof, relayer_fee);
+        self.context.process_instruction(&ix).raw_result
+    }
+
+    /// Close the election and submit its decrypted tally; returns the
+    /// counts submitted
+    pub fn finalize(&self) -> Vec<u64> {
+        let close = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: privacy_layer::accounts::CloseElection {
+                private_election: self.private_election,
+                closer: self.authority,
+            }
+            .to_account_metas(None),
+            data: privacy_layer::instruction::CloseElection {}.data(),
+        };
+        let request = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: privacy_layer::accounts::RequestTally {
+                private_election: self.private_election,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: privacy_layer::instruction::RequestTally {}.data(),
+        };
+        for ix in [close, request] {
+            assert!(self.context.process_instruction(&ix).raw_result.is_ok());
+        }
+
+        let tally = {
+            let store = self.context.account_store.borrow();
+            EncryptedTallyAccount::decode(&store[&find_encrypted_tally(&self.election).0].data).unwrap()
+        };
+        let table = DiscreteLog::new(16);
+        let (counts, proofs): (Vec<u64>, Vec<DecryptionProofArgs>) = tally
+            .aggregate
+            .iter()
+            .map(|&ciphertext| {
+                let (count, proof) = keypair()
+                    .secret
+                    .decrypt_with_proof(&ciphertext.into(), &table, &rand::random())
+                    .unwrap();
+                (count, DecryptionProofArgs { challenge: proof.challenge, response: proof.response })
+            })
+            .unzip();
+
+        for ix in submit_tally_result(&self.election, &self.authority, &counts, &proofs) {
+            let result = self.context.process_instruction(&ix);
+            assert!(result.raw_result.is_ok(), "submit failed: {:?}", result.raw_result);
+        }
+        counts
+    }
+
+    /// Every `T` event the program has emitted so far, in order
+    pub fn events<T: Event>(&self) -> Vec<T> {
+        let logger = self.context.mollusk.logger.as_ref().unwrap().borrow();
+        logger
+            .get_recorded_content()
+            .iter()
+            .filter_map(|line| line.strip_prefix("Program data: "))
+            .map(|data| BASE64_STANDARD.decode(data).unwrap())
+            .filter(|data| data.starts_with(T::DISCRIMINATOR))
+            .map(|data| T::try_from_slice(&data[8..]).unwrap())
+            .collect()
+    }
+}
+
+/// Secret of voter `index` in an anonymous election
+pub fn voter_secret(index: usize) -> [u8; 32] {
+    let mut secret = [0u8; 32];
+    secret[..8].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+    secret
+}
+
+/// The instruction error `error` fails with
+pub fn program_error(error: PrivacyError) -> InstructionError {
+    InstructionError::Custom(anchor_lang::error::ERROR_CODE_OFFSET + error as u32)
+}
+
+pub fn keypair() -> ElGamalKeypair {
+    ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]))
+}
+
+pub fn account(data: Vec<u8>, owner: &Pubkey) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+pub fn program_account<T: AccountSerialize>(contents: &T) -> Account {
+    let mut data = Vec::new();
+    contents.try_serialize(&mut data).unwrap();
+    account(data, &PROGRAM_ID)
+}
@@ -0,0 +1,84 @@
+//! Compute unit benchmarks against the built program
+//!
+//! Ignored by default; see `common` for how to run them.
+
+mod common;
+
+use common::*;
+use privacy_layer::state::{BallotRule, PrivateElection, MAX_OPTIONS};
+
+/// Single choice between two options
+fn choice() -> [u64; 2] {
+    [1, 0]
+}
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn bench_cast_at_different_record_counts() {
+    // Voter 0 casts after 0, 10 and 100 others; only its own nullifier
+    // record is looked up, so the cost mustn't move with the count
+    let mut costs = Vec::new();
+    for records in [0, 10, 100] {
+        let bench = Bench::new(2, BallotRule::SingleChoice, 101);
+        for index in 1..=records {
+            bench.cast(index, &bench.ballot(index, &choice()));
+        }
+        assert_eq!(bench.load_election().total_encrypted_votes, records as u64);
+
+        let units = bench.cast(0, &bench.ballot(0, &choice()));
+        println!("cast with {records} existing nullifier records: {units} CU");
+        costs.push(units);
+    }
+
+    // Ballot encryptions differ between runs, so allow for the small
+    // variation in point decoding
+    let baseline = costs[0];
+    for units in &costs {
+        assert!(units.abs_diff(baseline) <= baseline / 100, "{costs:?}");
+    }
+}
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn bench_max_option_cast_and_recast() {
+    // Every accepted ballot shape at MAX_OPTIONS: each option adds a 0/1
+    // proof and each allowed total a branch of the total proof, and the
+    // cast adds every option to the tally
+    let rules = (1..=MAX_OPTIONS)
+        .map(|max_choices| BallotRule::Approval { max_choices })
+        .chain([BallotRule::SingleChoice])
+        .filter(|&rule| PrivateElection::validate_options(MAX_OPTIONS, rule).is_ok());
+
+    for rule in rules {
+        let bench = Bench::new(MAX_OPTIONS, rule, 1);
+        let voter = bench.voters[0];
+        let mut choices = vec![0; MAX_OPTIONS as usize];
+        choices[0] = 1;
+
+        // Proofs are checked as the ballot is written, a few options at a
+        // time; casting checks the voter's Merkle proof, as deep as trees
+        // go, and adds the checked ballot to the tally
+        let merkle_proof = bench.tree.proof(0).unwrap();
+        let ballot = bench.ballot(0, &choices);
+        let write = bench.write_ballot(&voter, &ballot, Some(&merkle_proof));
+        let cast = bench.cast(0, &ballot);
+        choices.rotate_right(1);
+        let ballot = bench.ballot(0, &choices);
+        let rewrite = bench.write_ballot(&voter, &ballot, Some(&merkle_proof));
+        let recast = bench.recast(0, &ballot);
+        let rule = match rule {
+            BallotRule::SingleChoice => "single choice".to_string(),
+            BallotRule::Approval { max_choices } => format!("approval of up to {max_choices}"),
+        };
+        println!(
+            "{MAX_OPTIONS} options, {rule}: largest write {} CU, cast {cast} CU, recast {recast} CU",
+            write.max(rewrite)
+        );
+
+        // Mollusk meters against the limit too; this names the ballot that
+        // broke it
+        for units in [write, cast, rewrite, recast] {
+            assert!(units <= MAX_COMPUTE_UNITS, "{rule}: {units} CU");
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Events emitted by the built program
+//!
+//! Ignored by default; see `common` for how to run them.
+
+mod common;
+
+use common::*;
+use privacy_layer::events::{EncryptedVoteCast, TallyFinalized};
+use privacy_layer::state::BallotRule;
+use privacy_layer_client::pda::find_private_election;
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn test_encrypted_vote_cast_events() {
+    let bench = Bench::new(2, BallotRule::SingleChoice, 3);
+    let ballots: Vec<_> = (0..3).map(|index| bench.ballot(index, &[1, 0])).collect();
+    for (index, ballot) in ballots.iter().enumerate() {
+        bench.cast(index, ballot);
+    }
+
+    let events = bench.events::<EncryptedVoteCast>();
+    assert_eq!(events.len(), ballots.len());
+    for (vote_index, (event, ballot)) in events.iter().zip(&ballots).enumerate() {
+        assert_eq!(event.private_election, find_private_election(&bench.election).0);
+        assert_eq!(event.nullifier, ballot.nullifier);
+        assert!(event.ciphertexts == ballot.ciphertexts);
+        assert_eq!(event.commitment, ballot.commitment);
+        assert_eq!(event.weight, 1);
+        assert_eq!(event.vote_index, vote_index as u64);
+        assert_eq!(event.timestamp, NOW + 10);
+    }
+}
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn test_tally_finalized_event() {
+    let bench = Bench::new(2, BallotRule::SingleChoice, 3);
+    for (index, choices) in [[1, 0], [0, 1], [1, 0]].iter().enumerate() {
+        bench.cast(index, &bench.ballot(index, choices));
+    }
+    let counts = bench.finalize();
+    assert_eq!(counts, vec![2, 1]);
+
+    let events = bench.events::<TallyFinalized>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].private_election, bench.private_election);
+    assert_eq!(events[0].counts, counts);
+    assert_eq!(events[0].submitted_by, bench.authority);
+    assert_eq!(events[0].finalized_at, NOW + 10);
+}
@@ -0,0 +1,147 @@
+//! Upgrading accounts written by the baseline program
+//!
+//! Ignored by default; see `common` for how to run them.
+
+mod common;
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use mollusk_svm::Mollusk;
+use privacy_layer::errors::PrivacyError;
+use privacy_layer::state::{ElectionStatus, NullifierRecord, PrivateElection};
+use privacy_layer_client::pda::*;
+use privacy_layer_client::{DecodeAccount, PROGRAM_ID};
+use solana_account::Account;
+
+macro_rules! fixture {
+    ($name:literal) => {
+        include_bytes!(concat!("../../../test-vectors/accounts/v1/", $name, ".bin")).to_vec()
+    };
+}
+
+/// The fixture's mpl-gov-micro election and authority
+const ELECTION: Pubkey = Pubkey::new_from_array([5; 32]);
+const AUTHORITY: Pubkey = Pubkey::new_from_array([1; 32]);
+
+/// A v1 fixture moved to its PDA, with the bump to match
+fn at_pda(mut data: Vec<u8>, bump: u8) -> Account {
+    data[8] = bump;
+    account(data, &PROGRAM_ID)
+}
+
+/// The v1 election fixture, ended so that it can be migrated
+fn ended_election() -> Vec<u8> {
+    let mut data = fixture!("private_election");
+    *data.last_mut().unwrap() = ElectionStatus::Ended as u8;
+    data
+}
+
+fn migrate_account(account: Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: privacy_layer::accounts::MigrateAccount {
+            account,
+            payer: AUTHORITY,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: privacy_layer::instruction::MigrateAccount {}.data(),
+    }
+}
+
+fn migrate_nullifier_set(private_election: Pubkey, nullifier: [u8; 32]) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: privacy_layer::accounts::MigrateNullifierSet {
+            private_election,
+            nullifier_set: find_nullifier_set(&ELECTION).0,
+            nullifier_record: find_nullifier_record(&private_election, &nullifier).0,
+            authority: AUTHORITY,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: privacy_layer::instruction::MigrateNullifierSet { nullifier }.data(),
+    }
+}
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn test_migrate_baseline_election() {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "privacy_layer");
+    mollusk.sysvars.clock.unix_timestamp = NOW;
+
+    let (private_election, election_bump) = find_private_election(&ELECTION);
+    let (nullifier_set, set_bump) = find_nullifier_set(&ELECTION);
+    let (encrypted_vote, vote_bump) = find_encrypted_vote(&private_election, &[10; 32]);
+    let mut vote = fixture!("encrypted_vote");
+    vote[9..41].copy_from_slice(private_election.as_ref());
+
+    let mut store = HashMap::new();
+    store.insert(private_election, at_pda(ended_election(), election_bump));
+    store.insert(nullifier_set, at_pda(fixture!("nullifier_set"), set_bump));
+    store.insert(encrypted_vote, at_pda(vote, vote_bump));
+    store.insert(AUTHORITY, Account::new(10_000_000_000, 0, &system_program::ID));
+    let context = mollusk.with_context(store);
+    let lamports = |address: &Pubkey| context.account_store.borrow().get(address).map_or(0, |a| a.lamports);
+    let data = |address: &Pubkey| context.account_store.borrow()[address].data.clone();
+
+    // The set can't be split before the election is readable
+    let early = context.process_instruction(&migrate_nullifier_set(private_election, [10; 32]));
+    assert!(early.raw_result.is_err());
+
+    assert!(context.process_instruction(&migrate_account(private_election)).raw_result.is_ok());
+    // A second pass is a no-op
+    assert!(context.process_instruction(&migrate_account(private_election)).raw_result.is_ok());
+    let election = PrivateElection::decode(&data(&private_election)).unwrap();
+    assert_eq!(election.open_vote_accounts, 3);
+    assert_eq!(data(&private_election).len(), PrivateElection::LEN);
+
+    // A v1 vote has no per-option layout to move to
+    let vote_before = data(&encrypted_vote);
+    let result = context.process_instruction(&migrate_account(encrypted_vote));
+    assert_eq!(result.raw_result, Err(program_error(PrivacyError::InvalidBallotSize)));
+    assert_eq!(data(&encrypted_vote), vote_before);
+
+    // Nullifiers move one by one; the set closes with the last
+    let set_rent = lamports(&nullifier_set);
+    let authority_before = lamports(&AUTHORITY);
+    for nullifier in [[10; 32], [11; 32], [12; 32]] {
+        let result = context.process_instruction(&migrate_nullifier_set(private_election, nullifier));
+        assert!(result.raw_result.is_ok(), "{:?}", result.raw_result);
+
+        let record = find_nullifier_record(&private_election, &nullifier).0;
+        let record = NullifierRecord::decode(&data(&record)).unwrap();
+        assert_eq!(record.election, private_election);
+        assert_eq!(record.rent_payer, AUTHORITY);
+    }
+    assert_eq!(lamports(&nullifier_set), 0);
+    let record_rent = Rent::default().minimum_balance(NullifierRecord::LEN);
+    assert_eq!(lamports(&AUTHORITY), authority_before + set_rent - 3 * record_rent);
+
+    // Only nullifiers that were in the set get a record
+    let stray = context.process_instruction(&migrate_nullifier_set(private_election, [13; 32]));
+    assert!(stray.raw_result.is_err());
+}
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn test_nullifier_set_keeps_unknown_nullifiers_out() {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "privacy_layer");
+    mollusk.sysvars.clock.unix_timestamp = NOW;
+
+    let (private_election, election_bump) = find_private_election(&ELECTION);
+    let (nullifier_set, set_bump) = find_nullifier_set(&ELECTION);
+    let mut store = HashMap::new();
+    store.insert(private_election, at_pda(ended_election(), election_bump));
+    store.insert(nullifier_set, at_pda(fixture!("nullifier_set"), set_bump));
+    store.insert(AUTHORITY, Account::new(10_000_000_000, 0, &system_program::ID));
+    let context = mollusk.with_context(store);
+    assert!(context.process_instruction(&migrate_account(private_election)).raw_result.is_ok());
+
+    let result = context.process_instruction(&migrate_nullifier_set(private_election, [13; 32]));
+    assert_eq!(result.raw_result, Err(program_error(PrivacyError::InvalidNullifier)));
+}
@@ -0,0 +1,64 @@
+//! Relayed ballots against the built program
+//!
+//! Ignored by default; see `common` for how to run them.
+
+mod common;
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use common::*;
+use privacy_layer::errors::PrivacyError;
+use privacy_layer::groth16::test_prover::TestProver;
+use privacy_layer::state::EncryptedVote;
+use privacy_layer_client::pda::*;
+use privacy_layer_client::DecodeAccount;
+
+const RELAYER_FEE: u64 = 5_000;
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn test_relayer_paid_from_fee_vault() {
+    let prover = TestProver::default();
+    let bench = Bench::anonymous(2, 10_000, &prover);
+    let vault = find_relayer_fee_vault(&bench.private_election).0;
+    let minimum = Rent::default().minimum_balance(0);
+    bench.fund(&vault, minimum + RELAYER_FEE);
+    let relayer = Pubkey::new_unique();
+    bench.fund(&relayer, 1_000_000_000);
+
+    let ballot = bench.ballot(0, &[1, 0]);
+    assert_eq!(bench.relay(&prover, &relayer, &ballot, RELAYER_FEE), Ok(()));
+
+    // The relayer got the fee and paid rent for the vote accounts
+    let nullifier_record = find_nullifier_record(&bench.private_election, &ballot.nullifier).0;
+    let encrypted_vote = find_encrypted_vote(&bench.private_election, &ballot.nullifier).0;
+    let rent = bench.lamports(&nullifier_record) + bench.lamports(&encrypted_vote);
+    assert_eq!(bench.lamports(&vault), minimum);
+    assert_eq!(bench.lamports(&relayer), 1_000_000_000 + RELAYER_FEE - rent);
+
+    let vote = EncryptedVote::decode(&bench.context.account_store.borrow()[&encrypted_vote].data).unwrap();
+    assert_eq!(vote.commitment, ballot.commitment);
+    assert_eq!(bench.load_election().total_encrypted_votes, 1);
+}
+
+#[test]
+#[ignore = "needs the SBF build of the program"]
+fn test_fee_vault_stays_rent_exempt() {
+    let prover = TestProver::default();
+    let bench = Bench::anonymous(2, 10_000, &prover);
+    let vault = find_relayer_fee_vault(&bench.private_election).0;
+    let minimum = Rent::default().minimum_balance(0);
+    bench.fund(&vault, minimum + RELAYER_FEE - 1);
+    let relayer = Pubkey::new_unique();
+    bench.fund(&relayer, 1_000_000_000);
+
+    let ballot = bench.ballot(0, &[1, 0]);
+    assert_eq!(
+        bench.relay(&prover, &relayer, &ballot, RELAYER_FEE),
+        Err(program_error(PrivacyError::RelayerFeeVaultUnderfunded))
+    );
+    assert_eq!(bench.lamports(&vault), minimum + RELAYER_FEE - 1);
+
+    // A relayer working for free still gets the ballot in
+    assert_eq!(bench.relay(&prover, &relayer, &ballot, 0), Ok(()));
+    assert_eq!(bench.lamports(&vault), minimum + RELAYER_FEE - 1);
+}
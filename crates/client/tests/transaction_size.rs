@@ -0,0 +1,218 @@
+//! Largest transactions against the packet size limit
+//!
+//! A ballot and a public voter's Merkle proof are written to its buffer a
+//! few entries at a time and a tally is submitted a few counts at a time,
+//! so no transaction grows with `MAX_OPTIONS` or the voter tree's depth.
+//! These tests serialize real transactions to check every one fits.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{pubkey, system_program, InstructionData};
+use privacy_crypto::merkle::MAX_MERKLE_DEPTH;
+use privacy_crypto::MerkleProof;
+use privacy_layer::groth16::Groth16Proof;
+use privacy_layer::instructions::{BallotChunk, BallotProofArgs, DecryptionProofArgs};
+use privacy_layer::state::{
+    BallotCiphertext, BallotRule, PrivateElection, MAX_OPTIONS, MAX_TRANSACTION_SIZE, MIN_OPTIONS,
+};
+use privacy_layer_client::instructions::{
+    COUNTS_PER_SUBMIT, OPTIONS_PER_WRITE, SIBLINGS_PER_WRITE, TOTALS_PER_WRITE,
+};
+use privacy_layer_client::pda::*;
+use privacy_layer_client::{
+    cast_encrypted_vote, recast_encrypted_vote, relay_encrypted_vote, submit_tally_result,
+    write_ballot_buffer, Eligibility, PreparedBallot,
+};
+use solana_message::{v0, AddressLookupTableAccount, VersionedMessage};
+
+const COMPUTE_BUDGET_ID: Pubkey = pubkey!("ComputeBudget111111111111111111111111111111");
+
+/// `SetComputeUnitLimit(1_400_000)`, which every ballot needs
+fn compute_unit_limit() -> Instruction {
+    let mut data = vec![2];
+    data.extend_from_slice(&1_400_000u32.to_le_bytes());
+    Instruction::new_with_bytes(COMPUTE_BUDGET_ID, &data, vec![])
+}
+
+/// A ballot of the largest size `rule` allows with `num_options` options
+fn ballot(num_options: u8, rule: BallotRule) -> PreparedBallot {
+    PreparedBallot {
+        nullifier: [1; 32],
+        commitment: [2; 32],
+        ciphertexts: vec![BallotCiphertext::default(); num_options as usize],
+        weight: u64::MAX,
+        proof: BallotProofArgs {
+            slot_proofs: vec![[3; 128]; num_options as usize],
+            total_challenges: vec![[4; 32]; rule.num_totals()],
+            total_responses: vec![[5; 32]; rule.num_totals()],
+        },
+    }
+}
+
+fn groth16_proof() -> Groth16Proof {
+    Groth16Proof {
+        a: [6; 64],
+        b: [7; 128],
+        c: [8; 64],
+    }
+}
+
+fn public(depth: usize) -> Eligibility {
+    Eligibility::Public(MerkleProof {
+        leaf_index: u32::MAX,
+        siblings: vec![[9; 32]; depth],
+    })
+}
+
+/// Every ballot rule `initialize_private_election` accepts
+fn accepted_ballots() -> impl Iterator<Item = (u8, BallotRule)> {
+    (MIN_OPTIONS..=MAX_OPTIONS).flat_map(|num_options| {
+        (1..=num_options)
+            .map(|max_choices| BallotRule::Approval { max_choices })
+            .chain([BallotRule::SingleChoice])
+            .filter(move |&rule| PrivateElection::validate_options(num_options, rule).is_ok())
+            .map(move |rule| (num_options, rule))
+    })
+}
+
+/// Serialized size of a v0 transaction from `signer` with the election's
+/// accounts in a lookup table
+fn transaction_size(election: &Pubkey, signer: &Pubkey, instruction: Instruction) -> usize {
+    let private_election = find_private_election(election).0;
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: vec![
+            private_election,
+            find_encrypted_tally(election).0,
+            find_verifying_key(&private_election).0,
+            find_relayer_fee_vault(&private_election).0,
+            find_tally_result(&private_election).0,
+            system_program::ID,
+        ],
+    };
+    let message = v0::Message::try_compile(
+        signer,
+        &[compute_unit_limit(), instruction],
+        &[table],
+        Default::default(),
+    )
+    .unwrap();
+    let message = VersionedMessage::V0(message);
+
+    let signatures = message.header().num_required_signatures as usize;
+    1 + 64 * signatures + message.serialize().len()
+}
+
+/// Sizes of the transactions writing a ballot to its buffer, with a
+/// Merkle proof from the deepest voter tree
+fn write_sizes(num_options: u8, rule: BallotRule) -> Vec<usize> {
+    let (election, submitter) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let eligibility = public(MAX_MERKLE_DEPTH);
+    write_ballot_buffer(&election, &submitter, &ballot(num_options, rule), eligibility.merkle_proof())
+        .into_iter()
+        .map(|ix| transaction_size(&election, &submitter, ix))
+        .collect()
+}
+
+fn relay_size() -> usize {
+    let (election, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ix = relay_encrypted_vote(&election, &relayer, &ballot(2, BallotRule::SingleChoice), &groth16_proof(), u64::MAX);
+    transaction_size(&election, &relayer, ix)
+}
+
+fn cast_size(eligibility: &Eligibility) -> usize {
+    let (election, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ix = cast_encrypted_vote(&election, &voter, &ballot(2, BallotRule::SingleChoice), eligibility);
+    transaction_size(&election, &voter, ix)
+}
+
+fn recast_size(eligibility: &Eligibility) -> usize {
+    let (election, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ix = recast_encrypted_vote(&election, &voter, &ballot(2, BallotRule::SingleChoice), eligibility);
+    transaction_size(&election, &voter, ix)
+}
+
+#[test]
+fn test_ballot_writes_fit() {
+    for (num_options, rule) in accepted_ballots() {
+        for size in write_sizes(num_options, rule) {
+            assert!(size <= MAX_TRANSACTION_SIZE, "{num_options} options: {size}");
+        }
+    }
+}
+
+#[test]
+fn test_writes_are_as_large_as_fit() {
+    let (election, submitter) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let write = |chunk: BallotChunk| {
+        let mut ix = write_ballot_buffer(&election, &submitter, &ballot(1, BallotRule::SingleChoice), None).remove(0);
+        ix.data = privacy_layer::instruction::WriteBallotBuffer {
+            nullifier: [1; 32],
+            chunk,
+        }
+        .data();
+        transaction_size(&election, &submitter, ix)
+    };
+    let options = |count: usize| BallotChunk::Options {
+        first: 0,
+        ciphertexts: vec![BallotCiphertext::default(); count],
+        slot_proofs: vec![[3; 128]; count],
+    };
+    let totals = |count: usize| BallotChunk::TotalProof {
+        first: 0,
+        challenges: vec![[4; 32]; count],
+        responses: vec![[5; 32]; count],
+    };
+    let siblings = |count: usize| BallotChunk::MerkleProof {
+        leaf_index: u32::MAX,
+        first: 0,
+        siblings: vec![[9; 32]; count],
+    };
+
+    assert!(write(options(OPTIONS_PER_WRITE)) <= MAX_TRANSACTION_SIZE);
+    assert!(write(options(OPTIONS_PER_WRITE + 1)) > MAX_TRANSACTION_SIZE);
+    assert!(write(totals(TOTALS_PER_WRITE)) <= MAX_TRANSACTION_SIZE);
+    assert!(write(totals(TOTALS_PER_WRITE + 1)) > MAX_TRANSACTION_SIZE);
+    assert!(write(siblings(SIBLINGS_PER_WRITE)) <= MAX_TRANSACTION_SIZE);
+    assert!(write(siblings(SIBLINGS_PER_WRITE + 1)) > MAX_TRANSACTION_SIZE);
+}
+
+#[test]
+fn test_submitted_ballots_fit() {
+    // The ballot and any Merkle proof are in the buffer, so the rest of a
+    // vote is the same size whatever the election's options or voter tree
+    assert!(relay_size() <= MAX_TRANSACTION_SIZE);
+    for eligibility in [Eligibility::Anonymous(Box::new(groth16_proof())), public(MAX_MERKLE_DEPTH)] {
+        assert!(cast_size(&eligibility) <= relay_size());
+        assert!(recast_size(&eligibility) <= cast_size(&eligibility));
+    }
+    assert_eq!(cast_size(&public(0)), cast_size(&public(MAX_MERKLE_DEPTH)));
+}
+
+#[test]
+fn test_tally_submissions_fit() {
+    let (election, submitter) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let num_options = MAX_OPTIONS as usize;
+    let proofs = vec![
+        DecryptionProofArgs {
+            challenge: [1; 32],
+            response: [2; 32],
+        };
+        num_options
+    ];
+
+    let ixs = submit_tally_result(&election, &submitter, &vec![u64::MAX; num_options], &proofs);
+    assert_eq!(ixs.len(), num_options.div_ceil(COUNTS_PER_SUBMIT));
+    for ix in ixs {
+        assert!(transaction_size(&election, &submitter, ix) <= MAX_TRANSACTION_SIZE);
+    }
+
+    // As many counts as fit
+    let mut ix = submit_tally_result(&election, &submitter, &[0], &proofs[..1]).remove(0);
+    ix.data = privacy_layer::instruction::SubmitTallyResult {
+        counts: vec![u64::MAX; COUNTS_PER_SUBMIT + 1],
+        proofs: proofs[..1].repeat(COUNTS_PER_SUBMIT + 1),
+    }
+    .data();
+    assert!(transaction_size(&election, &submitter, ix) > MAX_TRANSACTION_SIZE);
+}
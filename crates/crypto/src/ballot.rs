@@ -0,0 +1,362 @@
+//! Multi-option encrypted ballots
+//!
+//! A ballot holds one ciphertext per option. Each slot encrypts 0 or 1 and
+//! the homomorphic sum of the slots encrypts the number of options chosen,
+//! so per-option tallies are simply the slot-wise sum of all ballots.
+
+use alloc::vec::Vec;
+use curve25519_dalek::scalar::Scalar;
+use serde::{Deserialize, Serialize};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::elgamal::{ElGamalCiphertext, ElGamalPublicKey};
+use crate::proofs::{BinaryProof, MembershipProof};
+use crate::{CryptoError, Result};
+
+/// How many options a single ballot may select
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub enum BallotRule {
+    /// Exactly one option (one-hot)
+    SingleChoice,
+    /// Between zero and `max_choices` options
+    Approval { max_choices: u8 },
+}
+
+impl BallotRule {
+    /// Plaintext values the sum of all slots may take
+    pub fn allowed_totals(&self) -> Vec<u64> {
+        match *self {
+            BallotRule::SingleChoice => alloc::vec![1],
+            BallotRule::Approval { max_choices } => (0..=max_choices as u64).collect(),
+        }
+    }
+}
+
+/// One ElGamal ciphertext per option
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct EncryptedBallot {
+    pub ciphertexts: Vec<ElGamalCiphertext>,
+}
+
+impl EncryptedBallot {
+    /// Number of options on the ballot
+    pub fn num_options(&self) -> usize {
+        self.ciphertexts.len()
+    }
+
+    /// Slot-wise homomorphic addition of two ballots
+    pub fn add(&self, other: &EncryptedBallot) -> Result<EncryptedBallot> {
+        if self.num_options() != other.num_options() {
+            return Err(CryptoError::InvalidCiphertext);
+        }
+
+        let ciphertexts = self
+            .ciphertexts
+            .iter()
+            .zip(&other.ciphertexts)
+            .map(|(a, b)| a.add(b))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(EncryptedBallot { ciphertexts })
+    }
+
+    /// Homomorphic sum of every slot (encrypts the number of choices made)
+    pub fn total(&self) -> Result<ElGamalCiphertext> {
+        let (first, rest) = self
+            .ciphertexts
+            .split_first()
+            .ok_or(CryptoError::InvalidCiphertext)?;
+
+        rest.iter().try_fold(*first, |acc, ct| acc.add(ct))
+    }
+}
+
+/// Validity proof for an `EncryptedBallot`
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BallotProof {
+    /// Proof that each slot encrypts 0 or 1
+    pub slot_proofs: Vec<BinaryProof>,
+    /// Proof that the slot total is allowed by the ballot rule
+    pub total_proof: MembershipProof,
+}
+
+impl BallotProof {
+    /// Verify a ballot against the election key and rule
+    pub fn verify(
+        &self,
+        public_key: &ElGamalPublicKey,
+        ballot: &EncryptedBallot,
+        rule: BallotRule,
+        context: &[u8],
+    ) -> Result<()> {
+        if ballot.num_options() == 0 || self.slot_proofs.len() != ballot.num_options() {
+            return Err(CryptoError::InvalidProof);
+        }
+
+        for (slot, (ciphertext, proof)) in ballot.ciphertexts.iter().zip(&self.slot_proofs).enumerate() {
+            verify_slot_proof(public_key, ciphertext, proof, slot, context)?;
+        }
+
+        verify_total_proof(public_key, ballot, &self.total_proof, rule, context)
+    }
+}
+
+/// Verify that the ciphertext in `slot` of a ballot encrypts 0 or 1
+///
+/// One part of `BallotProof::verify`, for checking a ballot a few slots
+/// at a time.
+pub fn verify_slot_proof(
+    public_key: &ElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    proof: &BinaryProof,
+    slot: usize,
+    context: &[u8],
+) -> Result<()> {
+    proof.verify(public_key, ciphertext, &slot_context(context, slot))
+}
+
+/// Verify that the slot total of `ballot` is allowed by `rule`
+///
+/// The rest of `BallotProof::verify` once every slot has been checked.
+pub fn verify_total_proof(
+    public_key: &ElGamalPublicKey,
+    ballot: &EncryptedBallot,
+    proof: &MembershipProof,
+    rule: BallotRule,
+    context: &[u8],
+) -> Result<()> {
+    proof.verify(public_key, &ballot.total()?, &rule.allowed_totals(), context)
+}
+
+/// Encrypt a ballot and prove it follows `rule`
+///
+/// `choices` holds 0 or 1 per option. `randomness` holds one 32-byte
+/// value per option and should be generated off-chain for security.
+pub fn encrypt_ballot(
+    public_key: &ElGamalPublicKey,
+    choices: &[u64],
+    randomness: &[[u8; 32]],
+    proof_randomness: &[u8; 32],
+    rule: BallotRule,
+    context: &[u8],
+) -> Result<(EncryptedBallot, BallotProof)> {
+    if choices.is_empty() || choices.len() != randomness.len() {
+        return Err(CryptoError::InvalidCiphertext);
+    }
+
+    let mut ciphertexts = Vec::with_capacity(choices.len());
+    let mut slot_proofs = Vec::with_capacity(choices.len());
+    for (slot, (&choice, r)) in choices.iter().zip(randomness).enumerate() {
+        let ciphertext = public_key.encrypt_with_randomness(choice, r)?;
+        slot_proofs.push(BinaryProof::prove(
+            public_key,
+            &ciphertext,
+            choice,
+            r,
+            proof_randomness,
+            &slot_context(context, slot),
+        )?);
+        ciphertexts.push(ciphertext);
+    }
+    let ballot = EncryptedBallot { ciphertexts };
+
+    // The total is encrypted under the sum of the slot randomness
+    let total_randomness = randomness
+        .iter()
+        .map(|r| Scalar::from_bytes_mod_order(*r))
+        .sum::<Scalar>()
+        .to_bytes();
+    let total: u64 = choices.iter().sum();
+    let total_proof = MembershipProof::prove(
+        public_key,
+        &ballot.total()?,
+        &rule.allowed_totals(),
+        total,
+        &total_randomness,
+        proof_randomness,
+        context,
+    )?;
+
+    Ok((
+        ballot,
+        BallotProof {
+            slot_proofs,
+            total_proof,
+        },
+    ))
+}
+
+fn slot_context(context: &[u8], slot: usize) -> Vec<u8> {
+    let mut slot_context = Vec::with_capacity(context.len() + 8);
+    slot_context.extend_from_slice(context);
+    slot_context.extend_from_slice(&(slot as u64).to_le_bytes());
+    slot_context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elgamal::ElGamalKeypair;
+    use rand::{thread_rng, Rng};
+
+    const CONTEXT: &[u8] = b"election-1/nullifier-1";
+
+    fn randomness(n: usize) -> Vec<[u8; 32]> {
+        let mut rng = thread_rng();
+        (0..n).map(|_| rng.gen()).collect()
+    }
+
+    #[test]
+    fn test_single_choice_ballot() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let (ballot, proof) = encrypt_ballot(
+            &keypair.public,
+            &[0, 1, 0],
+            &randomness(3),
+            &rng.gen(),
+            BallotRule::SingleChoice,
+            CONTEXT,
+        )
+        .unwrap();
+
+        assert!(proof
+            .verify(&keypair.public, &ballot, BallotRule::SingleChoice, CONTEXT)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_single_choice_rejects_empty_or_double_vote() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        for choices in [[0u64, 0, 0], [1, 1, 0]] {
+            assert!(encrypt_ballot(
+                &keypair.public,
+                &choices,
+                &randomness(3),
+                &rng.gen(),
+                BallotRule::SingleChoice,
+                CONTEXT,
+            )
+            .is_err());
+        }
+
+        // A valid approval ballot can't be passed off as single choice
+        let (ballot, proof) = encrypt_ballot(
+            &keypair.public,
+            &[1, 1, 0],
+            &randomness(3),
+            &rng.gen(),
+            BallotRule::Approval { max_choices: 2 },
+            CONTEXT,
+        )
+        .unwrap();
+        assert!(proof
+            .verify(&keypair.public, &ballot, BallotRule::SingleChoice, CONTEXT)
+            .is_err());
+    }
+
+    #[test]
+    fn test_approval_ballot() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let rule = BallotRule::Approval { max_choices: 2 };
+
+        for choices in [[0u64, 0, 0, 0], [1, 0, 0, 1]] {
+            let (ballot, proof) =
+                encrypt_ballot(&keypair.public, &choices, &randomness(4), &rng.gen(), rule, CONTEXT)
+                    .unwrap();
+            assert!(proof.verify(&keypair.public, &ballot, rule, CONTEXT).is_ok());
+        }
+
+        assert!(encrypt_ballot(
+            &keypair.public,
+            &[1, 1, 1, 0],
+            &randomness(4),
+            &rng.gen(),
+            rule,
+            CONTEXT,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_slot_swap_detected() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let (mut ballot, proof) = encrypt_ballot(
+            &keypair.public,
+            &[1, 0],
+            &randomness(2),
+            &rng.gen(),
+            BallotRule::SingleChoice,
+            CONTEXT,
+        )
+        .unwrap();
+        ballot.ciphertexts.swap(0, 1);
+
+        assert!(proof
+            .verify(&keypair.public, &ballot, BallotRule::SingleChoice, CONTEXT)
+            .is_err());
+    }
+
+    #[test]
+    fn test_slots_verify_one_at_a_time() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let rule = BallotRule::SingleChoice;
+
+        let (ballot, proof) =
+            encrypt_ballot(&keypair.public, &[0, 1, 0], &randomness(3), &rng.gen(), rule, CONTEXT).unwrap();
+
+        for (slot, (ciphertext, slot_proof)) in ballot.ciphertexts.iter().zip(&proof.slot_proofs).enumerate() {
+            assert!(verify_slot_proof(&keypair.public, ciphertext, slot_proof, slot, CONTEXT).is_ok());
+            // Each proof is bound to its slot
+            assert!(verify_slot_proof(&keypair.public, ciphertext, slot_proof, slot + 1, CONTEXT).is_err());
+        }
+        assert!(verify_total_proof(&keypair.public, &ballot, &proof.total_proof, rule, CONTEXT).is_ok());
+        assert!(verify_total_proof(
+            &keypair.public,
+            &ballot,
+            &proof.total_proof,
+            BallotRule::Approval { max_choices: 1 },
+            CONTEXT
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_per_option_tally() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let votes = [[1u64, 0, 0], [0, 0, 1], [1, 0, 0]];
+        let mut tally: Option<EncryptedBallot> = None;
+        for choices in votes {
+            let (ballot, _) = encrypt_ballot(
+                &keypair.public,
+                &choices,
+                &randomness(3),
+                &rng.gen(),
+                BallotRule::SingleChoice,
+                CONTEXT,
+            )
+            .unwrap();
+            tally = Some(match tally {
+                Some(t) => t.add(&ballot).unwrap(),
+                None => ballot,
+            });
+        }
+
+        let counts: Vec<u64> = tally
+            .unwrap()
+            .ciphertexts
+            .iter()
+            .map(|ct| keypair.secret.decrypt_with_bound(ct, 16).unwrap())
+            .collect();
+        assert_eq!(counts, [2, 0, 1]);
+    }
+}
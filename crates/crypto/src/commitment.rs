@@ -1,18 +1,222 @@
+//! Pedersen commitments on Ristretto
+//!
+//! `C = v*G + r*H`, where `G` is the Ristretto basepoint and `H` is hashed
+//! to the group from a fixed label, so nobody knows `log_G(H)`.
+//! Commitments are perfectly hiding, computationally binding and additively
+//! homomorphic. `CommitmentEqualityProof` links a commitment to an
+//! `ElGamalCiphertext` by proving both hide the same value.
+
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_TABLE,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+};
+use serde::{Deserialize, Serialize};
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use sha2::Sha512;
 use sha3::{Digest, Keccak256};
 
-/// Create a Pedersen-style commitment to a vote
+use crate::dleq::canonical_scalar;
+use crate::elgamal::{ElGamalCiphertext, ElGamalPublicKey};
+use crate::transcript::Transcript;
+use crate::{CryptoError, Result};
+
+/// Label hashed to the group to derive the second generator `H`
+pub const PEDERSEN_H_LABEL: &[u8] = b"privacy-crypto/pedersen-generator-H/v1";
+
+/// Domain separator for `commit_ballot`
+pub const BALLOT_COMMITMENT_DOMAIN: &[u8] = b"privacy-crypto/ballot-commitment/v1";
+
+/// Domain separator for `ballot_signal`
+pub const BALLOT_SIGNAL_DOMAIN: &[u8] = b"privacy-crypto/ballot-signal/v1";
+
+/// Domain separator for `commit_aggregate`
+pub const AGGREGATE_COMMITMENT_DOMAIN: &[u8] = b"privacy-crypto/aggregate-commitment/v1";
+
+/// Serialized size of a `CommitmentEqualityProof`
+pub const COMMITMENT_EQUALITY_PROOF_LEN: usize = 128;
+
+/// The second Pedersen generator `H`
 ///
-/// Commitment = H(vote || blinding_factor)
+/// Derived as `from_uniform_bytes(SHA-512(PEDERSEN_H_LABEL))`, so its
+/// discrete log relative to `G` is unknown.
+pub fn pedersen_h() -> RistrettoPoint {
+    let hash: [u8; 64] = Sha512::digest(PEDERSEN_H_LABEL).into();
+    RistrettoPoint::from_uniform_bytes(&hash)
+}
+
+/// Pedersen commitment `C = v*G + r*H`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct PedersenCommitment {
+    /// Compressed commitment point (32 bytes)
+    pub point: [u8; 32],
+}
+
+impl PedersenCommitment {
+    /// Commit to `value` with a 32-byte blinding factor
+    ///
+    /// The blinding factor should be generated off-chain for security.
+    pub fn commit(value: u64, blinding: &[u8; 32]) -> Self {
+        Self::from_point(&commit_point(&Scalar::from(value), &Scalar::from_bytes_mod_order(*blinding)))
+    }
+
+    /// Wrap an existing compressed commitment, checking it decodes
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self> {
+        let commitment = Self { point: bytes };
+        commitment.as_point()?;
+        Ok(commitment)
+    }
+
+    /// Decompress the commitment point
+    pub fn as_point(&self) -> Result<RistrettoPoint> {
+        CompressedRistretto(self.point)
+            .decompress()
+            .ok_or(CryptoError::InvalidCommitmentInput)
+    }
+
+    /// Check that this commitment opens to `value` with `blinding`
+    pub fn verify_opening(&self, value: u64, blinding: &[u8; 32]) -> bool {
+        *self == Self::commit(value, blinding)
+    }
+
+    /// Homomorphic addition: commits to `v1 + v2` with blinding `r1 + r2`
+    pub fn add(&self, other: &PedersenCommitment) -> Result<PedersenCommitment> {
+        Ok(Self::from_point(&(self.as_point()? + other.as_point()?)))
+    }
+
+    /// Homomorphic subtraction: commits to `v1 - v2` with blinding `r1 - r2`
+    pub fn sub(&self, other: &PedersenCommitment) -> Result<PedersenCommitment> {
+        Ok(Self::from_point(&(self.as_point()? - other.as_point()?)))
+    }
+
+    fn from_point(point: &RistrettoPoint) -> Self {
+        Self {
+            point: point.compress().to_bytes(),
+        }
+    }
+}
+
+/// Proof that a Pedersen commitment and an ElGamal ciphertext hide the
+/// same value
 ///
-/// This allows proving knowledge of a vote without revealing it
-pub fn commit_vote(vote: u8, blinding_factor: &[u8; 32]) -> [u8; 32] {
-    let mut hasher = Keccak256::new();
+/// For `C = v*G + s*H` and `(C1, C2) = (r*G, v*G + r*Y)` this is a sigma
+/// protocol for knowledge of `(v, s, r)` satisfying all three equations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[repr(C)]
+pub struct CommitmentEqualityProof {
+    /// Fiat-Shamir challenge (32 bytes)
+    pub challenge: [u8; 32],
+    /// Response for the value (32 bytes)
+    pub response_value: [u8; 32],
+    /// Response for the commitment blinding factor (32 bytes)
+    pub response_blinding: [u8; 32],
+    /// Response for the encryption randomness (32 bytes)
+    pub response_randomness: [u8; 32],
+}
 
-    hasher.update(&[vote]);
-    hasher.update(blinding_factor);
+unsafe impl Pod for CommitmentEqualityProof {}
+unsafe impl Zeroable for CommitmentEqualityProof {}
 
-    let result = hasher.finalize();
-    result.into()
+impl CommitmentEqualityProof {
+    /// Prove that `PedersenCommitment::commit(value, blinding)` and
+    /// `ciphertext` hide the same value
+    ///
+    /// `randomness` must be the value passed to `encrypt_with_randomness`.
+    /// `context` binds the proof to where it will be used.
+    pub fn prove(
+        public_key: &ElGamalPublicKey,
+        ciphertext: &ElGamalCiphertext,
+        value: u64,
+        blinding: &[u8; 32],
+        randomness: &[u8; 32],
+        proof_randomness: &[u8; 32],
+        context: &[u8],
+    ) -> Result<Self> {
+        let y = public_key.as_point()?;
+        let v = Scalar::from(value);
+        let s = Scalar::from_bytes_mod_order(*blinding);
+        let r = Scalar::from_bytes_mod_order(*randomness);
+
+        let commitment = PedersenCommitment::from_point(&commit_point(&v, &s));
+        if public_key.encrypt_with_randomness(value, randomness)? != *ciphertext {
+            return Err(CryptoError::InvalidCiphertext);
+        }
+
+        let mut transcript = equality_transcript(public_key, &commitment, ciphertext, context);
+        let mut witness = [0u8; 96];
+        witness[..32].copy_from_slice(v.as_bytes());
+        witness[32..64].copy_from_slice(s.as_bytes());
+        witness[64..].copy_from_slice(r.as_bytes());
+        let k_v = transcript.witness_scalar(b"nonce-value", &witness, proof_randomness);
+        let k_s = transcript.witness_scalar(b"nonce-blinding", &witness, proof_randomness);
+        let k_r = transcript.witness_scalar(b"nonce-randomness", &witness, proof_randomness);
+
+        let t_commitment = commit_point(&k_v, &k_s);
+        let t_c1 = &k_r * RISTRETTO_BASEPOINT_TABLE;
+        let t_c2 = &k_v * RISTRETTO_BASEPOINT_TABLE + k_r * y;
+        append_nonces(&mut transcript, &t_commitment, &t_c1, &t_c2);
+
+        let c = transcript.challenge_scalar(b"equality-challenge");
+
+        Ok(Self {
+            challenge: c.to_bytes(),
+            response_value: (k_v + c * v).to_bytes(),
+            response_blinding: (k_s + c * s).to_bytes(),
+            response_randomness: (k_r + c * r).to_bytes(),
+        })
+    }
+
+    /// Verify that `commitment` and `ciphertext` hide the same value
+    pub fn verify(
+        &self,
+        public_key: &ElGamalPublicKey,
+        commitment: &PedersenCommitment,
+        ciphertext: &ElGamalCiphertext,
+        context: &[u8],
+    ) -> Result<()> {
+        let y = public_key.as_point()?;
+        let big_c = commitment.as_point()?;
+        let (c1, c2) = ciphertext.decompress()?;
+
+        let c = canonical_scalar(&self.challenge)?;
+        let z_v = canonical_scalar(&self.response_value)?;
+        let z_s = canonical_scalar(&self.response_blinding)?;
+        let z_r = canonical_scalar(&self.response_randomness)?;
+
+        // T = z*Base - c*Statement for each of the three equations
+        let t_commitment = commit_point(&z_v, &z_s) - c * big_c;
+        let t_c1 = &z_r * RISTRETTO_BASEPOINT_TABLE - c * c1;
+        let t_c2 = &z_v * RISTRETTO_BASEPOINT_TABLE + z_r * y - c * c2;
+
+        let mut transcript = equality_transcript(public_key, commitment, ciphertext, context);
+        append_nonces(&mut transcript, &t_commitment, &t_c1, &t_c2);
+
+        if transcript.challenge_scalar(b"equality-challenge") == c {
+            Ok(())
+        } else {
+            Err(CryptoError::InvalidProof)
+        }
+    }
+
+    /// Serialize to 128 bytes
+    pub fn to_bytes(&self) -> [u8; COMMITMENT_EQUALITY_PROOF_LEN] {
+        let mut bytes = [0u8; COMMITMENT_EQUALITY_PROOF_LEN];
+        bytes.copy_from_slice(bytemuck::bytes_of(self));
+        bytes
+    }
+
+    /// Deserialize from 128 bytes
+    pub fn from_bytes(bytes: &[u8; COMMITMENT_EQUALITY_PROOF_LEN]) -> Self {
+        bytemuck::pod_read_unaligned(bytes)
+    }
+}
+
+/// Create a Pedersen commitment to a vote
+///
+/// Commitment = vote*G + blinding*H, compressed
+pub fn commit_vote(vote: u8, blinding_factor: &[u8; 32]) -> [u8; 32] {
+    PedersenCommitment::commit(vote as u64, blinding_factor).point
 }
 
 /// Verify a vote commitment
@@ -39,9 +243,111 @@ pub fn commit_encrypted_vote(
     result.into()
 }
 
+/// Commitment binding a cast ballot to its election and nullifier
+///
+/// `Keccak256(domain || election_id || nullifier || c1_0 || c2_0 || ...)`.
+/// Every input is already public on-chain, so no blinding factor is
+/// needed; the commitment is a compact receipt a voter can check their
+/// stored ballot against, and it doesn't reveal or prove how they voted.
+pub fn commit_ballot(
+    election_id: &[u8; 32],
+    nullifier: &[u8; 32],
+    ciphertexts: &[ElGamalCiphertext],
+) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+
+    hasher.update(BALLOT_COMMITMENT_DOMAIN);
+    hasher.update(election_id);
+    hasher.update(nullifier);
+    hasher.update((ciphertexts.len() as u32).to_le_bytes());
+    for ciphertext in ciphertexts {
+        hasher.update(ciphertext.c1);
+        hasher.update(ciphertext.c2);
+    }
+
+    hasher.finalize().into()
+}
+
+/// Commitment to an election's aggregate ciphertexts, one per option
+///
+/// `Keccak256(domain || len || c1_0 || c2_0 || ...)` with `len` as u32 LE.
+/// Lets a published tally name the exact aggregate it was decrypted from.
+pub fn commit_aggregate(aggregate: &[ElGamalCiphertext]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+
+    hasher.update(AGGREGATE_COMMITMENT_DOMAIN);
+    hasher.update((aggregate.len() as u32).to_le_bytes());
+    for ciphertext in aggregate {
+        hasher.update(ciphertext.c1);
+        hasher.update(ciphertext.c2);
+    }
+
+    hasher.finalize().into()
+}
+
+/// External signal a zero-knowledge eligibility proof is bound to
+///
+/// `Keccak256(domain || commitment || revision || submitter || fee)` with
+/// `revision` as u32 LE and `fee` as u64 LE. Binding the proof to the
+/// ballot commitment stops it being reused with other ciphertexts; binding
+/// it to the revision stops an old submission being replayed over a newer
+/// one when re-voting is allowed. Binding the submitter and fee means a
+/// relayer can't be front-run or paid more than the voter agreed to.
+pub fn ballot_signal(
+    commitment: &[u8; 32],
+    revision: u32,
+    submitter: &[u8; 32],
+    fee: u64,
+) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+
+    hasher.update(BALLOT_SIGNAL_DOMAIN);
+    hasher.update(commitment);
+    hasher.update(revision.to_le_bytes());
+    hasher.update(submitter);
+    hasher.update(fee.to_le_bytes());
+
+    hasher.finalize().into()
+}
+
+fn commit_point(value: &Scalar, blinding: &Scalar) -> RistrettoPoint {
+    value * RISTRETTO_BASEPOINT_TABLE + blinding * pedersen_h()
+}
+
+fn equality_transcript(
+    public_key: &ElGamalPublicKey,
+    commitment: &PedersenCommitment,
+    ciphertext: &ElGamalCiphertext,
+    context: &[u8],
+) -> Transcript {
+    let mut transcript = Transcript::new(b"pedersen-elgamal-equality-proof");
+    transcript.append_message(b"context", context);
+    transcript.append_message(b"public-key", &public_key.point);
+    transcript.append_message(b"commitment", &commitment.point);
+    transcript.append_message(b"c1", &ciphertext.c1);
+    transcript.append_message(b"c2", &ciphertext.c2);
+    transcript
+}
+
+fn append_nonces(
+    transcript: &mut Transcript,
+    t_commitment: &RistrettoPoint,
+    t_c1: &RistrettoPoint,
+    t_c2: &RistrettoPoint,
+) {
+    transcript.append_point(b"T-commitment", &t_commitment.compress());
+    transcript.append_point(b"T-c1", &t_c1.compress());
+    transcript.append_point(b"T-c2", &t_c2.compress());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::elgamal::ElGamalKeypair;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+    use rand::{thread_rng, Rng};
+
+    const CONTEXT: &[u8] = b"election-1/nullifier-1";
 
     #[test]
     fn test_commitment_deterministic() {
@@ -96,4 +402,159 @@ mod tests {
 
         assert_eq!(commitment1, commitment2);
     }
+
+    #[test]
+    fn test_ballot_commitment_binds_every_input() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let ballot: Vec<ElGamalCiphertext> = (0..3)
+            .map(|i| keypair.public.encrypt_with_randomness(i % 2, &rng.gen()).unwrap())
+            .collect();
+        let commitment = commit_ballot(&[1u8; 32], &[2u8; 32], &ballot);
+
+        assert_eq!(commitment, commit_ballot(&[1u8; 32], &[2u8; 32], &ballot));
+        assert_ne!(commitment, commit_ballot(&[9u8; 32], &[2u8; 32], &ballot));
+        assert_ne!(commitment, commit_ballot(&[1u8; 32], &[9u8; 32], &ballot));
+        assert_ne!(commitment, commit_ballot(&[1u8; 32], &[2u8; 32], &ballot[..2]));
+
+        let mut swapped = ballot.clone();
+        swapped.swap(0, 1);
+        assert_ne!(commitment, commit_ballot(&[1u8; 32], &[2u8; 32], &swapped));
+    }
+
+    #[test]
+    fn test_ballot_signal_binds_every_input() {
+        let signal = ballot_signal(&[1u8; 32], 0, &[3u8; 32], 0);
+
+        assert_eq!(signal, ballot_signal(&[1u8; 32], 0, &[3u8; 32], 0));
+        assert_ne!(signal, ballot_signal(&[2u8; 32], 0, &[3u8; 32], 0));
+        assert_ne!(signal, ballot_signal(&[1u8; 32], 1, &[3u8; 32], 0));
+        assert_ne!(signal, ballot_signal(&[1u8; 32], 0, &[4u8; 32], 0));
+        assert_ne!(signal, ballot_signal(&[1u8; 32], 0, &[3u8; 32], 5000));
+    }
+
+    #[test]
+    fn test_generator_h_is_independent() {
+        let h = pedersen_h();
+
+        assert_ne!(h, RISTRETTO_BASEPOINT_POINT);
+        assert_eq!(h, pedersen_h());
+        // Zero value with blinding one is H itself
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(PedersenCommitment::commit(0, &one).as_point().unwrap(), h);
+    }
+
+    #[test]
+    fn test_homomorphic_add_and_sub() {
+        let mut rng = thread_rng();
+        let r1 = Scalar::random(&mut rng);
+        let r2 = Scalar::random(&mut rng);
+
+        let c1 = PedersenCommitment::commit(7, &r1.to_bytes());
+        let c2 = PedersenCommitment::commit(5, &r2.to_bytes());
+
+        let sum = c1.add(&c2).unwrap();
+        assert!(sum.verify_opening(12, &(r1 + r2).to_bytes()));
+
+        let diff = c1.sub(&c2).unwrap();
+        assert!(diff.verify_opening(2, &(r1 - r2).to_bytes()));
+        assert_eq!(diff.add(&c2).unwrap(), c1);
+    }
+
+    #[test]
+    fn test_invalid_commitment_bytes() {
+        assert_eq!(
+            PedersenCommitment::from_bytes([0xFF; 32]),
+            Err(CryptoError::InvalidCommitmentInput)
+        );
+    }
+
+    #[test]
+    fn test_equality_proof_roundtrip() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let blinding: [u8; 32] = rng.gen();
+        let randomness: [u8; 32] = rng.gen();
+
+        let ciphertext = keypair.public.encrypt_with_randomness(1, &randomness).unwrap();
+        let commitment = PedersenCommitment::commit(1, &blinding);
+        let proof = CommitmentEqualityProof::prove(
+            &keypair.public,
+            &ciphertext,
+            1,
+            &blinding,
+            &randomness,
+            &rng.gen(),
+            CONTEXT,
+        )
+        .unwrap();
+
+        assert!(proof.verify(&keypair.public, &commitment, &ciphertext, CONTEXT).is_ok());
+        assert_eq!(CommitmentEqualityProof::from_bytes(&proof.to_bytes()), proof);
+    }
+
+    #[test]
+    fn test_equality_proof_rejects_different_values() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let blinding: [u8; 32] = rng.gen();
+        let randomness: [u8; 32] = rng.gen();
+
+        let ciphertext = keypair.public.encrypt_with_randomness(1, &randomness).unwrap();
+        let proof = CommitmentEqualityProof::prove(
+            &keypair.public,
+            &ciphertext,
+            1,
+            &blinding,
+            &randomness,
+            &rng.gen(),
+            CONTEXT,
+        )
+        .unwrap();
+
+        // Same blinding, different value
+        let other = PedersenCommitment::commit(0, &blinding);
+        assert_eq!(
+            proof.verify(&keypair.public, &other, &ciphertext, CONTEXT),
+            Err(CryptoError::InvalidProof)
+        );
+
+        // The prover can't claim a value the ciphertext doesn't encrypt
+        assert!(CommitmentEqualityProof::prove(
+            &keypair.public,
+            &ciphertext,
+            0,
+            &blinding,
+            &randomness,
+            &rng.gen(),
+            CONTEXT,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_equality_proof_bound_to_context() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let blinding: [u8; 32] = rng.gen();
+        let randomness: [u8; 32] = rng.gen();
+
+        let ciphertext = keypair.public.encrypt_with_randomness(0, &randomness).unwrap();
+        let commitment = PedersenCommitment::commit(0, &blinding);
+        let proof = CommitmentEqualityProof::prove(
+            &keypair.public,
+            &ciphertext,
+            0,
+            &blinding,
+            &randomness,
+            &rng.gen(),
+            CONTEXT,
+        )
+        .unwrap();
+
+        assert!(proof
+            .verify(&keypair.public, &commitment, &ciphertext, b"another-context")
+            .is_err());
+    }
 }
@@ -0,0 +1,161 @@
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use serde::{Deserialize, Serialize};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::transcript::Transcript;
+use crate::{CryptoError, Result};
+
+/// Chaum-Pedersen proof of discrete log equality
+///
+/// Proves knowledge of `x` such that `X = x * G` and `Y = x * H` for
+/// public `G, H, X, Y`. Used to show that a decryption share (or a full
+/// decryption) was computed with the secret behind a known public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[repr(C)]
+pub struct DleqProof {
+    /// Fiat-Shamir challenge (32 bytes)
+    pub challenge: [u8; 32],
+    /// Response s = k + c * x (32 bytes)
+    pub response: [u8; 32],
+}
+
+impl DleqProof {
+    /// Prove that `x * g` and `x * h` share the same discrete log
+    ///
+    /// The caller's transcript should already bind the statement's
+    /// context (election, ciphertext, trustee index...).
+    pub fn prove(
+        transcript: &mut Transcript,
+        g: &RistrettoPoint,
+        h: &RistrettoPoint,
+        x: &Scalar,
+        randomness: &[u8; 32],
+    ) -> Self {
+        let big_x = x * g;
+        let big_y = x * h;
+        append_statement(transcript, g, h, &big_x, &big_y);
+
+        let k = transcript.witness_scalar(b"dleq-nonce", x.as_bytes(), randomness);
+        transcript.append_point(b"A", &(k * g).compress());
+        transcript.append_point(b"B", &(k * h).compress());
+
+        let c = transcript.challenge_scalar(b"dleq-challenge");
+        let s = k + c * x;
+
+        Self {
+            challenge: c.to_bytes(),
+            response: s.to_bytes(),
+        }
+    }
+
+    /// Verify that `big_x = x * g` and `big_y = x * h` for the same `x`
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        g: &RistrettoPoint,
+        h: &RistrettoPoint,
+        big_x: &RistrettoPoint,
+        big_y: &RistrettoPoint,
+    ) -> Result<()> {
+        let c = canonical_scalar(&self.challenge)?;
+        let s = canonical_scalar(&self.response)?;
+        append_statement(transcript, g, h, big_x, big_y);
+
+        // A = s*G - c*X, B = s*H - c*Y
+        let a = s * g - c * big_x;
+        let b = s * h - c * big_y;
+        transcript.append_point(b"A", &a.compress());
+        transcript.append_point(b"B", &b.compress());
+
+        if transcript.challenge_scalar(b"dleq-challenge") == c {
+            Ok(())
+        } else {
+            Err(CryptoError::InvalidProof)
+        }
+    }
+}
+
+fn append_statement(
+    transcript: &mut Transcript,
+    g: &RistrettoPoint,
+    h: &RistrettoPoint,
+    big_x: &RistrettoPoint,
+    big_y: &RistrettoPoint,
+) {
+    transcript.append_message(b"dom-sep", b"dleq-proof");
+    transcript.append_point(b"G", &g.compress());
+    transcript.append_point(b"H", &h.compress());
+    transcript.append_point(b"X", &big_x.compress());
+    transcript.append_point(b"Y", &big_y.compress());
+}
+
+/// Decode a canonically encoded scalar
+pub(crate) fn canonical_scalar(bytes: &[u8; 32]) -> Result<Scalar> {
+    Option::from(Scalar::from_canonical_bytes(*bytes)).ok_or(CryptoError::InvalidProof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_dleq_roundtrip() {
+        let mut rng = thread_rng();
+        let g = RISTRETTO_BASEPOINT_POINT;
+        let h = RistrettoPoint::random(&mut rng);
+        let x = Scalar::random(&mut rng);
+
+        let proof = DleqProof::prove(&mut Transcript::new(b"test"), &g, &h, &x, &[7u8; 32]);
+
+        assert!(proof
+            .verify(&mut Transcript::new(b"test"), &g, &h, &(x * g), &(x * h))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_dleq_rejects_different_logs() {
+        let mut rng = thread_rng();
+        let g = RISTRETTO_BASEPOINT_POINT;
+        let h = RistrettoPoint::random(&mut rng);
+        let x = Scalar::random(&mut rng);
+        let y = Scalar::random(&mut rng);
+
+        let proof = DleqProof::prove(&mut Transcript::new(b"test"), &g, &h, &x, &[7u8; 32]);
+
+        assert_eq!(
+            proof.verify(&mut Transcript::new(b"test"), &g, &h, &(x * g), &(y * h)),
+            Err(CryptoError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn test_dleq_bound_to_transcript() {
+        let mut rng = thread_rng();
+        let g = RISTRETTO_BASEPOINT_POINT;
+        let h = RistrettoPoint::random(&mut rng);
+        let x = Scalar::random(&mut rng);
+
+        let proof = DleqProof::prove(&mut Transcript::new(b"context-a"), &g, &h, &x, &[7u8; 32]);
+
+        assert!(proof
+            .verify(&mut Transcript::new(b"context-b"), &g, &h, &(x * g), &(x * h))
+            .is_err());
+    }
+
+    #[test]
+    fn test_dleq_rejects_tampered_response() {
+        let mut rng = thread_rng();
+        let g = RISTRETTO_BASEPOINT_POINT;
+        let h = RistrettoPoint::random(&mut rng);
+        let x = Scalar::random(&mut rng);
+
+        let mut proof = DleqProof::prove(&mut Transcript::new(b"test"), &g, &h, &x, &[7u8; 32]);
+        proof.response[0] ^= 1;
+
+        assert!(proof
+            .verify(&mut Transcript::new(b"test"), &g, &h, &(x * g), &(x * h))
+            .is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use alloc::collections::BTreeMap;
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_TABLE,
+    ristretto::RistrettoPoint,
+    scalar::Scalar,
+    traits::Identity,
+};
+
+use crate::{CryptoError, Result};
+
+/// Default upper bound used by `ElGamalSecretKey::decrypt`
+///
+/// Covers tallies of up to ~1M votes with a 1024-entry table
+pub const DEFAULT_DECRYPT_BOUND: u64 = 1 << 20;
+
+/// Baby-step giant-step solver for small discrete logarithms
+///
+/// Recovers `m` from `m * G` for any `m` in `[0, bound)` using
+/// `O(sqrt(bound))` memory and point additions. The baby-step table
+/// is built once and can be reused across many decryptions, e.g. one
+/// table per election for every option tally.
+#[derive(Debug, Clone)]
+pub struct DiscreteLog {
+    /// Compressed `j * G` -> `j` for `j` in `[0, step)`
+    baby_steps: BTreeMap<[u8; 32], u64>,
+    /// Number of baby steps (ceil(sqrt(bound)))
+    step: u64,
+    /// `-step * G`, subtracted once per giant step
+    giant_step: RistrettoPoint,
+    /// Exclusive upper bound on solvable values
+    bound: u64,
+}
+
+impl DiscreteLog {
+    /// Precompute the baby-step table for values in `[0, bound)`
+    pub fn new(bound: u64) -> Self {
+        let mut step = bound.isqrt();
+        if step.saturating_mul(step) < bound {
+            step += 1;
+        }
+        let step = step.max(1);
+
+        let mut baby_steps = BTreeMap::new();
+        let mut point = RistrettoPoint::identity();
+        let generator = RISTRETTO_BASEPOINT_TABLE.basepoint();
+        for j in 0..step {
+            baby_steps.insert(point.compress().to_bytes(), j);
+            point += generator;
+        }
+
+        // `point` is now step * G
+        Self {
+            baby_steps,
+            step,
+            giant_step: -point,
+            bound,
+        }
+    }
+
+    /// Exclusive upper bound on values this table can recover
+    pub fn bound(&self) -> u64 {
+        self.bound
+    }
+
+    /// Solve `point = m * G` for `m` in `[0, bound)`
+    pub fn solve(&self, point: &RistrettoPoint) -> Result<u64> {
+        let giant_steps = self.bound.div_ceil(self.step);
+        let mut current = *point;
+
+        for i in 0..giant_steps {
+            if let Some(j) = self.baby_steps.get(&current.compress().to_bytes()) {
+                let m = i * self.step + j;
+                if m < self.bound {
+                    return Ok(m);
+                }
+                return Err(CryptoError::DecryptionFailed);
+            }
+            current += self.giant_step;
+        }
+
+        Err(CryptoError::DecryptionFailed)
+    }
+
+    /// Solve for a plaintext encoded as a scalar multiple of the basepoint
+    pub fn solve_scalar(&self, scalar: &Scalar) -> Result<u64> {
+        self.solve(&(scalar * RISTRETTO_BASEPOINT_TABLE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(m: u64) -> RistrettoPoint {
+        &Scalar::from(m) * RISTRETTO_BASEPOINT_TABLE
+    }
+
+    #[test]
+    fn test_solve_small_values() {
+        let table = DiscreteLog::new(100);
+
+        for m in 0..100u64 {
+            assert_eq!(table.solve(&encode(m)).unwrap(), m);
+        }
+    }
+
+    #[test]
+    fn test_solve_respects_bound() {
+        let table = DiscreteLog::new(1000);
+
+        assert_eq!(table.solve(&encode(999)).unwrap(), 999);
+        assert_eq!(table.solve(&encode(1000)), Err(CryptoError::DecryptionFailed));
+        assert_eq!(table.solve(&encode(5000)), Err(CryptoError::DecryptionFailed));
+    }
+
+    #[test]
+    fn test_solve_large_value() {
+        let table = DiscreteLog::new(DEFAULT_DECRYPT_BOUND);
+
+        assert_eq!(table.solve(&encode(123_456)).unwrap(), 123_456);
+        assert_eq!(
+            table.solve(&encode(DEFAULT_DECRYPT_BOUND - 1)).unwrap(),
+            DEFAULT_DECRYPT_BOUND - 1
+        );
+    }
+
+    #[test]
+    fn test_non_square_bound() {
+        let table = DiscreteLog::new(17);
+
+        for m in 0..17u64 {
+            assert_eq!(table.solve(&encode(m)).unwrap(), m);
+        }
+        assert!(table.solve(&encode(17)).is_err());
+    }
+
+    #[test]
+    fn test_solve_scalar() {
+        let table = DiscreteLog::new(64);
+
+        assert_eq!(table.solve_scalar(&Scalar::from(42u64)).unwrap(), 42);
+    }
+}
@@ -1,5 +1,5 @@
 use curve25519_dalek::{
-    constants::RISTRETTO_BASEPOINT_TABLE,
+    constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE},
     ristretto::{CompressedRistretto, RistrettoPoint},
     scalar::Scalar,
     traits::Identity,
@@ -8,6 +8,9 @@ use serde::{Deserialize, Serialize};
 use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
 
+use crate::dleq::DleqProof;
+use crate::dlog::{DiscreteLog, DEFAULT_DECRYPT_BOUND};
+use crate::transcript::Transcript;
 use crate::{CryptoError, Result};
 
 /// ElGamal public key
@@ -23,12 +26,19 @@ unsafe impl Zeroable for ElGamalPublicKey {}
 
 impl ElGamalPublicKey {
     /// Create from compressed point bytes
+    ///
+    /// Rejects non-canonical encodings and the identity, which would
+    /// publish every plaintext as `C2 = m*G`. Ristretto has prime order, so
+    /// the identity is the only point of small order.
     pub fn from_bytes(bytes: [u8; 32]) -> Result<Self> {
         // Validate that it's a valid point
         let compressed = CompressedRistretto(bytes);
-        compressed
+        let point = compressed
             .decompress()
             .ok_or(CryptoError::InvalidPublicKey)?;
+        if point == RistrettoPoint::identity() {
+            return Err(CryptoError::InvalidPublicKey);
+        }
 
         Ok(Self { point: bytes })
     }
@@ -60,13 +70,29 @@ impl ElGamalPublicKey {
 
         // C2 = m * G + r * Y
         let m_point = &m_scalar * RISTRETTO_BASEPOINT_TABLE;
-        let c2_point = m_point + (&r * public_point);
+        let c2_point = m_point + (r * public_point);
 
         Ok(ElGamalCiphertext {
             c1: c1_point.compress().to_bytes(),
             c2: c2_point.compress().to_bytes(),
         })
     }
+
+    /// Verify a proof that `ciphertext` decrypts to `plaintext` under this key
+    pub fn verify_decryption(
+        &self,
+        ciphertext: &ElGamalCiphertext,
+        plaintext: u64,
+        proof: &DleqProof,
+    ) -> Result<()> {
+        let y = self.as_point()?;
+        let (c1, c2) = ciphertext.decompress()?;
+        let m_point = &Scalar::from(plaintext) * RISTRETTO_BASEPOINT_TABLE;
+
+        // log_G(Y) == log_C1(C2 - m*G)
+        let mut transcript = decryption_transcript(self, ciphertext, plaintext);
+        proof.verify(&mut transcript, &RISTRETTO_BASEPOINT_POINT, &c1, &y, &(c2 - m_point))
+    }
 }
 
 /// ElGamal secret key
@@ -88,7 +114,32 @@ impl ElGamalSecretKey {
     }
 
     /// Decrypt a ciphertext with this secret key
+    ///
+    /// Recovers plaintexts in `[0, DEFAULT_DECRYPT_BOUND)`. Use
+    /// `decrypt_with_bound` for larger tallies or `decrypt_with_table`
+    /// to reuse a precomputed table across many ciphertexts.
     pub fn decrypt(&self, ciphertext: &ElGamalCiphertext) -> Result<u64> {
+        self.decrypt_with_bound(ciphertext, DEFAULT_DECRYPT_BOUND)
+    }
+
+    /// Decrypt a ciphertext whose plaintext is known to be below `bound`
+    pub fn decrypt_with_bound(&self, ciphertext: &ElGamalCiphertext, bound: u64) -> Result<u64> {
+        let table = DiscreteLog::new(bound);
+        self.decrypt_with_table(ciphertext, &table)
+    }
+
+    /// Decrypt a ciphertext using a precomputed discrete log table
+    pub fn decrypt_with_table(
+        &self,
+        ciphertext: &ElGamalCiphertext,
+        table: &DiscreteLog,
+    ) -> Result<u64> {
+        let m_point = self.decrypt_to_point(ciphertext)?;
+        table.solve(&m_point)
+    }
+
+    /// Decrypt a ciphertext to the encoded point m * G
+    pub fn decrypt_to_point(&self, ciphertext: &ElGamalCiphertext) -> Result<RistrettoPoint> {
         let c1 = CompressedRistretto(ciphertext.c1)
             .decompress()
             .ok_or(CryptoError::InvalidCiphertext)?;
@@ -99,21 +150,50 @@ impl ElGamalSecretKey {
         let x = self.as_scalar();
 
         // Compute m * G = C2 - x * C1
-        let m_point = c2 - (x * c1);
-
-        // Brute force discrete log for small values (sufficient for vote counts)
-        // In practice, we know votes are small integers
-        for i in 0..10000u64 {
-            let test_point = &Scalar::from(i) * RISTRETTO_BASEPOINT_TABLE;
-            if test_point == m_point {
-                return Ok(i);
-            }
-        }
+        Ok(c2 - (x * c1))
+    }
 
-        Err(CryptoError::DecryptionFailed)
+    /// Decrypt a ciphertext and prove the result is correct
+    ///
+    /// The proof shows `log_G(Y) == log_C1(C2 - m*G)`, i.e. that the
+    /// plaintext was obtained with the secret behind the public key,
+    /// without revealing the key.
+    pub fn decrypt_with_proof(
+        &self,
+        ciphertext: &ElGamalCiphertext,
+        table: &DiscreteLog,
+        randomness: &[u8; 32],
+    ) -> Result<(u64, DleqProof)> {
+        let plaintext = self.decrypt_with_table(ciphertext, table)?;
+        let (c1, _) = ciphertext.decompress()?;
+        let public = ElGamalKeypair::from_secret(self.clone()).public;
+
+        let mut transcript = decryption_transcript(&public, ciphertext, plaintext);
+        let proof = DleqProof::prove(
+            &mut transcript,
+            &RISTRETTO_BASEPOINT_POINT,
+            &c1,
+            &self.as_scalar(),
+            randomness,
+        );
+
+        Ok((plaintext, proof))
     }
 }
 
+fn decryption_transcript(
+    public_key: &ElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    plaintext: u64,
+) -> Transcript {
+    let mut transcript = Transcript::new(b"elgamal-decryption-proof");
+    transcript.append_message(b"public-key", &public_key.point);
+    transcript.append_message(b"c1", &ciphertext.c1);
+    transcript.append_message(b"c2", &ciphertext.c2);
+    transcript.append_u64(b"plaintext", plaintext);
+    transcript
+}
+
 /// ElGamal keypair
 #[derive(Debug, Clone)]
 pub struct ElGamalKeypair {
@@ -166,6 +246,41 @@ unsafe impl Pod for ElGamalCiphertext {}
 unsafe impl Zeroable for ElGamalCiphertext {}
 
 impl ElGamalCiphertext {
+    /// Trivial encryption of zero (both components the identity)
+    ///
+    /// The neutral element for homomorphic addition, used to start an
+    /// accumulator before any ballot has been added.
+    pub fn zero() -> Self {
+        Self {
+            c1: RistrettoPoint::identity().compress().to_bytes(),
+            c2: RistrettoPoint::identity().compress().to_bytes(),
+        }
+    }
+
+    /// Decompress both components
+    pub fn decompress(&self) -> Result<(RistrettoPoint, RistrettoPoint)> {
+        let c1 = CompressedRistretto(self.c1)
+            .decompress()
+            .ok_or(CryptoError::InvalidCiphertext)?;
+        let c2 = CompressedRistretto(self.c2)
+            .decompress()
+            .ok_or(CryptoError::InvalidCiphertext)?;
+        Ok((c1, c2))
+    }
+
+    /// Check the ciphertext is well formed for a freshly cast ballot
+    ///
+    /// Both components must be canonical Ristretto encodings and `C1`
+    /// must not be the identity: `C1 = r*G` with `r = 0` leaves
+    /// `C2 = m*G`, publishing the plaintext.
+    pub fn validate(&self) -> Result<()> {
+        let (c1, _) = self.decompress()?;
+        if c1 == RistrettoPoint::identity() {
+            return Err(CryptoError::InvalidCiphertext);
+        }
+        Ok(())
+    }
+
     /// Homomorphic addition of two ciphertexts
     /// E(m1) + E(m2) = E(m1 + m2)
     pub fn add(&self, other: &ElGamalCiphertext) -> Result<ElGamalCiphertext> {
@@ -193,6 +308,18 @@ impl ElGamalCiphertext {
         })
     }
 
+    /// Homomorphic subtraction of two ciphertexts
+    /// E(m1) - E(m2) = E(m1 - m2)
+    pub fn sub(&self, other: &ElGamalCiphertext) -> Result<ElGamalCiphertext> {
+        let (c1_self, c2_self) = self.decompress()?;
+        let (c1_other, c2_other) = other.decompress()?;
+
+        Ok(ElGamalCiphertext {
+            c1: (c1_self - c1_other).compress().to_bytes(),
+            c2: (c2_self - c2_other).compress().to_bytes(),
+        })
+    }
+
     /// Scalar multiplication for weighted addition
     pub fn mul_scalar(&self, scalar: u64) -> Result<ElGamalCiphertext> {
         let c1 = CompressedRistretto(self.c1)
@@ -225,6 +352,23 @@ mod tests {
         assert!(keypair.public.as_point().is_ok());
     }
 
+    #[test]
+    fn test_public_key_validation() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        assert_eq!(ElGamalPublicKey::from_bytes(keypair.public.point), Ok(keypair.public));
+
+        // Identity
+        assert_eq!(ElGamalPublicKey::from_bytes([0u8; 32]), Err(CryptoError::InvalidPublicKey));
+        // Not a valid encoding
+        assert_eq!(ElGamalPublicKey::from_bytes([0xFF; 32]), Err(CryptoError::InvalidPublicKey));
+        // Non-canonical: the field element p + 1 instead of 1
+        let mut non_canonical = [0xFF; 32];
+        non_canonical[0] = 0xEE;
+        non_canonical[31] = 0x7F;
+        assert_eq!(ElGamalPublicKey::from_bytes(non_canonical), Err(CryptoError::InvalidPublicKey));
+    }
+
     #[test]
     fn test_encryption_decryption() {
         let mut rng = thread_rng();
@@ -257,6 +401,56 @@ mod tests {
         assert_eq!(m1 + m2, decrypted_sum);
     }
 
+    #[test]
+    fn test_homomorphic_subtraction() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let c1 = keypair.public.encrypt_with_randomness(42, &rand::Rng::gen(&mut rng)).unwrap();
+        let c2 = keypair.public.encrypt_with_randomness(10, &rand::Rng::gen(&mut rng)).unwrap();
+
+        let c_diff = c1.sub(&c2).unwrap();
+        assert_eq!(keypair.secret.decrypt(&c_diff).unwrap(), 32);
+        // Swapping a ballot out of a sum leaves the rest untouched
+        assert_eq!(c1.add(&c2).unwrap().sub(&c2).unwrap(), c1);
+    }
+
+    #[test]
+    fn test_zero_is_additive_identity() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let randomness = rand::Rng::gen(&mut rng);
+        let ciphertext = keypair.public.encrypt_with_randomness(9, &randomness).unwrap();
+
+        assert_eq!(ElGamalCiphertext::zero().add(&ciphertext).unwrap(), ciphertext);
+        assert_eq!(keypair.secret.decrypt(&ElGamalCiphertext::zero()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ciphertext_validation() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let ciphertext = keypair
+            .public
+            .encrypt_with_randomness(1, &rand::Rng::gen(&mut rng))
+            .unwrap();
+        assert!(ciphertext.validate().is_ok());
+
+        // r = 0 exposes the plaintext
+        let exposed = keypair.public.encrypt_with_randomness(1, &[0u8; 32]).unwrap();
+        assert_eq!(exposed.validate(), Err(CryptoError::InvalidCiphertext));
+        assert_eq!(ElGamalCiphertext::zero().validate(), Err(CryptoError::InvalidCiphertext));
+
+        // Non-canonical encodings of either component
+        let mut bad_c1 = ciphertext;
+        bad_c1.c1 = [0xFF; 32];
+        assert_eq!(bad_c1.validate(), Err(CryptoError::InvalidCiphertext));
+        let mut bad_c2 = ciphertext;
+        bad_c2.c2[31] |= 0x80;
+        assert_eq!(bad_c2.validate(), Err(CryptoError::InvalidCiphertext));
+    }
+
     #[test]
     fn test_scalar_multiplication() {
         let mut rng = thread_rng();
@@ -280,8 +474,86 @@ mod tests {
 
         // Test borsh serialization
         let serialized = borsh::to_vec(&keypair.public).unwrap();
-        let deserialized: ElGamalPublicKey = borsh::from_slice(&serialized).unwrap();
+        let deserialized: ElGamalPublicKey = BorshDeserialize::try_from_slice(&serialized).unwrap();
 
         assert_eq!(keypair.public, deserialized);
     }
+
+    #[test]
+    fn test_decrypt_above_brute_force_limit() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let message = 250_000u64;
+        let randomness = rand::Rng::gen(&mut rng);
+        let ciphertext = keypair.public.encrypt_with_randomness(message, &randomness).unwrap();
+
+        assert_eq!(keypair.secret.decrypt(&ciphertext).unwrap(), message);
+    }
+
+    #[test]
+    fn test_decrypt_with_bound() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let randomness = rand::Rng::gen(&mut rng);
+        let ciphertext = keypair.public.encrypt_with_randomness(500, &randomness).unwrap();
+
+        assert_eq!(keypair.secret.decrypt_with_bound(&ciphertext, 501).unwrap(), 500);
+        assert_eq!(
+            keypair.secret.decrypt_with_bound(&ciphertext, 500),
+            Err(CryptoError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn test_decryption_proof() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let table = DiscreteLog::new(1024);
+
+        let randomness = rand::Rng::gen(&mut rng);
+        let ciphertext = keypair.public.encrypt_with_randomness(321, &randomness).unwrap();
+        let (plaintext, proof) = keypair
+            .secret
+            .decrypt_with_proof(&ciphertext, &table, &rand::Rng::gen(&mut rng))
+            .unwrap();
+
+        assert_eq!(plaintext, 321);
+        assert!(keypair.public.verify_decryption(&ciphertext, 321, &proof).is_ok());
+        assert_eq!(
+            keypair.public.verify_decryption(&ciphertext, 322, &proof),
+            Err(CryptoError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn test_decryption_proof_wrong_key() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let other = ElGamalKeypair::generate(&mut rng);
+        let table = DiscreteLog::new(1024);
+
+        let randomness = rand::Rng::gen(&mut rng);
+        let ciphertext = keypair.public.encrypt_with_randomness(7, &randomness).unwrap();
+        let (_, proof) = keypair
+            .secret
+            .decrypt_with_proof(&ciphertext, &table, &rand::Rng::gen(&mut rng))
+            .unwrap();
+
+        assert!(other.public.verify_decryption(&ciphertext, 7, &proof).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_with_shared_table() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let table = DiscreteLog::new(1 << 16);
+
+        for message in [0u64, 1, 4_096, 65_535] {
+            let randomness = rand::Rng::gen(&mut rng);
+            let ciphertext = keypair.public.encrypt_with_randomness(message, &randomness).unwrap();
+            assert_eq!(keypair.secret.decrypt_with_table(&ciphertext, &table).unwrap(), message);
+        }
+    }
 }
@@ -11,6 +11,11 @@ pub enum CryptoError {
     InvalidNullifierInput,
     InvalidCommitmentInput,
     ArithmeticError,
+    InvalidThreshold,
+    InvalidShare,
+    InsufficientShares,
+    InvalidProof,
+    InvalidMerkleProof,
 }
 
 impl fmt::Display for CryptoError {
@@ -25,6 +30,11 @@ impl fmt::Display for CryptoError {
             CryptoError::InvalidNullifierInput => write!(f, "Invalid nullifier input"),
             CryptoError::InvalidCommitmentInput => write!(f, "Invalid commitment input"),
             CryptoError::ArithmeticError => write!(f, "Arithmetic error"),
+            CryptoError::InvalidThreshold => write!(f, "Invalid threshold parameters"),
+            CryptoError::InvalidShare => write!(f, "Invalid secret share"),
+            CryptoError::InsufficientShares => write!(f, "Not enough shares to reconstruct"),
+            CryptoError::InvalidProof => write!(f, "Invalid proof"),
+            CryptoError::InvalidMerkleProof => write!(f, "Invalid merkle proof"),
         }
     }
 }
@@ -2,35 +2,51 @@
 //!
 //! This library provides:
 //! - ElGamal encryption and decryption
+//! - Baby-step giant-step discrete log for tally decryption
 //! - Homomorphic addition operations
-//! - Nullifier generation
-//! - Vote commitments
+//! - Threshold key generation and verifiable partial decryption
+//! - Zero-knowledge proofs of ballot validity
+//! - Multi-option encrypted ballots
+//! - Versioned nullifier generation (Keccak and Poseidon)
+//! - Voter eligibility Merkle trees, optionally weighted
+//! - Pedersen vote commitments linked to ElGamal ciphertexts
 
 #![cfg_attr(not(test), no_std)]
 
 extern crate alloc;
 
 pub mod elgamal;
+pub mod dlog;
+pub mod transcript;
+pub mod dleq;
+pub mod threshold;
+pub mod proofs;
+pub mod ballot;
 pub mod nullifier;
+pub mod merkle;
 pub mod commitment;
 pub mod errors;
 
 // Re-exports
 pub use elgamal::{ElGamalKeypair, ElGamalPublicKey, ElGamalSecretKey, ElGamalCiphertext};
-pub use nullifier::compute_nullifier;
-pub use commitment::commit_vote;
+pub use dlog::{DiscreteLog, DEFAULT_DECRYPT_BOUND};
+pub use transcript::Transcript;
+pub use dleq::DleqProof;
+pub use threshold::{
+    DkgDealer, PartialDecryption, PolynomialCommitment, SecretShare, ThresholdParams, TrusteeKey,
+};
+pub use proofs::{encrypt_binary_with_proof, BinaryProof, MembershipProof, BINARY_PROOF_LEN};
+pub use ballot::{
+    encrypt_ballot, verify_slot_proof, verify_total_proof, BallotProof, BallotRule, EncryptedBallot,
+};
+pub use nullifier::{compute_nullifier, compute_public_nullifier};
+#[cfg(feature = "poseidon")]
+pub use nullifier::compute_poseidon_nullifier;
+pub use merkle::{weighted_leaf, KeccakHasher, MerkleHasher, MerkleProof, MerkleTree};
+pub use commitment::{
+    ballot_signal, commit_aggregate, commit_ballot, commit_vote, CommitmentEqualityProof, PedersenCommitment,
+};
 pub use errors::CryptoError;
 
 /// Result type for crypto operations
 pub type Result<T> = core::result::Result<T, CryptoError>;
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_library_loads() {
-        // Basic sanity check
-        assert!(true);
-    }
-}
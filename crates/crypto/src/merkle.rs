@@ -0,0 +1,335 @@
+//! Voter eligibility Merkle trees
+//!
+//! Fixed-depth binary trees over 32-byte voter commitments. Unfilled
+//! positions hold precomputed empty-subtree hashes, so a tree of depth 20
+//! (about a million voters) can be built and appended to without
+//! materialising the empty half. Leaves and internal nodes are hashed
+//! with distinct prefixes so a node can never be passed off as a leaf.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use sha3::{Digest, Keccak256};
+
+use crate::{CryptoError, Result};
+
+/// Maximum supported tree depth (2^32 leaves)
+pub const MAX_MERKLE_DEPTH: usize = 32;
+
+/// Domain separator for `weighted_leaf`
+pub const WEIGHTED_LEAF_DOMAIN: &[u8] = b"privacy-crypto/weighted-leaf/v1";
+
+/// Leaf committing a voter to their vote weight
+///
+/// `Keccak256(domain || voter || weight)` with `weight` as u64 LE. Proving
+/// inclusion of this leaf proves the weight as well as eligibility.
+pub fn weighted_leaf(voter: &[u8; 32], weight: u64) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(WEIGHTED_LEAF_DOMAIN);
+    hasher.update(voter);
+    hasher.update(weight.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Hash function used to build a Merkle tree
+pub trait MerkleHasher {
+    /// Hash a leaf value
+    fn hash_leaf(leaf: &[u8; 32]) -> [u8; 32];
+
+    /// Hash two child nodes into their parent
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32];
+}
+
+/// Keccak256 with `0x00` / `0x01` leaf and node prefixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeccakHasher;
+
+impl MerkleHasher for KeccakHasher {
+    fn hash_leaf(leaf: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update([0x00]);
+        hasher.update(leaf);
+        hasher.finalize().into()
+    }
+
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update([0x01]);
+        hasher.update(left);
+        hasher.update(right);
+        hasher.finalize().into()
+    }
+}
+
+/// Inclusion proof for one leaf
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// Position of the leaf in the tree
+    pub leaf_index: u32,
+    /// Sibling hashes from the leaf level up to just below the root
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// Recompute the root implied by this proof for `leaf`
+    pub fn compute_root<H: MerkleHasher>(&self, leaf: &[u8; 32]) -> Result<[u8; 32]> {
+        compute_root::<H>(leaf, self.leaf_index, &self.siblings)
+    }
+
+    /// Check that `leaf` is included under `root`
+    pub fn verify<H: MerkleHasher>(&self, root: &[u8; 32], leaf: &[u8; 32]) -> bool {
+        matches!(self.compute_root::<H>(leaf), Ok(computed) if &computed == root)
+    }
+}
+
+/// Recompute a root from a leaf, its index and its sibling path
+///
+/// Allocation-free so it can run on-chain.
+pub fn compute_root<H: MerkleHasher>(
+    leaf: &[u8; 32],
+    leaf_index: u32,
+    siblings: &[[u8; 32]],
+) -> Result<[u8; 32]> {
+    if siblings.len() > MAX_MERKLE_DEPTH || (leaf_index as u64) >> siblings.len() != 0 {
+        return Err(CryptoError::InvalidMerkleProof);
+    }
+
+    let mut node = H::hash_leaf(leaf);
+    let mut index = leaf_index;
+    for sibling in siblings {
+        node = if index & 1 == 0 {
+            H::hash_nodes(&node, sibling)
+        } else {
+            H::hash_nodes(sibling, &node)
+        };
+        index >>= 1;
+    }
+
+    Ok(node)
+}
+
+/// Sparse, append-only Merkle tree of fixed depth
+#[derive(Debug, Clone)]
+pub struct MerkleTree<H: MerkleHasher = KeccakHasher> {
+    depth: usize,
+    /// Filled nodes per level; `levels[0]` are hashed leaves
+    levels: Vec<Vec<[u8; 32]>>,
+    /// Root of an empty subtree at each height
+    zeros: Vec<[u8; 32]>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    /// Create an empty tree of the given depth
+    pub fn new(depth: usize) -> Result<Self> {
+        if depth == 0 || depth > MAX_MERKLE_DEPTH {
+            return Err(CryptoError::InvalidMerkleProof);
+        }
+
+        let mut zeros = Vec::with_capacity(depth + 1);
+        zeros.push(H::hash_leaf(&[0u8; 32]));
+        for level in 0..depth {
+            let zero = zeros[level];
+            zeros.push(H::hash_nodes(&zero, &zero));
+        }
+
+        Ok(Self {
+            depth,
+            levels: (0..=depth).map(|_| Vec::new()).collect(),
+            zeros,
+            _hasher: PhantomData,
+        })
+    }
+
+    /// Build a tree from a list of leaves
+    pub fn from_leaves(depth: usize, leaves: &[[u8; 32]]) -> Result<Self> {
+        let mut tree = Self::new(depth)?;
+        for leaf in leaves {
+            tree.append(*leaf)?;
+        }
+        Ok(tree)
+    }
+
+    /// Tree depth
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Number of leaves appended so far
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Whether no leaves have been appended
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Append a leaf, updating only the path to the root
+    ///
+    /// Returns the index of the new leaf.
+    pub fn append(&mut self, leaf: [u8; 32]) -> Result<u32> {
+        let index = self.len();
+        if (index as u64) >> self.depth != 0 {
+            return Err(CryptoError::InvalidMerkleProof);
+        }
+
+        self.levels[0].push(H::hash_leaf(&leaf));
+        let mut position = index;
+        for level in 0..self.depth {
+            let parent = position / 2;
+            let left = self.node(level, parent * 2);
+            let right = self.node(level, parent * 2 + 1);
+            let hash = H::hash_nodes(&left, &right);
+
+            let nodes = &mut self.levels[level + 1];
+            if parent < nodes.len() {
+                nodes[parent] = hash;
+            } else {
+                nodes.push(hash);
+            }
+            position = parent;
+        }
+
+        Ok(index as u32)
+    }
+
+    /// Current root
+    pub fn root(&self) -> [u8; 32] {
+        self.node(self.depth, 0)
+    }
+
+    /// Inclusion proof for the leaf at `index`
+    pub fn proof(&self, index: u32) -> Result<MerkleProof> {
+        if index as usize >= self.len() {
+            return Err(CryptoError::InvalidMerkleProof);
+        }
+
+        let mut siblings = Vec::with_capacity(self.depth);
+        let mut position = index as usize;
+        for level in 0..self.depth {
+            siblings.push(self.node(level, position ^ 1));
+            position /= 2;
+        }
+
+        Ok(MerkleProof {
+            leaf_index: index,
+            siblings,
+        })
+    }
+
+    fn node(&self, level: usize, position: usize) -> [u8; 32] {
+        self.levels[level]
+            .get(position)
+            .copied()
+            .unwrap_or(self.zeros[level])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(i: u32) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[..4].copy_from_slice(&i.to_le_bytes());
+        leaf[31] = 0xAA;
+        leaf
+    }
+
+    #[test]
+    fn test_proofs_verify_for_every_leaf() {
+        let leaves: Vec<[u8; 32]> = (0..13).map(leaf).collect();
+        let tree = MerkleTree::<KeccakHasher>::from_leaves(4, &leaves).unwrap();
+        let root = tree.root();
+
+        for (i, l) in leaves.iter().enumerate() {
+            let proof = tree.proof(i as u32).unwrap();
+            assert_eq!(proof.siblings.len(), 4);
+            assert!(proof.verify::<KeccakHasher>(&root, l));
+        }
+    }
+
+    #[test]
+    fn test_proof_rejects_wrong_leaf_or_index() {
+        let leaves: Vec<[u8; 32]> = (0..8).map(leaf).collect();
+        let tree = MerkleTree::<KeccakHasher>::from_leaves(3, &leaves).unwrap();
+        let root = tree.root();
+
+        let mut proof = tree.proof(2).unwrap();
+        assert!(!proof.verify::<KeccakHasher>(&root, &leaf(3)));
+
+        proof.leaf_index = 3;
+        assert!(!proof.verify::<KeccakHasher>(&root, &leaf(2)));
+
+        // Index outside the tree
+        proof.leaf_index = 8;
+        assert!(!proof.verify::<KeccakHasher>(&root, &leaf(2)));
+    }
+
+    #[test]
+    fn test_incremental_matches_batch_build() {
+        let leaves: Vec<[u8; 32]> = (0..5).map(leaf).collect();
+        let batch = MerkleTree::<KeccakHasher>::from_leaves(10, &leaves).unwrap();
+
+        let mut incremental = MerkleTree::<KeccakHasher>::new(10).unwrap();
+        let mut roots = Vec::new();
+        for l in &leaves {
+            incremental.append(*l).unwrap();
+            roots.push(incremental.root());
+        }
+
+        assert_eq!(incremental.root(), batch.root());
+        // Every append changes the root
+        roots.dedup();
+        assert_eq!(roots.len(), leaves.len());
+    }
+
+    #[test]
+    fn test_old_proofs_fail_after_append() {
+        let mut tree = MerkleTree::<KeccakHasher>::new(3).unwrap();
+        tree.append(leaf(0)).unwrap();
+        let proof = tree.proof(0).unwrap();
+        let old_root = tree.root();
+
+        tree.append(leaf(1)).unwrap();
+
+        assert!(proof.verify::<KeccakHasher>(&old_root, &leaf(0)));
+        assert!(!proof.verify::<KeccakHasher>(&tree.root(), &leaf(0)));
+        assert!(tree.proof(0).unwrap().verify::<KeccakHasher>(&tree.root(), &leaf(0)));
+    }
+
+    #[test]
+    fn test_tree_full() {
+        let mut tree = MerkleTree::<KeccakHasher>::new(1).unwrap();
+        tree.append(leaf(0)).unwrap();
+        tree.append(leaf(1)).unwrap();
+
+        assert_eq!(tree.append(leaf(2)), Err(CryptoError::InvalidMerkleProof));
+    }
+
+    #[test]
+    fn test_leaf_and_node_domains_differ() {
+        let a = leaf(1);
+        let b = leaf(2);
+        let node = KeccakHasher::hash_nodes(&a, &b);
+
+        assert_ne!(KeccakHasher::hash_leaf(&node), node);
+        assert_ne!(KeccakHasher::hash_leaf(&a), a);
+    }
+
+    #[test]
+    fn test_weighted_leaf_binds_weight() {
+        let tree = MerkleTree::<KeccakHasher>::from_leaves(4, &[weighted_leaf(&leaf(0), 100)]).unwrap();
+        let proof = tree.proof(0).unwrap();
+
+        assert!(proof.verify::<KeccakHasher>(&tree.root(), &weighted_leaf(&leaf(0), 100)));
+        assert!(!proof.verify::<KeccakHasher>(&tree.root(), &weighted_leaf(&leaf(0), 101)));
+        assert!(!proof.verify::<KeccakHasher>(&tree.root(), &leaf(0)));
+    }
+
+    #[test]
+    fn test_invalid_depth() {
+        assert!(MerkleTree::<KeccakHasher>::new(0).is_err());
+        assert!(MerkleTree::<KeccakHasher>::new(MAX_MERKLE_DEPTH + 1).is_err());
+    }
+}
@@ -1,24 +1,40 @@
+//! Versioned, domain-separated nullifiers
+//!
+//! A nullifier is a deterministic per-voter, per-election tag: the same
+//! voter always derives the same value for an election, so a second vote
+//! collides with the first. There is deliberately no nonce or other
+//! voter-chosen input, otherwise one voter could mint many nullifiers.
+//!
+//! Every scheme hashes a versioned domain tag first. A future change to
+//! the derivation gets a new tag rather than silently changing outputs.
+//! Vectors shared with the TypeScript SDK live in
+//! `test-vectors/nullifier.json` at the repository root.
+
 use sha3::{Digest, Keccak256};
 
+/// Domain tag for `compute_nullifier`
+pub const NULLIFIER_DOMAIN: &[u8] = b"privacy-layer/nullifier/v1";
+
+/// Domain tag for `compute_public_nullifier`
+pub const PUBLIC_NULLIFIER_DOMAIN: &[u8] = b"privacy-layer/public-nullifier/v1";
+
+/// Domain tag for `compute_poseidon_nullifier`, read as a big-endian
+/// field element
+pub const POSEIDON_NULLIFIER_DOMAIN: &[u8] = b"nullifier-v1";
+
 /// Compute a nullifier for vote uniqueness
 ///
-/// Nullifier = H(voter_secret || election_id || nonce)
+/// Nullifier = Keccak256(NULLIFIER_DOMAIN || election_id || voter_secret)
 ///
 /// This ensures:
 /// - Same voter + same election = same nullifier (prevents double voting)
 /// - Different elections = different nullifiers (can vote in multiple elections)
-/// - Nonce adds optional entropy if needed
-pub fn compute_nullifier(
-    voter_secret: &[u8; 32],
-    election_id: &[u8; 32],
-    nonce: u64,
-) -> [u8; 32] {
+pub fn compute_nullifier(voter_secret: &[u8; 32], election_id: &[u8; 32]) -> [u8; 32] {
     let mut hasher = Keccak256::new();
 
-    // Hash all inputs together
-    hasher.update(voter_secret);
+    hasher.update(NULLIFIER_DOMAIN);
     hasher.update(election_id);
-    hasher.update(nonce.to_le_bytes());
+    hasher.update(voter_secret);
 
     let result = hasher.finalize();
     result.into()
@@ -28,9 +44,55 @@ pub fn compute_nullifier(
 pub fn compute_nullifier_with_pubkey(
     voter_secret: &[u8; 32],
     election_pubkey: &[u8; 32],
-    nonce: u64,
 ) -> [u8; 32] {
-    compute_nullifier(voter_secret, election_pubkey, nonce)
+    compute_nullifier(voter_secret, election_pubkey)
+}
+
+/// Compute the nullifier for a public-eligibility election
+///
+/// Nullifier = Keccak256(PUBLIC_NULLIFIER_DOMAIN || election_id || voter)
+///
+/// In public mode the voter's wallet is the Merkle leaf, so the nullifier
+/// is derived from it directly: one wallet, one nullifier per election.
+pub fn compute_public_nullifier(voter: &[u8; 32], election_id: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+
+    hasher.update(PUBLIC_NULLIFIER_DOMAIN);
+    hasher.update(election_id);
+    hasher.update(voter);
+
+    let result = hasher.finalize();
+    result.into()
+}
+
+/// Compute the circuit-friendly nullifier used by anonymous elections
+///
+/// Nullifier = Poseidon(POSEIDON_NULLIFIER_DOMAIN, voter_secret, election_id)
+///
+/// Uses the circom Poseidon parameters over the BN254 scalar field, so an
+/// eligibility circuit can recompute it. Inputs are big-endian and reduced
+/// modulo the field order; the output is a canonical big-endian field
+/// element, as the on-chain Groth16 verifier requires.
+#[cfg(feature = "poseidon")]
+pub fn compute_poseidon_nullifier(voter_secret: &[u8; 32], election_id: &[u8; 32]) -> [u8; 32] {
+    use ark_bn254::Fr;
+    use ark_ff::{BigInteger, PrimeField};
+    use light_poseidon::{Poseidon, PoseidonHasher};
+
+    let inputs = [
+        Fr::from_be_bytes_mod_order(POSEIDON_NULLIFIER_DOMAIN),
+        Fr::from_be_bytes_mod_order(voter_secret),
+        Fr::from_be_bytes_mod_order(election_id),
+    ];
+
+    // Circom parameters exist for every width up to 12, and every input
+    // is already a field element, so neither call can fail
+    let mut poseidon = Poseidon::<Fr>::new_circom(inputs.len()).expect("supported width");
+    let hash = poseidon.hash(&inputs).expect("inputs are field elements");
+
+    let mut nullifier = [0u8; 32];
+    nullifier.copy_from_slice(&hash.into_bigint().to_bytes_be());
+    nullifier
 }
 
 /// Verify that a nullifier was generated correctly (for testing)
@@ -38,9 +100,8 @@ pub fn verify_nullifier(
     nullifier: &[u8; 32],
     voter_secret: &[u8; 32],
     election_id: &[u8; 32],
-    nonce: u64,
 ) -> bool {
-    let computed = compute_nullifier(voter_secret, election_id, nonce);
+    let computed = compute_nullifier(voter_secret, election_id);
     &computed == nullifier
 }
 
@@ -48,14 +109,21 @@ pub fn verify_nullifier(
 mod tests {
     use super::*;
 
+    const VECTORS: &str = include_str!("../../../test-vectors/nullifier.json");
+
+    fn hex32(value: &serde_json::Value) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        hex::decode_to_slice(value.as_str().unwrap(), &mut out).unwrap();
+        out
+    }
+
     #[test]
     fn test_nullifier_deterministic() {
         let voter_secret = [1u8; 32];
         let election_id = [2u8; 32];
-        let nonce = 0u64;
 
-        let nullifier1 = compute_nullifier(&voter_secret, &election_id, nonce);
-        let nullifier2 = compute_nullifier(&voter_secret, &election_id, nonce);
+        let nullifier1 = compute_nullifier(&voter_secret, &election_id);
+        let nullifier2 = compute_nullifier(&voter_secret, &election_id);
 
         assert_eq!(nullifier1, nullifier2);
     }
@@ -65,10 +133,9 @@ mod tests {
         let voter1_secret = [1u8; 32];
         let voter2_secret = [2u8; 32];
         let election_id = [3u8; 32];
-        let nonce = 0u64;
 
-        let nullifier1 = compute_nullifier(&voter1_secret, &election_id, nonce);
-        let nullifier2 = compute_nullifier(&voter2_secret, &election_id, nonce);
+        let nullifier1 = compute_nullifier(&voter1_secret, &election_id);
+        let nullifier2 = compute_nullifier(&voter2_secret, &election_id);
 
         assert_ne!(nullifier1, nullifier2);
     }
@@ -78,34 +145,74 @@ mod tests {
         let voter_secret = [1u8; 32];
         let election1_id = [2u8; 32];
         let election2_id = [3u8; 32];
-        let nonce = 0u64;
 
-        let nullifier1 = compute_nullifier(&voter_secret, &election1_id, nonce);
-        let nullifier2 = compute_nullifier(&voter_secret, &election2_id, nonce);
+        let nullifier1 = compute_nullifier(&voter_secret, &election1_id);
+        let nullifier2 = compute_nullifier(&voter_secret, &election2_id);
 
         assert_ne!(nullifier1, nullifier2);
     }
 
     #[test]
-    fn test_nullifier_different_nonce() {
+    fn test_verify_nullifier() {
         let voter_secret = [1u8; 32];
         let election_id = [2u8; 32];
 
-        let nullifier1 = compute_nullifier(&voter_secret, &election_id, 0);
-        let nullifier2 = compute_nullifier(&voter_secret, &election_id, 1);
+        let nullifier = compute_nullifier(&voter_secret, &election_id);
 
-        assert_ne!(nullifier1, nullifier2);
+        assert!(verify_nullifier(&nullifier, &voter_secret, &election_id));
+        assert!(!verify_nullifier(&nullifier, &[3u8; 32], &election_id));
     }
 
     #[test]
-    fn test_verify_nullifier() {
+    fn test_public_nullifier() {
+        let voter = [1u8; 32];
+        let election_id = [2u8; 32];
+
+        let nullifier = compute_public_nullifier(&voter, &election_id);
+
+        assert_eq!(nullifier, compute_public_nullifier(&voter, &election_id));
+        assert_ne!(nullifier, compute_public_nullifier(&[3u8; 32], &election_id));
+        assert_ne!(nullifier, compute_public_nullifier(&voter, &[3u8; 32]));
+        // Distinct from the secret-based scheme on the same inputs
+        assert_ne!(nullifier, compute_nullifier(&voter, &election_id));
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_poseidon_nullifier() {
         let voter_secret = [1u8; 32];
         let election_id = [2u8; 32];
-        let nonce = 42u64;
 
-        let nullifier = compute_nullifier(&voter_secret, &election_id, nonce);
+        let nullifier = compute_poseidon_nullifier(&voter_secret, &election_id);
+
+        assert_eq!(nullifier, compute_poseidon_nullifier(&voter_secret, &election_id));
+        assert_ne!(nullifier, compute_poseidon_nullifier(&[3u8; 32], &election_id));
+        assert_ne!(nullifier, compute_poseidon_nullifier(&voter_secret, &[3u8; 32]));
+        assert_ne!(nullifier, compute_nullifier(&voter_secret, &election_id));
+    }
 
-        assert!(verify_nullifier(&nullifier, &voter_secret, &election_id, nonce));
-        assert!(!verify_nullifier(&nullifier, &voter_secret, &election_id, nonce + 1));
+    #[test]
+    fn test_shared_vectors() {
+        let vectors: serde_json::Value = serde_json::from_str(VECTORS).unwrap();
+
+        for case in vectors["keccak_v1"].as_array().unwrap() {
+            assert_eq!(
+                compute_nullifier(&hex32(&case["voter_secret"]), &hex32(&case["election_id"])),
+                hex32(&case["nullifier"])
+            );
+        }
+        for case in vectors["public_v1"].as_array().unwrap() {
+            assert_eq!(
+                compute_public_nullifier(&hex32(&case["voter"]), &hex32(&case["election_id"])),
+                hex32(&case["nullifier"])
+            );
+        }
+        #[cfg(feature = "poseidon")]
+        for case in vectors["poseidon_v1"].as_array().unwrap() {
+            assert_eq!(
+                compute_poseidon_nullifier(&hex32(&case["voter_secret"]), &hex32(&case["election_id"])),
+                hex32(&case["nullifier"])
+            );
+        }
     }
 }
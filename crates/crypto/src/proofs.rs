@@ -0,0 +1,484 @@
+//! Non-interactive proofs about ElGamal ciphertexts
+//!
+//! `BinaryProof` is a disjunctive Chaum-Pedersen (CDS) proof that a
+//! ciphertext `(C1, C2) = (r*G, m*G + r*Y)` encrypts `m = 0` or `m = 1`
+//! without revealing which. For each candidate `i` the statement is a
+//! discrete log equality `log_G(C1) == log_Y(C2 - i*G)`; the prover runs
+//! the real protocol for its vote and simulates the other branch, and the
+//! Fiat-Shamir challenge forces the two sub-challenges to sum to it.
+//! `MembershipProof` is the same construction over an arbitrary set of
+//! allowed plaintexts.
+
+use alloc::{vec, vec::Vec};
+use curve25519_dalek::{
+    constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE},
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+use serde::{Deserialize, Serialize};
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+
+use crate::dleq::canonical_scalar;
+use crate::elgamal::{ElGamalCiphertext, ElGamalPublicKey};
+use crate::transcript::Transcript;
+use crate::{CryptoError, Result};
+
+/// Serialized size of a `BinaryProof`
+pub const BINARY_PROOF_LEN: usize = 128;
+
+/// Proof that an ElGamal ciphertext encrypts 0 or 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[repr(C)]
+pub struct BinaryProof {
+    /// Challenge for the `m = 0` branch (32 bytes)
+    pub challenge_0: [u8; 32],
+    /// Challenge for the `m = 1` branch (32 bytes)
+    pub challenge_1: [u8; 32],
+    /// Response for the `m = 0` branch (32 bytes)
+    pub response_0: [u8; 32],
+    /// Response for the `m = 1` branch (32 bytes)
+    pub response_1: [u8; 32],
+}
+
+unsafe impl Pod for BinaryProof {}
+unsafe impl Zeroable for BinaryProof {}
+
+impl BinaryProof {
+    /// Prove that `ciphertext` encrypts `vote` (0 or 1) under `public_key`
+    ///
+    /// `randomness` must be the value passed to `encrypt_with_randomness`.
+    /// `context` binds the proof to where it will be used (e.g. election
+    /// and nullifier) so it can't be replayed elsewhere.
+    pub fn prove(
+        public_key: &ElGamalPublicKey,
+        ciphertext: &ElGamalCiphertext,
+        vote: u64,
+        randomness: &[u8; 32],
+        proof_randomness: &[u8; 32],
+        context: &[u8],
+    ) -> Result<Self> {
+        let mut transcript = statement_transcript(b"elgamal-binary-proof", public_key, ciphertext, context);
+        let proof = prove_disjunction(
+            &mut transcript,
+            public_key,
+            ciphertext,
+            &BINARY_VALUES,
+            vote,
+            randomness,
+            proof_randomness,
+        )?;
+
+        Ok(Self {
+            challenge_0: proof.challenges[0],
+            challenge_1: proof.challenges[1],
+            response_0: proof.responses[0],
+            response_1: proof.responses[1],
+        })
+    }
+
+    /// Verify that `ciphertext` encrypts 0 or 1 under `public_key`
+    pub fn verify(
+        &self,
+        public_key: &ElGamalPublicKey,
+        ciphertext: &ElGamalCiphertext,
+        context: &[u8],
+    ) -> Result<()> {
+        let mut transcript = statement_transcript(b"elgamal-binary-proof", public_key, ciphertext, context);
+        verify_disjunction(
+            &mut transcript,
+            public_key,
+            ciphertext,
+            &BINARY_VALUES,
+            &[self.challenge_0, self.challenge_1],
+            &[self.response_0, self.response_1],
+        )
+    }
+
+    /// Serialize to 128 bytes
+    pub fn to_bytes(&self) -> [u8; BINARY_PROOF_LEN] {
+        let mut bytes = [0u8; BINARY_PROOF_LEN];
+        bytes.copy_from_slice(bytemuck::bytes_of(self));
+        bytes
+    }
+
+    /// Deserialize from 128 bytes
+    pub fn from_bytes(bytes: &[u8; BINARY_PROOF_LEN]) -> Self {
+        bytemuck::pod_read_unaligned(bytes)
+    }
+}
+
+/// Proof that an ElGamal ciphertext encrypts one of a public set of values
+///
+/// Generalises `BinaryProof` to any number of branches, e.g. `{0..=k}`
+/// for the total of an approval ballot or `{1}` for a one-hot ballot.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct MembershipProof {
+    /// One challenge per allowed value
+    pub challenges: Vec<[u8; 32]>,
+    /// One response per allowed value
+    pub responses: Vec<[u8; 32]>,
+}
+
+impl MembershipProof {
+    /// Prove that `ciphertext` encrypts `value`, which must be in `allowed`
+    pub fn prove(
+        public_key: &ElGamalPublicKey,
+        ciphertext: &ElGamalCiphertext,
+        allowed: &[u64],
+        value: u64,
+        randomness: &[u8; 32],
+        proof_randomness: &[u8; 32],
+        context: &[u8],
+    ) -> Result<Self> {
+        let mut transcript = membership_transcript(public_key, ciphertext, allowed, context);
+        prove_disjunction(
+            &mut transcript,
+            public_key,
+            ciphertext,
+            allowed,
+            value,
+            randomness,
+            proof_randomness,
+        )
+    }
+
+    /// Verify that `ciphertext` encrypts some value in `allowed`
+    pub fn verify(
+        &self,
+        public_key: &ElGamalPublicKey,
+        ciphertext: &ElGamalCiphertext,
+        allowed: &[u64],
+        context: &[u8],
+    ) -> Result<()> {
+        let mut transcript = membership_transcript(public_key, ciphertext, allowed, context);
+        verify_disjunction(
+            &mut transcript,
+            public_key,
+            ciphertext,
+            allowed,
+            &self.challenges,
+            &self.responses,
+        )
+    }
+}
+
+/// Encrypt a 0/1 vote and prove it is well formed in one step
+pub fn encrypt_binary_with_proof(
+    public_key: &ElGamalPublicKey,
+    vote: u64,
+    randomness: &[u8; 32],
+    proof_randomness: &[u8; 32],
+    context: &[u8],
+) -> Result<(ElGamalCiphertext, BinaryProof)> {
+    let ciphertext = public_key.encrypt_with_randomness(vote, randomness)?;
+    let proof = BinaryProof::prove(
+        public_key,
+        &ciphertext,
+        vote,
+        randomness,
+        proof_randomness,
+        context,
+    )?;
+    Ok((ciphertext, proof))
+}
+
+const BINARY_VALUES: [u64; 2] = [0, 1];
+
+/// CDS proof that `ciphertext` encrypts `allowed[j]` for some `j`
+///
+/// For every branch `i` the statement is `log_G(C1) == log_Y(C2 - v_i*G)`.
+/// All branches but the real one are simulated with random challenges and
+/// responses; the real challenge is whatever makes them sum to the
+/// Fiat-Shamir challenge.
+fn prove_disjunction(
+    transcript: &mut Transcript,
+    public_key: &ElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    allowed: &[u64],
+    value: u64,
+    randomness: &[u8; 32],
+    proof_randomness: &[u8; 32],
+) -> Result<MembershipProof> {
+    let real = allowed
+        .iter()
+        .position(|&v| v == value)
+        .ok_or(CryptoError::InvalidProof)?;
+
+    let y = public_key.as_point()?;
+    let (c1, c2) = decompress_ciphertext(ciphertext)?;
+    let r = Scalar::from_bytes_mod_order(*randomness);
+
+    let mut challenges = vec![Scalar::ZERO; allowed.len()];
+    let mut responses = vec![Scalar::ZERO; allowed.len()];
+    let mut commitments = Vec::with_capacity(allowed.len());
+    let k = transcript.witness_scalar(b"nonce", r.as_bytes(), proof_randomness);
+
+    for (i, &v) in allowed.iter().enumerate() {
+        if i == real {
+            commitments.push((&k * RISTRETTO_BASEPOINT_TABLE, k * y));
+            continue;
+        }
+
+        // Simulate branch i
+        let mut witness = [0u8; 40];
+        witness[..32].copy_from_slice(r.as_bytes());
+        witness[32..].copy_from_slice(&(i as u64).to_le_bytes());
+        let c_i = transcript.witness_scalar(b"fake-challenge", &witness, proof_randomness);
+        let s_i = transcript.witness_scalar(b"fake-response", &witness, proof_randomness);
+
+        let a = &s_i * RISTRETTO_BASEPOINT_TABLE - c_i * c1;
+        let b = s_i * y - c_i * (c2 - encode_value(v));
+        commitments.push((a, b));
+        challenges[i] = c_i;
+        responses[i] = s_i;
+    }
+
+    append_commitments(transcript, &commitments);
+    let challenge = transcript.challenge_scalar(b"challenge");
+
+    let fake_sum: Scalar = challenges.iter().sum();
+    challenges[real] = challenge - fake_sum;
+    responses[real] = k + challenges[real] * r;
+
+    Ok(MembershipProof {
+        challenges: challenges.iter().map(Scalar::to_bytes).collect(),
+        responses: responses.iter().map(Scalar::to_bytes).collect(),
+    })
+}
+
+fn verify_disjunction(
+    transcript: &mut Transcript,
+    public_key: &ElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    allowed: &[u64],
+    challenges: &[[u8; 32]],
+    responses: &[[u8; 32]],
+) -> Result<()> {
+    if allowed.is_empty() || challenges.len() != allowed.len() || responses.len() != allowed.len() {
+        return Err(CryptoError::InvalidProof);
+    }
+
+    let y = public_key.as_point()?;
+    let (c1, c2) = decompress_ciphertext(ciphertext)?;
+
+    let mut commitments = Vec::with_capacity(allowed.len());
+    let mut challenge_sum = Scalar::ZERO;
+    for ((&v, c_i), s_i) in allowed.iter().zip(challenges).zip(responses) {
+        let c_i = canonical_scalar(c_i)?;
+        let s_i = canonical_scalar(s_i)?;
+
+        // A_i = s_i*G - c_i*C1, B_i = s_i*Y - c_i*(C2 - v_i*G)
+        let a = &s_i * RISTRETTO_BASEPOINT_TABLE - c_i * c1;
+        let b = s_i * y - c_i * (c2 - encode_value(v));
+        commitments.push((a, b));
+        challenge_sum += c_i;
+    }
+
+    append_commitments(transcript, &commitments);
+    if transcript.challenge_scalar(b"challenge") == challenge_sum {
+        Ok(())
+    } else {
+        Err(CryptoError::InvalidProof)
+    }
+}
+
+fn encode_value(value: u64) -> RistrettoPoint {
+    match value {
+        0 => RistrettoPoint::identity(),
+        1 => RISTRETTO_BASEPOINT_POINT,
+        _ => &Scalar::from(value) * RISTRETTO_BASEPOINT_TABLE,
+    }
+}
+
+fn statement_transcript(
+    label: &'static [u8],
+    public_key: &ElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    context: &[u8],
+) -> Transcript {
+    let mut transcript = Transcript::new(label);
+    transcript.append_message(b"context", context);
+    transcript.append_message(b"public-key", &public_key.point);
+    transcript.append_message(b"c1", &ciphertext.c1);
+    transcript.append_message(b"c2", &ciphertext.c2);
+    transcript
+}
+
+fn membership_transcript(
+    public_key: &ElGamalPublicKey,
+    ciphertext: &ElGamalCiphertext,
+    allowed: &[u64],
+    context: &[u8],
+) -> Transcript {
+    let mut transcript = statement_transcript(b"elgamal-membership-proof", public_key, ciphertext, context);
+    transcript.append_u64(b"num-values", allowed.len() as u64);
+    for &value in allowed {
+        transcript.append_u64(b"value", value);
+    }
+    transcript
+}
+
+fn append_commitments(transcript: &mut Transcript, commitments: &[(RistrettoPoint, RistrettoPoint)]) {
+    for (a, b) in commitments {
+        transcript.append_point(b"A", &a.compress());
+        transcript.append_point(b"B", &b.compress());
+    }
+}
+
+fn decompress_ciphertext(ciphertext: &ElGamalCiphertext) -> Result<(RistrettoPoint, RistrettoPoint)> {
+    let c1 = CompressedRistretto(ciphertext.c1)
+        .decompress()
+        .ok_or(CryptoError::InvalidCiphertext)?;
+    let c2 = CompressedRistretto(ciphertext.c2)
+        .decompress()
+        .ok_or(CryptoError::InvalidCiphertext)?;
+    Ok((c1, c2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elgamal::ElGamalKeypair;
+    use rand::{thread_rng, Rng};
+
+    const CONTEXT: &[u8] = b"election-1/nullifier-1";
+
+    #[test]
+    fn test_binary_proof_valid_votes() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        for vote in [0u64, 1] {
+            let (ct, proof) =
+                encrypt_binary_with_proof(&keypair.public, vote, &rng.gen(), &rng.gen(), CONTEXT)
+                    .unwrap();
+            assert!(proof.verify(&keypair.public, &ct, CONTEXT).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_binary_proof_rejects_out_of_range_vote() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let randomness = rng.gen();
+        let ct = keypair.public.encrypt_with_randomness(1000, &randomness).unwrap();
+
+        // Honest prover refuses
+        assert_eq!(
+            BinaryProof::prove(&keypair.public, &ct, 1000, &randomness, &rng.gen(), CONTEXT),
+            Err(CryptoError::InvalidProof)
+        );
+
+        // A proof for a 1-vote doesn't carry over to a 1000-vote
+        let (_, proof) =
+            encrypt_binary_with_proof(&keypair.public, 1, &randomness, &rng.gen(), CONTEXT).unwrap();
+        assert!(proof.verify(&keypair.public, &ct, CONTEXT).is_err());
+    }
+
+    #[test]
+    fn test_binary_proof_cheating_prover() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        // Lie about the vote: encrypt 2 but prove as if it were 1
+        let randomness = rng.gen();
+        let ct = keypair.public.encrypt_with_randomness(2, &randomness).unwrap();
+        let proof =
+            BinaryProof::prove(&keypair.public, &ct, 1, &randomness, &rng.gen(), CONTEXT).unwrap();
+
+        assert_eq!(
+            proof.verify(&keypair.public, &ct, CONTEXT),
+            Err(CryptoError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn test_binary_proof_bound_to_context() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let (ct, proof) =
+            encrypt_binary_with_proof(&keypair.public, 1, &rng.gen(), &rng.gen(), CONTEXT).unwrap();
+
+        assert!(proof.verify(&keypair.public, &ct, b"another-election").is_err());
+    }
+
+    #[test]
+    fn test_binary_proof_bound_to_public_key() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let other = ElGamalKeypair::generate(&mut rng);
+
+        let (ct, proof) =
+            encrypt_binary_with_proof(&keypair.public, 0, &rng.gen(), &rng.gen(), CONTEXT).unwrap();
+
+        assert!(proof.verify(&other.public, &ct, CONTEXT).is_err());
+    }
+
+    #[test]
+    fn test_membership_proof() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+        let allowed = [0u64, 1, 2, 3];
+
+        let randomness = rng.gen();
+        let ct = keypair.public.encrypt_with_randomness(2, &randomness).unwrap();
+        let proof =
+            MembershipProof::prove(&keypair.public, &ct, &allowed, 2, &randomness, &rng.gen(), CONTEXT)
+                .unwrap();
+
+        assert!(proof.verify(&keypair.public, &ct, &allowed, CONTEXT).is_ok());
+        assert!(proof.verify(&keypair.public, &ct, &[0, 1, 2], CONTEXT).is_err());
+    }
+
+    #[test]
+    fn test_membership_proof_single_value() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let randomness = rng.gen();
+        let ct = keypair.public.encrypt_with_randomness(1, &randomness).unwrap();
+        let proof =
+            MembershipProof::prove(&keypair.public, &ct, &[1], 1, &randomness, &rng.gen(), CONTEXT)
+                .unwrap();
+
+        assert!(proof.verify(&keypair.public, &ct, &[1], CONTEXT).is_ok());
+        assert!(proof.verify(&keypair.public, &ct, &[0], CONTEXT).is_err());
+    }
+
+    #[test]
+    fn test_membership_proof_value_outside_set() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let randomness = rng.gen();
+        let ct = keypair.public.encrypt_with_randomness(5, &randomness).unwrap();
+
+        assert_eq!(
+            MembershipProof::prove(&keypair.public, &ct, &[0, 1], 5, &randomness, &rng.gen(), CONTEXT),
+            Err(CryptoError::InvalidProof)
+        );
+
+        // Claiming a different in-set value doesn't produce a valid proof
+        let proof =
+            MembershipProof::prove(&keypair.public, &ct, &[0, 1], 1, &randomness, &rng.gen(), CONTEXT)
+                .unwrap();
+        assert!(proof.verify(&keypair.public, &ct, &[0, 1], CONTEXT).is_err());
+    }
+
+    #[test]
+    fn test_binary_proof_serialization() {
+        let mut rng = thread_rng();
+        let keypair = ElGamalKeypair::generate(&mut rng);
+
+        let (ct, proof) =
+            encrypt_binary_with_proof(&keypair.public, 1, &rng.gen(), &rng.gen(), CONTEXT).unwrap();
+        let decoded = BinaryProof::from_bytes(&proof.to_bytes());
+
+        assert_eq!(proof, decoded);
+        assert!(decoded.verify(&keypair.public, &ct, CONTEXT).is_ok());
+    }
+}
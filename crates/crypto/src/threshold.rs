@@ -0,0 +1,544 @@
+//! Threshold ElGamal with a Feldman-verifiable distributed key generation
+//!
+//! Each of `n` trustees acts as a dealer: it samples a random polynomial of
+//! degree `t - 1`, broadcasts Feldman commitments to its coefficients and
+//! privately sends every other trustee an evaluation of the polynomial.
+//! Recipients check each share against the dealer's commitments and sum the
+//! valid ones into their long-term key share. The joint election key is the
+//! sum of every qualified dealer's constant-term commitment, and no party
+//! ever holds the matching secret.
+//!
+//! To decrypt, any `t` trustees publish `D_i = s_i * C1` together with a
+//! Chaum-Pedersen proof that `D_i` used the same `s_i` as their public
+//! share. The shares are combined with Lagrange coefficients into
+//! `s * C1`, which yields `m * G = C2 - s * C1`.
+
+use alloc::vec::Vec;
+use curve25519_dalek::{
+    constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE},
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+use serde::{Deserialize, Serialize};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::dleq::DleqProof;
+use crate::dlog::DiscreteLog;
+use crate::elgamal::{ElGamalCiphertext, ElGamalPublicKey};
+use crate::transcript::Transcript;
+use crate::{CryptoError, Result};
+
+/// t-of-n threshold parameters
+///
+/// Trustee indices are 1-based: `1..=participants`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct ThresholdParams {
+    /// Minimum number of trustees required to decrypt
+    pub threshold: u16,
+    /// Total number of trustees
+    pub participants: u16,
+}
+
+impl ThresholdParams {
+    /// Create validated parameters (1 <= threshold <= participants)
+    pub fn new(threshold: u16, participants: u16) -> Result<Self> {
+        if threshold == 0 || threshold > participants {
+            return Err(CryptoError::InvalidThreshold);
+        }
+
+        Ok(Self {
+            threshold,
+            participants,
+        })
+    }
+
+    fn check_index(&self, index: u16) -> Result<()> {
+        if index == 0 || index > self.participants {
+            return Err(CryptoError::InvalidShare);
+        }
+        Ok(())
+    }
+}
+
+/// Feldman commitments `A_k = a_k * G` to a dealer's polynomial
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct PolynomialCommitment {
+    /// Compressed commitments, constant term first
+    pub coefficients: Vec<[u8; 32]>,
+}
+
+impl PolynomialCommitment {
+    /// The dealer's contribution to the joint public key (`A_0`)
+    pub fn public_contribution(&self) -> Result<RistrettoPoint> {
+        let first = self.coefficients.first().ok_or(CryptoError::InvalidShare)?;
+        decompress(first, CryptoError::InvalidPublicKey)
+    }
+
+    /// Evaluate the committed polynomial in the exponent at `index`
+    pub fn evaluate(&self, index: u16) -> Result<RistrettoPoint> {
+        let x = Scalar::from(index as u64);
+        let mut acc = RistrettoPoint::identity();
+
+        // Horner's rule from the highest degree coefficient down
+        for coefficient in self.coefficients.iter().rev() {
+            acc = acc * x + decompress(coefficient, CryptoError::InvalidPublicKey)?;
+        }
+
+        Ok(acc)
+    }
+
+    /// Check a received share against these commitments
+    ///
+    /// A failing share is grounds for a complaint against the dealer,
+    /// who is then excluded from the qualified set.
+    pub fn verify_share(&self, share: &SecretShare) -> Result<()> {
+        let expected = self.evaluate(share.recipient)?;
+        let actual = &share.as_scalar()? * RISTRETTO_BASEPOINT_TABLE;
+
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(CryptoError::InvalidShare)
+        }
+    }
+}
+
+/// A dealer's polynomial evaluated at one recipient's index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct SecretShare {
+    /// Index of the dealer that produced this share
+    pub dealer: u16,
+    /// Index of the trustee this share is for
+    pub recipient: u16,
+    /// Share value f(recipient) (32 bytes)
+    pub scalar: [u8; 32],
+}
+
+impl SecretShare {
+    fn as_scalar(&self) -> Result<Scalar> {
+        Option::from(Scalar::from_canonical_bytes(self.scalar)).ok_or(CryptoError::InvalidShare)
+    }
+}
+
+/// One trustee's dealing in the DKG
+#[derive(Debug, Clone)]
+pub struct DkgDealer {
+    index: u16,
+    params: ThresholdParams,
+    coefficients: Vec<Scalar>,
+}
+
+impl DkgDealer {
+    /// Create a dealer from `threshold` random 32-byte values
+    ///
+    /// Randomness should be generated off-chain for security. The first
+    /// value becomes this dealer's secret contribution; running a single
+    /// dealer is equivalent to a trusted-dealer Shamir split.
+    pub fn new(index: u16, params: ThresholdParams, randomness: &[[u8; 32]]) -> Result<Self> {
+        params.check_index(index)?;
+        if randomness.len() != params.threshold as usize {
+            return Err(CryptoError::InvalidThreshold);
+        }
+
+        let coefficients = randomness
+            .iter()
+            .map(|bytes| Scalar::from_bytes_mod_order(*bytes))
+            .collect();
+
+        Ok(Self {
+            index,
+            params,
+            coefficients,
+        })
+    }
+
+    /// Commitments to broadcast to all trustees
+    pub fn commitment(&self) -> PolynomialCommitment {
+        PolynomialCommitment {
+            coefficients: self
+                .coefficients
+                .iter()
+                .map(|a| (a * RISTRETTO_BASEPOINT_TABLE).compress().to_bytes())
+                .collect(),
+        }
+    }
+
+    /// Private share to send to `recipient`
+    pub fn share_for(&self, recipient: u16) -> Result<SecretShare> {
+        self.params.check_index(recipient)?;
+
+        let x = Scalar::from(recipient as u64);
+        let value = self
+            .coefficients
+            .iter()
+            .rev()
+            .fold(Scalar::ZERO, |acc, a| acc * x + a);
+
+        Ok(SecretShare {
+            dealer: self.index,
+            recipient,
+            scalar: value.to_bytes(),
+        })
+    }
+}
+
+/// A trustee's long-term share of the joint election secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrusteeKey {
+    /// 1-based trustee index
+    pub index: u16,
+    /// Secret share s_i (32 bytes)
+    pub share: [u8; 32],
+}
+
+impl TrusteeKey {
+    /// Combine the shares received from every qualified dealer
+    ///
+    /// Each share is verified against its dealer's commitments first, so a
+    /// single malicious dealer can't silently corrupt the key.
+    pub fn from_dealings(
+        params: &ThresholdParams,
+        index: u16,
+        dealings: &[(PolynomialCommitment, SecretShare)],
+    ) -> Result<Self> {
+        params.check_index(index)?;
+        if dealings.is_empty() {
+            return Err(CryptoError::InsufficientShares);
+        }
+
+        let mut dealers: Vec<u16> = Vec::with_capacity(dealings.len());
+        let mut sum = Scalar::ZERO;
+        for (commitment, share) in dealings {
+            if share.recipient != index
+                || commitment.coefficients.len() != params.threshold as usize
+                || dealers.contains(&share.dealer)
+            {
+                return Err(CryptoError::InvalidShare);
+            }
+            commitment.verify_share(share)?;
+
+            dealers.push(share.dealer);
+            sum += share.as_scalar()?;
+        }
+
+        Ok(Self {
+            index,
+            share: sum.to_bytes(),
+        })
+    }
+
+    /// Public verification share `s_i * G`
+    pub fn public_share(&self) -> [u8; 32] {
+        (&self.as_scalar() * RISTRETTO_BASEPOINT_TABLE)
+            .compress()
+            .to_bytes()
+    }
+
+    /// Compute this trustee's decryption share for a ciphertext
+    pub fn partial_decrypt(
+        &self,
+        ciphertext: &ElGamalCiphertext,
+        randomness: &[u8; 32],
+    ) -> Result<PartialDecryption> {
+        let c1 = decompress(&ciphertext.c1, CryptoError::InvalidCiphertext)?;
+        let s = self.as_scalar();
+
+        let mut transcript = partial_decryption_transcript(self.index, ciphertext);
+        let proof = DleqProof::prove(
+            &mut transcript,
+            &RISTRETTO_BASEPOINT_POINT,
+            &c1,
+            &s,
+            randomness,
+        );
+
+        Ok(PartialDecryption {
+            index: self.index,
+            share: (s * c1).compress().to_bytes(),
+            proof,
+        })
+    }
+
+    fn as_scalar(&self) -> Scalar {
+        Scalar::from_bytes_mod_order(self.share)
+    }
+}
+
+/// A trustee's decryption share `D_i = s_i * C1` with proof of correctness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct PartialDecryption {
+    /// 1-based trustee index
+    pub index: u16,
+    /// Decryption share D_i (32 bytes)
+    pub share: [u8; 32],
+    /// Proof that log_G(Y_i) == log_C1(D_i)
+    pub proof: DleqProof,
+}
+
+impl PartialDecryption {
+    /// Verify this share against the trustee's public verification share
+    pub fn verify(&self, ciphertext: &ElGamalCiphertext, public_share: &[u8; 32]) -> Result<()> {
+        let c1 = decompress(&ciphertext.c1, CryptoError::InvalidCiphertext)?;
+        let y_i = decompress(public_share, CryptoError::InvalidPublicKey)?;
+        let d_i = decompress(&self.share, CryptoError::InvalidShare)?;
+
+        let mut transcript = partial_decryption_transcript(self.index, ciphertext);
+        self.proof
+            .verify(&mut transcript, &RISTRETTO_BASEPOINT_POINT, &c1, &y_i, &d_i)
+    }
+}
+
+/// Joint election public key from the qualified dealers' commitments
+pub fn joint_public_key(commitments: &[PolynomialCommitment]) -> Result<ElGamalPublicKey> {
+    if commitments.is_empty() {
+        return Err(CryptoError::InsufficientShares);
+    }
+
+    let mut sum = RistrettoPoint::identity();
+    for commitment in commitments {
+        sum += commitment.public_contribution()?;
+    }
+
+    Ok(ElGamalPublicKey {
+        point: sum.compress().to_bytes(),
+    })
+}
+
+/// Public verification share of trustee `index`, derived from the
+/// qualified dealers' commitments
+pub fn trustee_public_share(commitments: &[PolynomialCommitment], index: u16) -> Result<[u8; 32]> {
+    let mut sum = RistrettoPoint::identity();
+    for commitment in commitments {
+        sum += commitment.evaluate(index)?;
+    }
+
+    Ok(sum.compress().to_bytes())
+}
+
+/// Combine at least `threshold` verified partial decryptions into `m * G`
+pub fn combine_partial_decryptions(
+    params: &ThresholdParams,
+    ciphertext: &ElGamalCiphertext,
+    partials: &[PartialDecryption],
+) -> Result<RistrettoPoint> {
+    let mut indices: Vec<u16> = Vec::with_capacity(partials.len());
+    for partial in partials {
+        params.check_index(partial.index)?;
+        if indices.contains(&partial.index) {
+            return Err(CryptoError::InvalidShare);
+        }
+        indices.push(partial.index);
+    }
+    if indices.len() < params.threshold as usize {
+        return Err(CryptoError::InsufficientShares);
+    }
+
+    let mut s_c1 = RistrettoPoint::identity();
+    for partial in partials {
+        let d_i = decompress(&partial.share, CryptoError::InvalidShare)?;
+        s_c1 += lagrange_coefficient(partial.index, &indices) * d_i;
+    }
+
+    let c2 = decompress(&ciphertext.c2, CryptoError::InvalidCiphertext)?;
+    Ok(c2 - s_c1)
+}
+
+/// Combine partial decryptions and recover the plaintext tally
+pub fn decrypt_with_partials(
+    params: &ThresholdParams,
+    ciphertext: &ElGamalCiphertext,
+    partials: &[PartialDecryption],
+    table: &DiscreteLog,
+) -> Result<u64> {
+    let m_point = combine_partial_decryptions(params, ciphertext, partials)?;
+    table.solve(&m_point)
+}
+
+/// Lagrange coefficient for `index` evaluated at zero over `indices`
+pub fn lagrange_coefficient(index: u16, indices: &[u16]) -> Scalar {
+    let x_i = Scalar::from(index as u64);
+    let mut numerator = Scalar::ONE;
+    let mut denominator = Scalar::ONE;
+
+    for &j in indices.iter().filter(|&&j| j != index) {
+        let x_j = Scalar::from(j as u64);
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+
+    numerator * denominator.invert()
+}
+
+fn partial_decryption_transcript(index: u16, ciphertext: &ElGamalCiphertext) -> Transcript {
+    let mut transcript = Transcript::new(b"threshold-partial-decryption");
+    transcript.append_u64(b"trustee", index as u64);
+    transcript.append_message(b"c1", &ciphertext.c1);
+    transcript.append_message(b"c2", &ciphertext.c2);
+    transcript
+}
+
+fn decompress(bytes: &[u8; 32], error: CryptoError) -> Result<RistrettoPoint> {
+    CompressedRistretto(*bytes).decompress().ok_or(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+
+    struct Dkg {
+        params: ThresholdParams,
+        commitments: Vec<PolynomialCommitment>,
+        trustees: Vec<TrusteeKey>,
+    }
+
+    fn run_dkg(threshold: u16, participants: u16) -> Dkg {
+        let mut rng = thread_rng();
+        let params = ThresholdParams::new(threshold, participants).unwrap();
+
+        let dealers: Vec<DkgDealer> = (1..=participants)
+            .map(|i| {
+                let randomness: Vec<[u8; 32]> = (0..threshold).map(|_| rng.gen()).collect();
+                DkgDealer::new(i, params, &randomness).unwrap()
+            })
+            .collect();
+        let commitments: Vec<PolynomialCommitment> =
+            dealers.iter().map(|d| d.commitment()).collect();
+
+        let trustees = (1..=participants)
+            .map(|i| {
+                let dealings: Vec<_> = dealers
+                    .iter()
+                    .zip(&commitments)
+                    .map(|(d, c)| (c.clone(), d.share_for(i).unwrap()))
+                    .collect();
+                TrusteeKey::from_dealings(&params, i, &dealings).unwrap()
+            })
+            .collect();
+
+        Dkg {
+            params,
+            commitments,
+            trustees,
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert_eq!(ThresholdParams::new(0, 3), Err(CryptoError::InvalidThreshold));
+        assert_eq!(ThresholdParams::new(4, 3), Err(CryptoError::InvalidThreshold));
+        assert!(ThresholdParams::new(3, 3).is_ok());
+    }
+
+    #[test]
+    fn test_public_shares_match_commitments() {
+        let dkg = run_dkg(2, 3);
+
+        for trustee in &dkg.trustees {
+            assert_eq!(
+                trustee.public_share(),
+                trustee_public_share(&dkg.commitments, trustee.index).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_threshold_decryption_of_tally() {
+        let mut rng = thread_rng();
+        let dkg = run_dkg(3, 5);
+        let public_key = joint_public_key(&dkg.commitments).unwrap();
+
+        let votes = [1u64, 0, 1, 1, 0, 1];
+        let mut tally = public_key.encrypt_with_randomness(0, &rng.gen()).unwrap();
+        for vote in votes {
+            let ct = public_key.encrypt_with_randomness(vote, &rng.gen()).unwrap();
+            tally = tally.add(&ct).unwrap();
+        }
+
+        // Any 3 of the 5 trustees can decrypt
+        let partials: Vec<PartialDecryption> = [1usize, 3, 4]
+            .iter()
+            .map(|&i| dkg.trustees[i].partial_decrypt(&tally, &rng.gen()).unwrap())
+            .collect();
+        for partial in &partials {
+            let public_share = dkg.trustees[partial.index as usize - 1].public_share();
+            assert!(partial.verify(&tally, &public_share).is_ok());
+        }
+
+        let table = DiscreteLog::new(64);
+        assert_eq!(decrypt_with_partials(&dkg.params, &tally, &partials, &table).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_insufficient_partials() {
+        let mut rng = thread_rng();
+        let dkg = run_dkg(3, 5);
+        let public_key = joint_public_key(&dkg.commitments).unwrap();
+        let ct = public_key.encrypt_with_randomness(1, &rng.gen()).unwrap();
+
+        let partials: Vec<PartialDecryption> = dkg.trustees[..2]
+            .iter()
+            .map(|t| t.partial_decrypt(&ct, &rng.gen()).unwrap())
+            .collect();
+
+        assert_eq!(
+            combine_partial_decryptions(&dkg.params, &ct, &partials),
+            Err(CryptoError::InsufficientShares)
+        );
+    }
+
+    #[test]
+    fn test_duplicate_partials_rejected() {
+        let mut rng = thread_rng();
+        let dkg = run_dkg(2, 3);
+        let public_key = joint_public_key(&dkg.commitments).unwrap();
+        let ct = public_key.encrypt_with_randomness(1, &rng.gen()).unwrap();
+
+        let partial = dkg.trustees[0].partial_decrypt(&ct, &rng.gen()).unwrap();
+
+        assert_eq!(
+            combine_partial_decryptions(&dkg.params, &ct, &[partial, partial]),
+            Err(CryptoError::InvalidShare)
+        );
+    }
+
+    #[test]
+    fn test_forged_partial_rejected() {
+        let mut rng = thread_rng();
+        let dkg = run_dkg(2, 3);
+        let public_key = joint_public_key(&dkg.commitments).unwrap();
+        let ct = public_key.encrypt_with_randomness(1, &rng.gen()).unwrap();
+
+        // Trustee 2 answers with trustee 1's share under its own index
+        let mut forged = dkg.trustees[0].partial_decrypt(&ct, &rng.gen()).unwrap();
+        forged.index = 2;
+
+        assert!(forged.verify(&ct, &dkg.trustees[1].public_share()).is_err());
+    }
+
+    #[test]
+    fn test_bad_dealer_share_detected() {
+        let mut rng = thread_rng();
+        let params = ThresholdParams::new(2, 3).unwrap();
+        let dealer = DkgDealer::new(1, params, &[rng.gen(), rng.gen()]).unwrap();
+        let commitment = dealer.commitment();
+
+        let mut share = dealer.share_for(2).unwrap();
+        assert!(commitment.verify_share(&share).is_ok());
+
+        share.scalar = Scalar::from(7u64).to_bytes();
+        assert_eq!(commitment.verify_share(&share), Err(CryptoError::InvalidShare));
+        assert!(TrusteeKey::from_dealings(&params, 2, &[(commitment, share)]).is_err());
+    }
+
+    #[test]
+    fn test_no_single_trustee_holds_joint_secret() {
+        let dkg = run_dkg(2, 3);
+        let public_key = joint_public_key(&dkg.commitments).unwrap();
+
+        for trustee in &dkg.trustees {
+            assert_ne!(trustee.public_share(), public_key.point);
+        }
+    }
+}
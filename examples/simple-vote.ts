@@ -5,12 +5,18 @@
  * 1. Initialize a private election
  * 2. Cast an encrypted vote
  * 3. Verify the vote was cast
+ *
+ * Expects `ELECTION` (an mpl-gov-micro election), `ELECTION_AUTHORITY`
+ * (its authority's keypair file), `MPC_PUBLIC_KEY`,
+ * `VOTER_MERKLE_ROOT` and `BALLOT`, a ballot prepared with
+ * `privacy-layer-client` for the provider wallet, with its Merkle proof.
  */
 
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { Connection, PublicKey } from "@solana/web3.js";
-import { PrivacyLayerClient, generateVoterSecret } from "../sdk/src";
+import { Connection, Keypair, PublicKey } from "@solana/web3.js";
+import * as fs from "fs";
+import { EncryptedBallot, PrivacyLayerClient, hexToBytes } from "../sdk/src";
 
 async function main() {
   // Setup connection
@@ -29,44 +35,72 @@ async function main() {
 
   console.log("🗳️  Simple Voting Example\n");
 
-  // Step 1: Create a mock election account
-  const mockElection = anchor.web3.Keypair.generate();
+  // Step 1: Load the mpl-gov-micro election and its authority, who must
+  // co-sign the private election
+  const election = new PublicKey(process.env.ELECTION!);
+  const electionAuthority = Keypair.fromSecretKey(
+    Uint8Array.from(JSON.parse(fs.readFileSync(process.env.ELECTION_AUTHORITY!, "utf8")))
+  );
 
   // Step 2: Initialize private election
   console.log("📋 Initializing private election...");
 
+  const now = Math.floor(Date.now() / 1000);
   const electionConfig = {
-    electionId: new Uint8Array(32), // Random election ID
-    mpcPublicKey: new Uint8Array(32), // Mock MPC public key
-    voterMerkleRoot: new Uint8Array(32), // Mock voter registry
-    endsAt: Date.now() / 1000 + 3600, // Ends in 1 hour
-    numOptions: 3, // 3 voting options
+    mpcPublicKey: hexToBytes(process.env.MPC_PUBLIC_KEY!), // Tally key
+    voterMerkleRoot: hexToBytes(process.env.VOTER_MERKLE_ROOT!), // Voter registry
+    startsAt: now + 60, // Opens in 1 minute
+    endsAt: now + 3600, // Ends in 1 hour
+    numOptions: 2, // 2 voting options
+    ballotRule: { singleChoice: {} },
+    eligibilityMode: { public: {} },
+    allowRevoting: false,
+    voteWeighting: { equal: {} },
+    auditPeriod: 0,
+    maxRelayerFee: 0,
   };
 
   const privateElection = await client.initializePrivateElection(
-    mockElection.publicKey,
+    election,
+    electionAuthority,
     electionConfig
   );
+  await client.openElection(privateElection, electionConfig.startsAt);
 
   console.log("✅ Election created!");
   console.log("   Address:", privateElection.toString());
   console.log("");
 
-  // Step 3: Generate voter secret
-  console.log("🔑 Generating voter secret...");
-  const voterSecret = generateVoterSecret();
-  console.log("✅ Voter secret generated (keep this private!)");
+  // Step 3: Load the ballot, encrypted and proven with privacy-layer-client
+  console.log("🔑 Loading prepared ballot...");
+  const prepared = JSON.parse(fs.readFileSync(process.env.BALLOT!, "utf8"));
+  const ballot: EncryptedBallot = {
+    nullifier: hexToBytes(prepared.nullifier),
+    commitment: hexToBytes(prepared.commitment),
+    ciphertexts: prepared.ciphertexts.map((ciphertext: any) => ({
+      c1: hexToBytes(ciphertext.c1),
+      c2: hexToBytes(ciphertext.c2),
+    })),
+    weight: 1,
+    proof: {
+      slotProofs: prepared.slot_proofs.map(hexToBytes),
+      totalChallenges: prepared.total_challenges.map(hexToBytes),
+      totalResponses: prepared.total_responses.map(hexToBytes),
+    },
+  };
+  console.log("✅ Ballot loaded");
   console.log("");
 
-  // Step 4: Cast vote
-  console.log("🗳️  Casting vote for Option 1...");
+  // Step 4: Cast vote once voting opens
+  console.log("🗳️  Casting vote...");
+  await new Promise((resolve) => setTimeout(resolve, 61_000));
 
-  const voteChoice = 0; // Vote for option 0
-  const signature = await client.prepareAndCastVote(
-    privateElection,
-    voteChoice,
-    voterSecret
-  );
+  const signature = await client.castEncryptedVote(privateElection, ballot, {
+    merkleProof: {
+      leafIndex: prepared.leaf_index,
+      siblings: prepared.siblings.map(hexToBytes),
+    },
+  });
 
   console.log("✅ Vote cast successfully!");
   console.log("   Transaction:", signature);
@@ -2,6 +2,21 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { PrivacyLayer } from "../target/types/privacy_layer";
 import { expect } from "chai";
+import {
+  MAX_OPTIONS,
+  MIN_OPTIONS,
+  TestElection,
+  castTestVote,
+  ensureGovConfig,
+  expectError,
+  findTallyResultPda,
+  fundVoters,
+  hex,
+  initializeTestElection,
+  loadElection,
+  openTestElection,
+  recastTestVote,
+} from "./test-helpers";
 
 describe("privacy-layer", () => {
   // Configure the client to use the local cluster
@@ -10,742 +25,295 @@ describe("privacy-layer", () => {
 
   const program = anchor.workspace.PrivacyLayer as Program<PrivacyLayer>;
 
-  // Test accounts and data
-  let mockElection: anchor.web3.Keypair;
-  let privateElection: anchor.web3.PublicKey;
-  let nullifierSet: anchor.web3.PublicKey;
-  let testKeys: any;
-
-  // Helper function to generate test data
-  function generateTestData() {
-    const electionId = new Uint8Array(32);
-    for (let i = 0; i < 32; i++) electionId[i] = i;
-
-    const mpcPublicKey = new Uint8Array(32);
-    for (let i = 0; i < 32; i++) mpcPublicKey[i] = (i * 2) % 256;
-
-    const merkleRoot = new Uint8Array(32);
-    for (let i = 0; i < 32; i++) merkleRoot[i] = (i * 3) % 256;
-
-    return {
-      electionId: Array.from(electionId),
-      mpcPublicKey: Array.from(mpcPublicKey),
-      merkleRoot: Array.from(merkleRoot),
-    };
-  }
-
-  // Helper function to generate nullifier
-  function generateNullifier(seed: number): number[] {
-    const nullifier = new Uint8Array(32);
-    for (let i = 0; i < 32; i++) {
-      nullifier[i] = (seed + i) % 256;
-    }
-    return Array.from(nullifier);
-  }
-
-  // Helper function to generate ciphertext
-  function generateCiphertext(vote: number, randomness: number): { c1: number[], c2: number[] } {
-    const c1 = new Uint8Array(32);
-    const c2 = new Uint8Array(32);
-
-    for (let i = 0; i < 32; i++) {
-      c1[i] = (randomness + i) % 256;
-      c2[i] = (randomness + vote + i) % 256;
-    }
-
-    return {
-      c1: Array.from(c1),
-      c2: Array.from(c2),
-    };
-  }
-
-  beforeEach(async () => {
-    // Create a new mock election for each test
-    mockElection = anchor.web3.Keypair.generate();
-
-    // Derive PDAs
-    [privateElection] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("private_election"), mockElection.publicKey.toBuffer()],
-      program.programId
-    );
-
-    [nullifierSet] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("nullifier_set"), mockElection.publicKey.toBuffer()],
-      program.programId
-    );
-
-    testKeys = generateTestData();
+  // The fixture election every test in this suite builds on, in order:
+  // created, opened, voted in, tallied
+  let election: TestElection;
+
+  before(async () => {
+    await ensureGovConfig(program);
+    election = loadElection("vote", program.programId);
+    await fundVoters(program, election);
   });
 
   describe("Initialize Private Election", () => {
-    it("Should initialize a private election successfully", async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 86400); // 24 hours
-      const numOptions = 3;
+    it("Should reject an out-of-range number of options", async () => {
+      for (const numOptions of [MIN_OPTIONS - 1, MAX_OPTIONS + 1]) {
+        await expectError(
+          initializeTestElection(program, election, provider.wallet.publicKey, { numOptions }),
+          "InvalidNumOptions"
+        );
+      }
+    });
 
-      await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          numOptions
-        )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
-          authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
+    it("Should reject a co-signer other than the election authority", async () => {
+      await expectError(
+        initializeTestElection(program, election, provider.wallet.publicKey, {
+          electionAuthority: anchor.web3.Keypair.generate(),
+        }),
+        "Unauthorized"
+      );
+    });
+
+    it("Should reject a start time in the past", async () => {
+      await expectError(
+        initializeTestElection(program, election, provider.wallet.publicKey, {
+          startsInSeconds: -60,
+        }),
+        "InvalidSchedule"
+      );
+    });
+
+    it("Should initialize a private election successfully", async () => {
+      await initializeTestElection(program, election, provider.wallet.publicKey);
 
       // Fetch and verify the created election
-      const electionData = await program.account.privateElection.fetch(privateElection);
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
 
       expect(electionData.authority.toString()).to.equal(provider.wallet.publicKey.toString());
-      expect(electionData.election.toString()).to.equal(mockElection.publicKey.toString());
+      expect(electionData.tallySubmitter.toString()).to.equal(provider.wallet.publicKey.toString());
+      expect(electionData.guardian.toString()).to.equal(provider.wallet.publicKey.toString());
+      expect(electionData.election.toString()).to.equal(election.election.toString());
       expect(electionData.totalEncryptedVotes.toNumber()).to.equal(0);
       expect(electionData.tallyRequested).to.be.false;
       expect(electionData.tallyFinalized).to.be.false;
-      expect(electionData.numOptions).to.equal(numOptions);
+      expect(electionData.numOptions).to.equal(election.fixture.num_options);
+      expect(electionData.allowRevoting).to.be.true;
+      expect(electionData.ballotRule).to.have.property("singleChoice");
+      expect(electionData.eligibilityMode).to.have.property("public");
     });
 
     it("Should store correct MPC public key", async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 86400);
-
-      await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          2
-        )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
-          authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        
-        .rpc();
-
-      const electionData = await program.account.privateElection.fetch(privateElection);
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
 
       // Verify MPC public key is stored correctly
-      expect(electionData.mpcPublicKey).to.deep.equal(testKeys.mpcPublicKey);
-      expect(electionData.voterMerkleRoot).to.deep.equal(testKeys.merkleRoot);
+      expect(electionData.mpcPublicKey).to.deep.equal(hex(election.fixture.mpc_public_key));
+      expect(electionData.voterMerkleRoot).to.deep.equal(hex(election.fixture.voter_merkle_root));
+
+      // Derived from the private election's address, not passed in
+      expect(electionData.electionId).to.deep.equal(Array.from(election.privateElection.toBytes()));
+      expect(electionData.electionId).to.deep.equal(hex(election.fixture.election_id));
     });
 
     it("Should initialize with correct election status", async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 86400);
-
-      await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          4
-        )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
-          authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        
-        .rpc();
-
-      const electionData = await program.account.privateElection.fetch(privateElection);
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
 
-      // Should be in Active status
-      expect(electionData.status).to.have.property("active");
+      // Voting stays closed until the election is opened
+      expect(electionData.status).to.have.property("pending");
     });
 
-    it("Should initialize nullifier set correctly", async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 86400);
+    it("Should initialize the encrypted tally correctly", async () => {
+      const tally = await program.account.encryptedTally.fetch(election.encryptedTally);
 
-      await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          2
-        )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
-          authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        
-        .rpc();
-
-      // Fetch nullifier set
-      const nullifierSetData = await program.account.nullifierSet.fetch(nullifierSet);
+      expect(tally.election.toString()).to.equal(election.privateElection.toString());
+      expect(tally.numOptions).to.equal(election.fixture.num_options);
+      expect(tally.ballots.toNumber()).to.equal(0);
+      expect(tally.totalWeight.toNumber()).to.equal(0);
+    });
 
-      expect(nullifierSetData.election.toString()).to.equal(mockElection.publicKey.toString());
-      expect(nullifierSetData.nullifiers).to.be.an("array").that.is.empty;
+    it("Should reject initializing the same election twice", async () => {
+      await expectError(
+        initializeTestElection(program, election, provider.wallet.publicKey),
+        "already in use"
+      );
     });
   });
 
   describe("Cast Encrypted Vote", () => {
-    // Setup: Initialize election before each vote test
-    beforeEach(async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 86400);
+    it("Should reject votes before the election is opened", async () => {
+      await expectError(
+        castTestVote(program, election, election.fixture.ballots[0]),
+        "ElectionNotActive"
+      );
+    });
 
-      await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          3
-        )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
-          authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        
-        .rpc();
+    it("Should open the election", async () => {
+      await openTestElection(program, election);
+
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
+      expect(electionData.status).to.have.property("active");
     });
 
     it("Should cast an encrypted vote successfully", async () => {
-      const nullifier = generateNullifier(1);
-      const { c1, c2 } = generateCiphertext(0, 100);
-      const commitment = generateNullifier(2);
-
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-        program.programId
+      const ballot = election.fixture.ballots[0];
+      const voter = election.voters[ballot.voter];
+      const { nullifier, nullifierRecord, encryptedVote } = await castTestVote(
+        program,
+        election,
+        ballot
       );
 
-      await program.methods
-        .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-        .accounts({
-          privateElection,
-          encryptedVote,
-          nullifierSet,
-          voter: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
-
       // Verify vote was recorded
       const voteData = await program.account.encryptedVote.fetch(encryptedVote);
 
-      expect(voteData.election.toString()).to.equal(privateElection.toString());
-      expect(voteData.ciphertextC1).to.deep.equal(c1);
-      expect(voteData.ciphertextC2).to.deep.equal(c2);
+      expect(voteData.election.toString()).to.equal(election.privateElection.toString());
+      expect(voteData.ciphertexts).to.deep.equal(
+        ballot.ciphertexts.map((ciphertext: any) => ({
+          c1: hex(ciphertext.c1),
+          c2: hex(ciphertext.c2),
+        }))
+      );
       expect(voteData.nullifier).to.deep.equal(nullifier);
+      expect(voteData.commitment).to.deep.equal(hex(ballot.commitment));
+      expect(voteData.revision).to.equal(0);
+      expect(voteData.weight.toNumber()).to.equal(1);
+
+      // The nullifier is marked spent in its own record
+      const recordData = await program.account.nullifierRecord.fetch(nullifierRecord);
+      expect(recordData.election.toString()).to.equal(election.privateElection.toString());
+      expect(recordData.nullifier).to.deep.equal(nullifier);
+      expect(recordData.rentPayer.toString()).to.equal(voter.publicKey.toString());
     });
 
-    it("Should increment vote count after casting", async () => {
-      const nullifier = generateNullifier(3);
-      const { c1, c2 } = generateCiphertext(1, 200);
-      const commitment = generateNullifier(4);
-
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-        program.programId
-      );
-
-      // Get initial count
-      let electionData = await program.account.privateElection.fetch(privateElection);
-      const initialCount = electionData.totalEncryptedVotes.toNumber();
-
-      // Cast vote
-      await program.methods
-        .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-        .accounts({
-          privateElection,
-          encryptedVote,
-          nullifierSet,
-          voter: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
+    it("Should increment vote count and tally after casting", async () => {
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
+      expect(electionData.totalEncryptedVotes.toNumber()).to.equal(1);
 
-      // Verify count increased
-      electionData = await program.account.privateElection.fetch(privateElection);
-      expect(electionData.totalEncryptedVotes.toNumber()).to.equal(initialCount + 1);
+      const tally = await program.account.encryptedTally.fetch(election.encryptedTally);
+      expect(tally.ballots.toNumber()).to.equal(1);
+      expect(tally.totalWeight.toNumber()).to.equal(1);
     });
 
-    it("Should store nullifier in nullifier set", async () => {
-      const nullifier = generateNullifier(5);
-      const { c1, c2 } = generateCiphertext(2, 300);
-      const commitment = generateNullifier(6);
-
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-        program.programId
+    it("Should prevent double voting with same nullifier", async () => {
+      // The nullifier record already exists
+      await expectError(
+        castTestVote(program, election, election.fixture.ballots[0]),
+        "NullifierAlreadyUsed"
       );
-
-      await program.methods
-        .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-        .accounts({
-          privateElection,
-          encryptedVote,
-          nullifierSet,
-          voter: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
-
-      // Check nullifier was added
-      const nullifierSetData = await program.account.nullifierSet.fetch(nullifierSet);
-      expect(nullifierSetData.nullifiers).to.have.lengthOf(1);
-      expect(nullifierSetData.nullifiers[0]).to.deep.equal(nullifier);
     });
 
-    it("Should prevent double voting with same nullifier", async () => {
-      const nullifier = generateNullifier(7);
-      const { c1, c2 } = generateCiphertext(0, 400);
-      const commitment = generateNullifier(8);
-
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-        program.programId
+    it("Should reject a nullifier not derived from the signer", async () => {
+      // Voter 3 is eligible but signs with voter 1's nullifier
+      await expectError(
+        castTestVote(program, election, election.fixture.ballots[1], { voterIndex: 3 }),
+        "InvalidNullifier"
       );
+    });
 
-      // First vote should succeed
-      await program.methods
-        .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-        .accounts({
-          privateElection,
-          encryptedVote,
-          nullifierSet,
-          voter: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
+    it("Should reject a commitment that doesn't match the ballot", async () => {
+      const other = election.fixture.ballots[2];
 
-      // Second vote with same nullifier should fail
-      const { c1: c1_2, c2: c2_2 } = generateCiphertext(1, 500);
-
-      try {
-        await program.methods
-          .castEncryptedVote(c1_2, c2_2, nullifier, commitment, null, null, null)
-          .accounts({
-            privateElection,
-            encryptedVote,
-            nullifierSet,
-            voter: provider.wallet.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
-          .rpc();
+      await expectError(
+        castTestVote(program, election, election.fixture.ballots[1], {
+          commitment: hex(other.commitment),
+        }),
+        "InvalidCommitment"
+      );
+    });
 
-        expect.fail("Should have thrown error for duplicate nullifier");
-      } catch (error) {
-        // The PDA derivation prevents double voting - account already exists
-        expect(error.message).to.match(/account|already|exists|initialized/i);
-      }
+    it("Should reject tampered ciphertexts", async () => {
+      // Swapping the options moves the vote without fixing the proof
+      const ballot = election.fixture.ballots[1];
+      const swapped = [...ballot.ciphertexts].reverse().map((ciphertext: any) => ({
+        c1: hex(ciphertext.c1),
+        c2: hex(ciphertext.c2),
+      }));
+
+      await expectError(
+        castTestVote(program, election, ballot, { ciphertexts: swapped }),
+        "InvalidVoteProof"
+      );
     });
 
     it("Should allow multiple votes with different nullifiers", async () => {
-      // Cast 3 votes with different nullifiers
-      for (let i = 0; i < 3; i++) {
-        const nullifier = generateNullifier(10 + i);
-        const { c1, c2 } = generateCiphertext(i, 600 + i * 100);
-        const commitment = generateNullifier(20 + i);
-
-        const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-          [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-          program.programId
-        );
-
-        await program.methods
-          .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-          .accounts({
-            privateElection,
-            encryptedVote,
-            nullifierSet,
-            voter: provider.wallet.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
-          .rpc();
+      for (const ballot of election.fixture.ballots.slice(1)) {
+        await castTestVote(program, election, ballot);
       }
 
       // Verify all votes were recorded
-      const electionData = await program.account.privateElection.fetch(privateElection);
-      expect(electionData.totalEncryptedVotes.toNumber()).to.equal(3);
-
-      const nullifierSetData = await program.account.nullifierSet.fetch(nullifierSet);
-      expect(nullifierSetData.nullifiers).to.have.lengthOf(3);
-    });
-
-    it("Should store correct timestamp for vote", async () => {
-      const nullifier = generateNullifier(13);
-      const { c1, c2 } = generateCiphertext(1, 900);
-      const commitment = generateNullifier(14);
-
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-        program.programId
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
+      expect(electionData.totalEncryptedVotes.toNumber()).to.equal(
+        election.fixture.ballots.length
       );
 
-      const beforeTime = Date.now() / 1000;
-
-      await program.methods
-        .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-        .accounts({
-          privateElection,
-          encryptedVote,
-          nullifierSet,
-          voter: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
+      const tally = await program.account.encryptedTally.fetch(election.encryptedTally);
+      expect(tally.ballots.toNumber()).to.equal(election.fixture.ballots.length);
+    });
 
-      const afterTime = Date.now() / 1000;
+    it("Should replace a ballot when re-voting", async () => {
+      const ballot = election.fixture.recasts[0];
+      const { encryptedVote } = await recastTestVote(program, election, ballot);
 
       const voteData = await program.account.encryptedVote.fetch(encryptedVote);
-      const voteTime = voteData.timestamp.toNumber();
+      expect(voteData.revision).to.equal(1);
+      expect(voteData.commitment).to.deep.equal(hex(ballot.commitment));
 
-      // Timestamp should be within reasonable range
-      expect(voteTime).to.be.at.least(beforeTime - 5);
-      expect(voteTime).to.be.at.most(afterTime + 5);
+      // Still one ballot per voter
+      const tally = await program.account.encryptedTally.fetch(election.encryptedTally);
+      expect(tally.ballots.toNumber()).to.equal(election.fixture.ballots.length);
     });
+  });
 
-    /* ============================================================
-     * ADVANCED TESTS (Commented out - require additional setup)
-     * ============================================================ */
-
-    /*
-    it("Should reject vote after election ends", async () => {
-      // Create election that ends immediately
-      const mockElectionExpired = anchor.web3.Keypair.generate();
-      const [privateElectionExpired] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("private_election"), mockElectionExpired.publicKey.toBuffer()],
-        program.programId
-      );
-      const [nullifierSetExpired] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("nullifier_set"), mockElectionExpired.publicKey.toBuffer()],
-        program.programId
-      );
-
-      const endsAt = new anchor.BN(Date.now() / 1000 - 100); // Already expired
-
-      await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          2
-        )
-        .accounts({
-          privateElection: privateElectionExpired,
-          nullifierSet: nullifierSetExpired,
-          election: mockElectionExpired.publicKey,
-          authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        
-        .rpc();
-
-      const nullifier = generateNullifier(100);
-      const { c1, c2 } = generateCiphertext(0, 1000);
-      const commitment = generateNullifier(101);
-
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElectionExpired.toBuffer(), Buffer.from(nullifier)],
-        program.programId
-      );
-
-      try {
-        await program.methods
-          .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-          .accounts({
-            privateElection: privateElectionExpired,
-            encryptedVote,
-            nullifierSet: nullifierSetExpired,
-            voter: provider.wallet.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
+  describe("Tally", () => {
+    it("Should reject a tally request while voting is open", async () => {
+      await expectError(
+        program.methods
+          .requestTally()
+          .accountsPartial({
+            privateElection: election.privateElection,
+            authority: provider.wallet.publicKey,
           })
-          .rpc();
-
-        expect.fail("Should have rejected vote for expired election");
-      } catch (error) {
-        expect(error.message).to.include("ElectionEnded");
-      }
-    });
-
-    it("Should verify ZK proof when provided", async () => {
-      // This would require:
-      // 1. Circom circuit implementation
-      // 2. Proof generation (snarkjs)
-      // 3. Verification key in program
-
-      const nullifier = generateNullifier(200);
-      const { c1, c2 } = generateCiphertext(0, 2000);
-      const commitment = generateNullifier(201);
-
-      // Mock ZK proof components
-      const zkProofA = Array.from(new Uint8Array(32).fill(1));
-      const zkProofB = Array.from(new Uint8Array(64).fill(2));
-      const zkProofC = Array.from(new Uint8Array(32).fill(3));
-
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-        program.programId
+          .rpc(),
+        "ElectionNotEnded"
       );
-
-      // This will fail in MVP because ZK verification is not implemented
-      // In production, this would verify the proof
-      await program.methods
-        .castEncryptedVote(c1, c2, nullifier, commitment, zkProofA, zkProofB, zkProofC)
-        .accounts({
-          privateElection,
-          encryptedVote,
-          nullifierSet,
-          voter: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
-    });
-
-    it("Should reject invalid ZK proof", async () => {
-      // Would test that invalid proofs are rejected
-      // Requires full ZK proof verification implementation
     });
-    */
-  });
-
-  describe("Election State Management", () => {
-    it("Should retrieve election data correctly", async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 3600);
-      const numOptions = 5;
 
+    it("Should close the election and request the tally", async () => {
       await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          numOptions
-        )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
-          authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
+        .closeElection()
+        .accountsPartial({
+          privateElection: election.privateElection,
+          closer: provider.wallet.publicKey,
         })
         .rpc();
-
-      const electionData = await program.account.privateElection.fetch(privateElection);
-
-      expect(electionData).to.have.property("authority");
-      expect(electionData).to.have.property("mpcPublicKey");
-      expect(electionData).to.have.property("voterMerkleRoot");
-      expect(electionData).to.have.property("totalEncryptedVotes");
-      expect(electionData).to.have.property("status");
-    });
-
-    it("Should track election statistics correctly", async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 3600);
-
       await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          2
-        )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
+        .requestTally()
+        .accountsPartial({
+          privateElection: election.privateElection,
           authority: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
         })
-        
         .rpc();
 
-      // Cast multiple votes
-      const voteCount = 5;
-      for (let i = 0; i < voteCount; i++) {
-        const nullifier = generateNullifier(50 + i);
-        const { c1, c2 } = generateCiphertext(i % 2, 1000 + i * 10);
-        const commitment = generateNullifier(60 + i);
-
-        const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-          [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-          program.programId
-        );
-
-        await program.methods
-          .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-          .accounts({
-            privateElection,
-            encryptedVote,
-            nullifierSet,
-            voter: provider.wallet.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
-          .rpc();
-      }
-
-      const electionData = await program.account.privateElection.fetch(privateElection);
-      expect(electionData.totalEncryptedVotes.toNumber()).to.equal(voteCount);
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
+      expect(electionData.status).to.have.property("ended");
+      expect(electionData.tallyRequested).to.be.true;
     });
-  });
-
-  describe("Security Tests", () => {
-    beforeEach(async () => {
-      const endsAt = new anchor.BN(Date.now() / 1000 + 86400);
 
-      await program.methods
-        .initializePrivateElection(
-          testKeys.electionId,
-          testKeys.mpcPublicKey,
-          testKeys.merkleRoot,
-          endsAt,
-          3
+    const submitTally = (counts: number[]) =>
+      program.methods
+        .submitTallyResult(
+          counts.map((count) => new anchor.BN(count)),
+          election.fixture.tally.proofs.map((proof: any) => ({
+            challenge: hex(proof.challenge),
+            response: hex(proof.response),
+          }))
         )
-        .accounts({
-          privateElection,
-          nullifierSet,
-          election: mockElection.publicKey,
-          authority: provider.wallet.publicKey,
+        .accountsPartial({
+          privateElection: election.privateElection,
+          encryptedTally: election.encryptedTally,
+          tallyResult: findTallyResultPda(election.privateElection, program.programId)[0],
+          submitter: provider.wallet.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
-        
         .rpc();
-    });
-
-    it("Should maintain nullifier uniqueness", async () => {
-      const votes = 10;
-      const nullifiers = new Set();
-
-      for (let i = 0; i < votes; i++) {
-        const nullifier = generateNullifier(70 + i);
-        nullifiers.add(JSON.stringify(nullifier));
-
-        const { c1, c2 } = generateCiphertext(i % 3, 1500 + i * 10);
-        const commitment = generateNullifier(80 + i);
-
-        const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-          [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-          program.programId
-        );
-
-        await program.methods
-          .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-          .accounts({
-            privateElection,
-            encryptedVote,
-            nullifierSet,
-            voter: provider.wallet.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
-          .rpc();
-      }
 
-      // Verify all nullifiers are unique
-      expect(nullifiers.size).to.equal(votes);
+    it("Should reject counts that don't decrypt the aggregate", async () => {
+      const counts = [...election.fixture.tally.counts].reverse();
 
-      const nullifierSetData = await program.account.nullifierSet.fetch(nullifierSet);
-      expect(nullifierSetData.nullifiers).to.have.lengthOf(votes);
+      await expectError(submitTally(counts), "InvalidMpcSignature");
     });
 
-    it("Should prevent replay attacks", async () => {
-      const nullifier = generateNullifier(90);
-      const { c1, c2 } = generateCiphertext(0, 2000);
-      const commitment = generateNullifier(91);
+    it("Should finalize with the decrypted counts", async () => {
+      await submitTally(election.fixture.tally.counts);
 
-      const [encryptedVote] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("encrypted_vote"), privateElection.toBuffer(), Buffer.from(nullifier)],
-        program.programId
+      const [tallyResult] = findTallyResultPda(election.privateElection, program.programId);
+      const resultData = await program.account.tallyResult.fetch(tallyResult);
+      expect(resultData.counts.map((count) => count.toNumber())).to.deep.equal(
+        election.fixture.tally.counts
       );
 
-      // First vote
-      await program.methods
-        .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-        .accounts({
-          privateElection,
-          encryptedVote,
-          nullifierSet,
-          voter: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .rpc();
-
-      // Attempt replay with same data
-      try {
-        await program.methods
-          .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-          .accounts({
-            privateElection,
-            encryptedVote,
-            nullifierSet,
-            voter: provider.wallet.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
-          .rpc();
-
-        expect.fail("Should have prevented replay attack");
-      } catch (error) {
-        // Should fail because account already exists or nullifier used
-        expect(error).to.exist;
-      }
-    });
-  });
-
-  /* ============================================================
-   * FUTURE TESTS (Commented out - require additional features)
-   * ============================================================ */
-
-  /*
-  describe("MPC Tally", () => {
-    it("Should request MPC tally", async () => {
-      // Would test:
-      // 1. CPI to Arcium MXE program
-      // 2. Tally request recorded
-      // 3. Status updated
-    });
-
-    it("Should submit and verify MPC tally results", async () => {
-      // Would test:
-      // 1. Submit tally from MPC network
-      // 2. Verify threshold signatures
-      // 3. Verify ZK proof of correct tally
-      // 4. Update final results
-    });
-
-    it("Should reject invalid tally proof", async () => {
-      // Would test rejection of invalid proofs
-    });
-  });
-
-  describe("Homomorphic Operations", () => {
-    it("Should aggregate votes homomorphically", async () => {
-      // Would test:
-      // 1. Cast multiple encrypted votes
-      // 2. Aggregate using homomorphic addition
-      // 3. Decrypt aggregate
-      // 4. Verify result matches sum
-    });
-  });
-
-  describe("Integration with mpl-gov-micro", () => {
-    it("Should call governance program with results", async () => {
-      // Would test CPI to governance program
-    });
-
-    it("Should verify governance program authorization", async () => {
-      // Would test authorization checks
+      const electionData = await program.account.privateElection.fetch(election.privateElection);
+      expect(electionData.status).to.have.property("finalized");
+      expect(electionData.tallyFinalized).to.be.true;
     });
   });
-  */
 });
@@ -2,75 +2,65 @@
  * Test Helper Utilities
  *
  * Common functions used across test suites
+ *
+ * Ballots need real encryptions and proofs, so they come from
+ * `test-vectors/ballots.json`, produced with `privacy-layer-client` and
+ * checked by its tests. Each fixture election has a matching mpl-gov-micro
+ * `Election` account in `tests/fixtures`, loaded into the test validator
+ * by `Anchor.toml` and owned by the fixture's `gov_program`.
  */
 
 import * as anchor from "@coral-xyz/anchor";
-import { PublicKey } from "@solana/web3.js";
+import { Keypair, PublicKey } from "@solana/web3.js";
 import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import * as fs from "fs";
+import * as path from "path";
 
-/**
- * Generate deterministic test data for elections
- */
-export function generateElectionData(seed: number = 0) {
-  const electionId = new Uint8Array(32);
-  for (let i = 0; i < 32; i++) electionId[i] = (seed + i) % 256;
+/** Bounds on `num_options`, as in `state::private_election` */
+export const MIN_OPTIONS = 2;
+export const MAX_OPTIONS = 16;
 
-  const mpcPublicKey = new Uint8Array(32);
-  for (let i = 0; i < 32; i++) mpcPublicKey[i] = ((seed + i) * 2) % 256;
+export const fixtures = JSON.parse(
+  fs.readFileSync(path.join(__dirname, "../../test-vectors/ballots.json"), "utf8")
+);
 
-  const merkleRoot = new Uint8Array(32);
-  for (let i = 0; i < 32; i++) merkleRoot[i] = ((seed + i) * 3) % 256;
-
-  return {
-    electionId: Array.from(electionId),
-    mpcPublicKey: Array.from(mpcPublicKey),
-    merkleRoot: Array.from(merkleRoot),
-  };
+/**
+ * A fixture election with its PDAs and keypairs
+ */
+export interface TestElection {
+  fixture: any;
+  election: PublicKey;
+  electionAuthority: Keypair;
+  privateElection: PublicKey;
+  encryptedTally: PublicKey;
+  voters: Keypair[];
 }
 
 /**
- * Generate a nullifier from a seed
+ * Decode a hex string as instruction bytes
  */
-export function generateNullifier(seed: number): number[] {
-  const nullifier = new Uint8Array(32);
-  for (let i = 0; i < 32; i++) {
-    nullifier[i] = (seed + i * 7) % 256;
-  }
-  return Array.from(nullifier);
+export function hex(value: string): number[] {
+  return Array.from(Buffer.from(value, "hex"));
 }
 
 /**
- * Generate mock ElGamal ciphertext
+ * Load a fixture election by name
  */
-export function generateCiphertext(
-  vote: number,
-  randomness: number
-): { c1: number[]; c2: number[] } {
-  const c1 = new Uint8Array(32);
-  const c2 = new Uint8Array(32);
-
-  for (let i = 0; i < 32; i++) {
-    c1[i] = (randomness + i * 5) % 256;
-    c2[i] = (randomness + vote + i * 11) % 256;
-  }
+export function loadElection(name: string, programId: PublicKey): TestElection {
+  const fixture = fixtures.elections[name];
+  const election = new PublicKey(fixture.election);
 
   return {
-    c1: Array.from(c1),
-    c2: Array.from(c2),
+    fixture,
+    election,
+    electionAuthority: Keypair.fromSeed(Buffer.from(fixture.authority_seed, "hex")),
+    privateElection: findPrivateElectionPda(election, programId)[0],
+    encryptedTally: findEncryptedTallyPda(election, programId)[0],
+    voters: fixture.voters.map((voter: any) => Keypair.fromSeed(Buffer.from(voter.seed, "hex"))),
   };
 }
 
-/**
- * Generate a commitment
- */
-export function generateCommitment(seed: number): number[] {
-  const commitment = new Uint8Array(32);
-  for (let i = 0; i < 32; i++) {
-    commitment[i] = (seed + i * 13) % 256;
-  }
-  return Array.from(commitment);
-}
-
 /**
  * Find private election PDA
  */
@@ -85,14 +75,48 @@ export function findPrivateElectionPda(
 }
 
 /**
- * Find nullifier set PDA
+ * Find encrypted tally PDA
  */
-export function findNullifierSetPda(
+export function findEncryptedTallyPda(
   election: PublicKey,
   programId: PublicKey
 ): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("nullifier_set"), election.toBuffer()],
+    [Buffer.from("encrypted_tally"), election.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Find nullifier record PDA
+ */
+export function findNullifierRecordPda(
+  privateElection: PublicKey,
+  nullifier: number[],
+  programId: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("nullifier"), privateElection.toBuffer(), Buffer.from(nullifier)],
+    programId
+  );
+}
+
+/**
+ * Find the ballot buffer PDA `submitter` writes the ballot of `nullifier` to
+ */
+export function findBallotBufferPda(
+  privateElection: PublicKey,
+  submitter: PublicKey,
+  nullifier: number[],
+  programId: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("ballot_buffer"),
+      privateElection.toBuffer(),
+      submitter.toBuffer(),
+      Buffer.from(nullifier),
+    ],
     programId
   );
 }
@@ -116,95 +140,377 @@ export function findEncryptedVotePda(
 }
 
 /**
- * Initialize a test election
+ * Find tally result PDA
+ */
+export function findTallyResultPda(
+  privateElection: PublicKey,
+  programId: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("tally_result"), privateElection.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Find gov config PDA
+ */
+export function findGovConfigPda(programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from("gov_config")], programId);
+}
+
+/**
+ * Register the fixture governance program, once per validator
+ *
+ * The provider wallet is the program's upgrade authority under
+ * `anchor test`.
+ */
+export async function ensureGovConfig(program: Program<any>) {
+  const [govConfig] = findGovConfigPda(program.programId);
+  if (await program.provider.connection.getAccountInfo(govConfig)) {
+    return;
+  }
+
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  );
+  await program.methods
+    .initializeGovConfig(new PublicKey(fixtures.gov_program))
+    .accountsPartial({
+      govConfig,
+      authority: program.provider.publicKey,
+      program: program.programId,
+      programData,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .rpc();
+}
+
+/**
+ * Fund the fixture voters, who pay for their own vote accounts
+ */
+export async function fundVoters(program: Program<any>, election: TestElection) {
+  const connection = program.provider.connection;
+  for (const voter of election.voters) {
+    const signature = await connection.requestAirdrop(
+      voter.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await connection.confirmTransaction(signature, "confirmed");
+  }
+}
+
+/**
+ * Initialize a fixture election
  */
 export async function initializeTestElection(
-  program: Program,
-  mockElection: anchor.web3.Keypair,
+  program: Program<any>,
+  election: TestElection,
   authority: PublicKey,
   options: {
     numOptions?: number;
+    startsInSeconds?: number;
     endsInSeconds?: number;
-    seed?: number;
+    electionAuthority?: Keypair;
   } = {}
 ) {
   const {
-    numOptions = 3,
+    numOptions = election.fixture.num_options,
+    startsInSeconds = 60,
     endsInSeconds = 86400,
-    seed = 0,
+    electionAuthority = election.electionAuthority,
   } = options;
 
-  const testData = generateElectionData(seed);
-  const endsAt = new anchor.BN(Date.now() / 1000 + endsInSeconds);
+  await program.methods
+    .initializePrivateElection(
+      hex(election.fixture.mpc_public_key),
+      hex(election.fixture.voter_merkle_root),
+      getFutureTimestamp(startsInSeconds),
+      getFutureTimestamp(endsInSeconds),
+      numOptions,
+      { singleChoice: {} },
+      { public: {} },
+      election.fixture.allow_revoting,
+      { equal: {} },
+      new anchor.BN(0),
+      new anchor.BN(0)
+    )
+    .accountsPartial({
+      privateElection: election.privateElection,
+      encryptedTally: election.encryptedTally,
+      govConfig: findGovConfigPda(program.programId)[0],
+      election: election.election,
+      electionAuthority: electionAuthority.publicKey,
+      authority,
+      payer: program.provider.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .signers([electionAuthority])
+    .rpc();
+}
+
+/**
+ * Open a fixture election and wait until it accepts votes
+ */
+export async function openTestElection(program: Program<any>, election: TestElection) {
+  const startsAt = getFutureTimestamp(2);
 
-  const [privateElection] = findPrivateElectionPda(
-    mockElection.publicKey,
-    program.programId
-  );
-  const [nullifierSet] = findNullifierSetPda(
-    mockElection.publicKey,
+  await program.methods
+    .openElection(startsAt)
+    .accountsPartial({
+      privateElection: election.privateElection,
+      authority: program.provider.publicKey,
+    })
+    .rpc();
+
+  await waitForClock(program, startsAt.toNumber());
+}
+
+/** Options, total proof entries and siblings per `writeBallotBuffer`, as in the SDK */
+const OPTIONS_PER_WRITE = 4;
+const TOTALS_PER_WRITE = 14;
+const SIBLINGS_PER_WRITE = 28;
+
+/**
+ * Write a fixture ballot and a public voter's Merkle proof (see
+ * `merkleProof`) to `submitter`'s buffer for `nullifier`
+ *
+ * A buffer left over from a ballot that failed to cast is closed first.
+ * `ciphertexts` replaces the ballot's to build invalid ones.
+ */
+export async function writeTestBallot(
+  program: Program<any>,
+  election: TestElection,
+  ballot: any,
+  submitter: Keypair,
+  nullifier: number[],
+  proof: { leafIndex: number; siblings: number[][] } | null,
+  ciphertexts?: any[]
+) {
+  const [ballotBuffer] = findBallotBufferPda(
+    election.privateElection,
+    submitter.publicKey,
+    nullifier,
     program.programId
   );
 
+  if (await program.provider.connection.getAccountInfo(ballotBuffer)) {
+    await program.methods
+      .closeBallotBuffer()
+      .accountsPartial({ ballotBuffer, submitter: submitter.publicKey })
+      .signers([submitter])
+      .rpc();
+  }
   await program.methods
-    .initializePrivateElection(
-      testData.electionId,
-      testData.mpcPublicKey,
-      testData.merkleRoot,
-      endsAt,
-      numOptions
-    )
-    .accounts({
-      privateElection,
-      nullifierSet,
-      election: mockElection.publicKey,
-      authority,
+    .createBallotBuffer(nullifier, proof ? proof.siblings.length : 0)
+    .accountsPartial({
+      privateElection: election.privateElection,
+      ballotBuffer,
+      submitter: submitter.publicKey,
       systemProgram: anchor.web3.SystemProgram.programId,
     })
+    .signers([submitter])
     .rpc();
 
-  return { privateElection, nullifierSet, testData };
+  const options = ciphertexts ?? ballot.ciphertexts.map((ciphertext: any) => ({
+    c1: hex(ciphertext.c1),
+    c2: hex(ciphertext.c2),
+  }));
+  const chunks: any[] = [];
+  for (let first = 0; first < options.length; first += OPTIONS_PER_WRITE) {
+    chunks.push({
+      options: {
+        first,
+        ciphertexts: options.slice(first, first + OPTIONS_PER_WRITE),
+        slotProofs: ballot.slot_proofs.slice(first, first + OPTIONS_PER_WRITE).map(hex),
+      },
+    });
+  }
+  for (let first = 0; first < ballot.total_challenges.length; first += TOTALS_PER_WRITE) {
+    chunks.push({
+      totalProof: {
+        first,
+        challenges: ballot.total_challenges.slice(first, first + TOTALS_PER_WRITE).map(hex),
+        responses: ballot.total_responses.slice(first, first + TOTALS_PER_WRITE).map(hex),
+      },
+    });
+  }
+  const siblings = proof ? proof.siblings : [];
+  for (let first = 0; first < siblings.length; first += SIBLINGS_PER_WRITE) {
+    chunks.push({
+      merkleProof: {
+        leafIndex: proof!.leafIndex,
+        first,
+        siblings: siblings.slice(first, first + SIBLINGS_PER_WRITE),
+      },
+    });
+  }
+  for (const chunk of chunks) {
+    await program.methods
+      .writeBallotBuffer(nullifier, chunk)
+      .accountsPartial({
+        privateElection: election.privateElection,
+        ballotBuffer,
+        submitter: submitter.publicKey,
+      })
+      .signers([submitter])
+      .rpc();
+  }
+
+  return ballotBuffer;
+}
+
+/**
+ * Merkle inclusion proof of a fixture voter
+ */
+export function merkleProof(voter: any) {
+  return {
+    leafIndex: voter.leaf_index,
+    siblings: voter.siblings.map(hex),
+  };
 }
 
 /**
- * Cast a test vote
+ * Write a fixture ballot to the voter's buffer and cast it
+ *
+ * `overrides` replaces parts of the ballot or the signer to build
+ * invalid votes.
  */
 export async function castTestVote(
-  program: Program,
-  privateElection: PublicKey,
-  nullifierSet: PublicKey,
-  voter: PublicKey,
-  options: {
-    vote?: number;
-    seed?: number;
+  program: Program<any>,
+  election: TestElection,
+  ballot: any,
+  overrides: {
+    voter?: Keypair;
+    voterIndex?: number;
+    nullifier?: number[];
+    commitment?: number[];
+    ciphertexts?: any[];
   } = {}
 ) {
-  const { vote = 0, seed = 1 } = options;
-
-  const nullifier = generateNullifier(seed);
-  const { c1, c2 } = generateCiphertext(vote, seed * 100);
-  const commitment = generateCommitment(seed);
+  const voterIndex = overrides.voterIndex ?? ballot.voter;
+  const voter = overrides.voter ?? election.voters[voterIndex];
+  const nullifier =
+    overrides.nullifier ?? hex(election.fixture.voters[ballot.voter].nullifier);
+  const ballotBuffer = await writeTestBallot(
+    program,
+    election,
+    ballot,
+    voter,
+    nullifier,
+    merkleProof(election.fixture.voters[voterIndex]),
+    overrides.ciphertexts
+  );
 
+  const [nullifierRecord] = findNullifierRecordPda(
+    election.privateElection,
+    nullifier,
+    program.programId
+  );
   const [encryptedVote] = findEncryptedVotePda(
-    privateElection,
+    election.privateElection,
     nullifier,
     program.programId
   );
 
   await program.methods
-    .castEncryptedVote(c1, c2, nullifier, commitment, null, null, null)
-    .accounts({
-      privateElection,
+    .castEncryptedVote(
+      nullifier,
+      overrides.commitment ?? hex(ballot.commitment),
+      new anchor.BN(1),
+      null,
+      null,
+      null
+    )
+    .accountsPartial({
+      privateElection: election.privateElection,
+      nullifierRecord,
       encryptedVote,
-      nullifierSet,
-      voter,
+      ballotBuffer,
+      encryptedTally: election.encryptedTally,
+      verifyingKey: null,
+      voter: voter.publicKey,
       systemProgram: anchor.web3.SystemProgram.programId,
     })
+    .signers([voter])
+    .rpc();
+
+  return { nullifier, nullifierRecord, encryptedVote };
+}
+
+/**
+ * Write a fixture re-vote to the voter's buffer and replace their ballot
+ * with it
+ */
+export async function recastTestVote(program: Program<any>, election: TestElection, ballot: any) {
+  const voter = election.voters[ballot.voter];
+  const nullifier = hex(election.fixture.voters[ballot.voter].nullifier);
+  const ballotBuffer = await writeTestBallot(
+    program,
+    election,
+    ballot,
+    voter,
+    nullifier,
+    merkleProof(election.fixture.voters[ballot.voter])
+  );
+
+  const [encryptedVote] = findEncryptedVotePda(
+    election.privateElection,
+    nullifier,
+    program.programId
+  );
+
+  await program.methods
+    .recastEncryptedVote(
+      nullifier,
+      hex(ballot.commitment),
+      new anchor.BN(1),
+      null,
+      null,
+      null
+    )
+    .accountsPartial({
+      privateElection: election.privateElection,
+      encryptedVote,
+      ballotBuffer,
+      encryptedTally: election.encryptedTally,
+      verifyingKey: null,
+      voter: voter.publicKey,
+    })
+    .signers([voter])
     .rpc();
 
   return { nullifier, encryptedVote };
 }
 
+/**
+ * Expect a transaction to fail with the given program error
+ */
+export async function expectError(transaction: Promise<any>, code: string) {
+  try {
+    await transaction;
+  } catch (error) {
+    expect(String(error) + (error.logs ?? []).join("\n")).to.include(code);
+    return;
+  }
+  expect.fail(`Expected ${code}`);
+}
+
+/**
+ * Wait until the cluster clock reaches `timestamp`
+ */
+export async function waitForClock(program: Program<any>, timestamp: number) {
+  const connection = program.provider.connection;
+  for (;;) {
+    const blockTime = await connection.getBlockTime(await connection.getSlot());
+    if (blockTime !== null && blockTime >= timestamp) {
+      return;
+    }
+    await delay(500);
+  }
+}
+
 /**
  * Delay for a specified number of milliseconds
  */
@@ -49,4 +49,70 @@ pub enum PrivacyError {
 
     #[msg("Arithmetic overflow")]
     ArithmeticOverflow,
+
+    #[msg("Vote validity proof failed verification")]
+    InvalidVoteProof,
+
+    #[msg("Ballot does not match the election's options")]
+    InvalidBallotSize,
+
+    #[msg("Invalid ballot rule for this election")]
+    InvalidBallotRule,
+
+    #[msg("Tally counts do not match the number of votes cast")]
+    TallyMismatch,
+
+    #[msg("Nullifier was not derived from the voter")]
+    InvalidNullifier,
+
+    #[msg("Invalid election schedule")]
+    InvalidSchedule,
+
+    #[msg("Voting has not started yet")]
+    VotingNotStarted,
+
+    #[msg("Number of options out of range")]
+    InvalidNumOptions,
+
+    #[msg("Commitment does not match the ballot")]
+    InvalidCommitment,
+
+    #[msg("Re-voting is not enabled for this election")]
+    RevotingDisabled,
+
+    #[msg("Invalid vote weight")]
+    InvalidWeight,
+
+    #[msg("Audit period has not ended yet")]
+    AuditPeriodNotOver,
+
+    #[msg("Vote accounts must be closed first")]
+    VoteAccountsOpen,
+
+    #[msg("Relayed votes require anonymous eligibility")]
+    RelayRequiresAnonymous,
+
+    #[msg("Relayer fee exceeds the election maximum")]
+    RelayerFeeTooHigh,
+
+    #[msg("Not a valid mpl-gov-micro election")]
+    InvalidGovElection,
+
+    #[msg("Account has an unknown layout version")]
+    UnsupportedAccountVersion,
+
+    #[msg("Relayer fee vault can't pay the fee and stay rent-exempt")]
+    RelayerFeeVaultUnderfunded,
+
+    #[msg("Anonymous elections need a verifying key; public elections can't have one")]
+    VerifyingKeyMismatch,
+
+    #[msg("Results were already published")]
+    ResultsAlreadyPublished,
+
+    #[msg("Ballot parts must be written in order")]
+    BallotWrittenOutOfOrder,
+
+    #[msg("Ballot buffer has not been completely written")]
+    BallotIncomplete,
 }
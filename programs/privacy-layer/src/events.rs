@@ -0,0 +1,216 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    BallotCiphertext, BallotRule, EligibilityMode, EncryptedVote, TallyResult, VoteWeighting,
+};
+
+/// A private election was initialized
+#[event]
+pub struct PrivateElectionCreated {
+    /// The private election account
+    pub private_election: Pubkey,
+    /// The public election in mpl-gov-micro
+    pub election: Pubkey,
+    /// Authority that manages the election
+    pub authority: Pubkey,
+    /// Election identifier used in nullifiers
+    pub election_id: [u8; 32],
+    /// ElGamal public key ballots are encrypted under
+    pub mpc_public_key: [u8; 32],
+    /// Root of the voter Merkle tree
+    pub voter_merkle_root: [u8; 32],
+    /// Timestamp when voting opens
+    pub starts_at: i64,
+    /// Timestamp when voting ends
+    pub ends_at: i64,
+    /// Number of vote options
+    pub num_options: u8,
+    /// How many options a ballot may select
+    pub ballot_rule: BallotRule,
+    /// How voters prove eligibility
+    pub eligibility_mode: EligibilityMode,
+    /// Whether ballots may be replaced until `ends_at`
+    pub allow_revoting: bool,
+    /// Where ballot weights come from
+    pub vote_weighting: VoteWeighting,
+}
+
+/// An encrypted ballot was cast
+#[event]
+pub struct EncryptedVoteCast {
+    /// The private election the ballot belongs to
+    pub private_election: Pubkey,
+    /// The spent nullifier
+    pub nullifier: [u8; 32],
+    /// One ElGamal ciphertext per option
+    pub ciphertexts: Vec<BallotCiphertext>,
+    /// Receipt commitment from `privacy_crypto::commit_ballot`
+    pub commitment: [u8; 32],
+    /// Weight the ballot is counted with
+    pub weight: u64,
+    /// Zero-based position of the ballot in cast order
+    pub vote_index: u64,
+    /// Timestamp when the ballot was cast
+    pub timestamp: i64,
+}
+
+impl EncryptedVoteCast {
+    /// The event for a newly stored `vote`, the `vote_index`th cast
+    pub fn new(vote: EncryptedVote, vote_index: u64) -> Self {
+        EncryptedVoteCast {
+            private_election: vote.election,
+            nullifier: vote.nullifier,
+            ciphertexts: vote.ciphertexts,
+            commitment: vote.commitment,
+            weight: vote.weight,
+            vote_index,
+            timestamp: vote.timestamp,
+        }
+    }
+}
+
+/// A ballot was replaced by a re-vote
+#[event]
+pub struct EncryptedVoteReplaced {
+    /// The private election the ballot belongs to
+    pub private_election: Pubkey,
+    /// The nullifier the ballot is stored under
+    pub nullifier: [u8; 32],
+    /// The new ciphertexts, one per option
+    pub ciphertexts: Vec<BallotCiphertext>,
+    /// Receipt commitment of the new ballot
+    pub commitment: [u8; 32],
+    /// Weight the new ballot is counted with
+    pub weight: u64,
+    /// Number of times the ballot has now been replaced
+    pub revision: u32,
+    /// Timestamp of the replacement
+    pub timestamp: i64,
+}
+
+/// Voting is over and the aggregate is ready for decryption
+#[event]
+pub struct TallyRequested {
+    /// The private election being tallied
+    pub private_election: Pubkey,
+    /// Number of ballots in the aggregate
+    pub total_votes: u64,
+    /// Timestamp of the request
+    pub requested_at: i64,
+}
+
+/// The decrypted tally was verified and the election finalized
+#[event]
+pub struct TallyFinalized {
+    /// The finalized private election
+    pub private_election: Pubkey,
+    /// Decrypted count per option
+    pub counts: Vec<u64>,
+    /// Account that submitted the result
+    pub submitted_by: Pubkey,
+    /// Timestamp of finalization
+    pub finalized_at: i64,
+}
+
+impl From<&TallyResult> for TallyFinalized {
+    fn from(result: &TallyResult) -> Self {
+        TallyFinalized {
+            private_election: result.election,
+            counts: result.counts.clone(),
+            submitted_by: result.submitted_by,
+            finalized_at: result.finalized_at,
+        }
+    }
+}
+
+/// The admin proposed handing the election to a new authority
+#[event]
+pub struct AuthorityProposed {
+    /// The private election
+    pub private_election: Pubkey,
+    /// Current admin
+    pub authority: Pubkey,
+    /// Proposed admin, or `None` if a pending proposal was withdrawn
+    pub pending_authority: Option<Pubkey>,
+}
+
+/// The proposed authority accepted and is now the election admin
+#[event]
+pub struct AuthorityTransferred {
+    /// The private election
+    pub private_election: Pubkey,
+    /// Previous admin
+    pub previous_authority: Pubkey,
+    /// New admin
+    pub authority: Pubkey,
+}
+
+/// The admin reassigned the tally submitter and guardian roles
+#[event]
+pub struct RolesUpdated {
+    /// The private election
+    pub private_election: Pubkey,
+    /// Signer allowed to submit the decrypted tally
+    pub tally_submitter: Pubkey,
+    /// Signer allowed to cancel the election
+    pub guardian: Pubkey,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{Discriminator, Event};
+
+    /// Decode an event from the bytes `emit!` logs
+    fn decode<T: Discriminator + AnchorDeserialize>(data: &[u8]) -> T {
+        let (discriminator, fields) = data.split_at(8);
+        assert_eq!(discriminator, T::DISCRIMINATOR);
+        T::try_from_slice(fields).unwrap()
+    }
+
+    fn vote(nullifier: u8) -> EncryptedVote {
+        EncryptedVote {
+            version: EncryptedVote::VERSION,
+            bump: 255,
+            election: Pubkey::new_from_array([1; 32]),
+            ciphertexts: vec![BallotCiphertext { c1: [2; 32], c2: [3; 32] }; 2],
+            nullifier: [nullifier; 32],
+            commitment: [5; 32],
+            revision: 0,
+            weight: 7,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_encrypted_vote_cast_fields() {
+        let event: EncryptedVoteCast = decode(&EncryptedVoteCast::new(vote(4), 3).data());
+
+        assert_eq!(event.private_election, Pubkey::new_from_array([1; 32]));
+        assert_eq!(event.nullifier, [4; 32]);
+        assert!(event.ciphertexts == vec![BallotCiphertext { c1: [2; 32], c2: [3; 32] }; 2]);
+        assert_eq!(event.commitment, [5; 32]);
+        assert_eq!(event.weight, 7);
+        assert_eq!(event.vote_index, 3);
+        assert_eq!(event.timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn test_tally_finalized_fields() {
+        let result = TallyResult {
+            version: TallyResult::VERSION,
+            bump: 255,
+            election: Pubkey::new_from_array([1; 32]),
+            counts: vec![25, 10],
+            aggregate: vec![BallotCiphertext::default(); 2],
+            submitted_by: Pubkey::new_from_array([2; 32]),
+            finalized_at: 1_700_000_000,
+            published: false,
+        };
+        let event: TallyFinalized = decode(&TallyFinalized::from(&result).data());
+
+        assert_eq!(event.private_election, result.election);
+        assert_eq!(event.counts, vec![25, 10]);
+        assert_eq!(event.submitted_by, result.submitted_by);
+        assert_eq!(event.finalized_at, 1_700_000_000);
+    }
+}
@@ -0,0 +1,195 @@
+//! Interface to the mpl-gov-micro governance program
+//!
+//! mpl-gov-micro is an Anchor program, so its accounts and instructions
+//! are identified by Anchor discriminators. This module relies on exactly
+//! two parts of its ABI:
+//! - the `Election` account starts with its `authority: Pubkey`, straight
+//!   after the discriminator
+//! - `receive_private_tally(tally: Vec<u64>, proof: TallyProof)` takes the
+//!   accounts `election` (writable), `privacy_program` (this program, which
+//!   it checks against the privacy layer registered for the election) and
+//!   `authority` (this program's `gov_authority` PDA, signing)
+//!
+//! Both are specified in `docs/PRIVACY_LAYER_SPEC.md`, "Interface with
+//! mpl-gov-micro".
+//!
+//! The governance program ID is held in `GovConfig`, set once by this
+//! program's upgrade authority.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+
+use crate::errors::PrivacyError;
+use crate::state::BallotCiphertext;
+use privacy_crypto::{commit_aggregate, ElGamalCiphertext};
+
+/// `sha256("account:Election")[..8]`
+pub const ELECTION_DISCRIMINATOR: [u8; 8] = [0x44, 0xbf, 0xa4, 0x55, 0x23, 0x69, 0x98, 0xca];
+
+/// `sha256("global:receive_private_tally")[..8]`
+pub const RECEIVE_PRIVATE_TALLY_DISCRIMINATOR: [u8; 8] =
+    [0xc6, 0xf1, 0x35, 0xd4, 0xf7, 0xc4, 0x5d, 0x59];
+
+/// Seed of the PDA this program signs governance CPIs with
+pub const GOV_AUTHORITY_SEED: &[u8] = b"gov_authority";
+
+/// Check `election` is an mpl-gov-micro `Election` and return its authority
+pub fn election_authority(election: &AccountInfo, gov_program: &Pubkey) -> Result<Pubkey> {
+    require_keys_eq!(*election.owner, *gov_program, PrivacyError::InvalidGovElection);
+
+    let data = election.try_borrow_data()?;
+    require!(
+        data.len() >= 8 + 32 && data[..8] == ELECTION_DISCRIMINATOR,
+        PrivacyError::InvalidGovElection
+    );
+
+    let mut authority = [0u8; 32];
+    authority.copy_from_slice(&data[8..40]);
+    Ok(Pubkey::new_from_array(authority))
+}
+
+/// What mpl-gov-micro checks a received tally against
+///
+/// The counts were verified here as the decryption of the aggregate; the
+/// proof ties them to that aggregate and the key it was decrypted under.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct TallyProof {
+    /// The `TallyResult` account holding the counts and aggregate
+    pub tally_result: Pubkey,
+
+    /// ElGamal public key the ballots were encrypted under
+    pub mpc_public_key: [u8; 32],
+
+    /// `commit_aggregate` of the aggregate ciphertexts, in option order
+    pub aggregate_hash: [u8; 32],
+}
+
+impl TallyProof {
+    pub fn new(tally_result: Pubkey, mpc_public_key: [u8; 32], aggregate: &[BallotCiphertext]) -> Self {
+        Self {
+            tally_result,
+            mpc_public_key,
+            aggregate_hash: commit_aggregate(
+                &aggregate.iter().copied().map(ElGamalCiphertext::from).collect::<Vec<_>>(),
+            ),
+        }
+    }
+}
+
+/// Build the `receive_private_tally` instruction carrying the final counts
+pub fn receive_private_tally(
+    gov_program: &Pubkey,
+    election: &Pubkey,
+    gov_authority: &Pubkey,
+    tally: &[u64],
+    proof: &TallyProof,
+) -> Result<Instruction> {
+    let mut data = RECEIVE_PRIVATE_TALLY_DISCRIMINATOR.to_vec();
+    tally.to_vec().serialize(&mut data)?;
+    proof.serialize(&mut data)?;
+
+    Ok(Instruction {
+        program_id: *gov_program,
+        accounts: vec![
+            AccountMeta::new(*election, false),
+            AccountMeta::new_readonly(crate::ID, false),
+            AccountMeta::new_readonly(*gov_authority, true),
+        ],
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(owner: &Pubkey, data: &mut [u8], gov_program: &Pubkey) -> Result<Pubkey> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let election = AccountInfo::new(&key, false, false, &mut lamports, data, owner, false, 0);
+        election_authority(&election, gov_program)
+    }
+
+    #[test]
+    fn test_election_authority() {
+        let gov_program = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut data = ELECTION_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&[0u8; 16]);
+
+        assert_eq!(check(&gov_program, &mut data, &gov_program).unwrap(), authority);
+    }
+
+    #[test]
+    fn test_election_authority_rejects_foreign_accounts() {
+        let gov_program = Pubkey::new_unique();
+        let mut data = ELECTION_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+
+        // Owned by another program
+        assert_eq!(
+            check(&Pubkey::new_unique(), &mut data.clone(), &gov_program),
+            Err(PrivacyError::InvalidGovElection.into())
+        );
+
+        // Another account type of the governance program
+        let mut wrong_type = data.clone();
+        wrong_type[0] ^= 1;
+        assert_eq!(
+            check(&gov_program, &mut wrong_type, &gov_program),
+            Err(PrivacyError::InvalidGovElection.into())
+        );
+
+        // Too short to hold an authority
+        assert_eq!(
+            check(&gov_program, &mut data[..39].to_vec(), &gov_program),
+            Err(PrivacyError::InvalidGovElection.into())
+        );
+    }
+
+    #[test]
+    fn test_receive_private_tally_encoding() {
+        let gov_program = Pubkey::new_unique();
+        let election = Pubkey::new_unique();
+        let gov_authority = Pubkey::new_unique();
+
+        let proof = TallyProof::new(Pubkey::new_unique(), [7; 32], &[BallotCiphertext::default(); 2]);
+
+        let ix = receive_private_tally(&gov_program, &election, &gov_authority, &[3, 5], &proof).unwrap();
+
+        assert_eq!(ix.program_id, gov_program);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(election, false),
+                AccountMeta::new_readonly(crate::ID, false),
+                AccountMeta::new_readonly(gov_authority, true),
+            ]
+        );
+        assert_eq!(&ix.data[..8], &RECEIVE_PRIVATE_TALLY_DISCRIMINATOR);
+        assert_eq!(&ix.data[8..12], &2u32.to_le_bytes());
+        assert_eq!(&ix.data[12..20], &3u64.to_le_bytes());
+        assert_eq!(&ix.data[20..28], &5u64.to_le_bytes());
+        assert_eq!(&ix.data[28..60], proof.tally_result.as_ref());
+        assert_eq!(&ix.data[60..92], &[7; 32]);
+        assert_eq!(&ix.data[92..], &proof.aggregate_hash);
+    }
+
+    #[test]
+    fn test_tally_proof_binds_aggregate() {
+        let tally_result = Pubkey::new_unique();
+        let aggregate = [BallotCiphertext::default(); 2];
+        let mut changed = aggregate;
+        changed[1].c2[0] = 1;
+
+        assert_eq!(
+            TallyProof::new(tally_result, [7; 32], &aggregate),
+            TallyProof::new(tally_result, [7; 32], &aggregate)
+        );
+        assert_ne!(
+            TallyProof::new(tally_result, [7; 32], &aggregate).aggregate_hash,
+            TallyProof::new(tally_result, [7; 32], &changed).aggregate_hash
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Groth16 verification over BN254
+//!
+//! Checks `e(-A, B) * e(alpha, beta) * e(L, gamma) * e(C, delta) == 1`
+//! with `L = IC_0 + sum(x_i * IC_i)`, using the alt_bn128 syscalls on-chain
+//! (and their arkworks fallback off-chain). Points and scalars use the
+//! EIP-197 big-endian encoding: G1 is `x || y`, G2 is `x1 || x0 || y1 || y0`.
+
+use anchor_lang::prelude::*;
+use solana_bn254::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
+
+use crate::errors::PrivacyError;
+use crate::state::EligibilityVerifyingKey;
+
+/// Encoded G1 point length
+pub const G1_LEN: usize = 64;
+
+/// Encoded G2 point length
+pub const G2_LEN: usize = 128;
+
+/// Public inputs of the eligibility circuit, in order:
+/// `voter_merkle_root`, `nullifier`, `election_id`, `signal`, `weight`
+pub const ELIGIBILITY_PUBLIC_INPUTS: usize = 5;
+
+/// BN254 base field modulus q (big-endian)
+const BASE_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+/// BN254 scalar field modulus r (big-endian)
+const SCALAR_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+/// A Groth16 proof
+#[derive(Clone, Copy)]
+pub struct Groth16Proof {
+    /// A in G1 (64 bytes)
+    pub a: [u8; G1_LEN],
+    /// B in G2 (128 bytes)
+    pub b: [u8; G2_LEN],
+    /// C in G1 (64 bytes)
+    pub c: [u8; G1_LEN],
+}
+
+impl Groth16Proof {
+    /// Assemble a proof from optional instruction arguments; `None` unless
+    /// all three points are present
+    pub fn from_parts(
+        a: Option<[u8; G1_LEN]>,
+        b: Option<[u8; G2_LEN]>,
+        c: Option<[u8; G1_LEN]>,
+    ) -> Option<Self> {
+        Some(Self { a: a?, b: b?, c: c? })
+    }
+}
+
+/// Verify `proof` against `vk` for the given public inputs
+///
+/// Every input must already be a canonical scalar (below r).
+pub fn verify_proof(
+    vk: &EligibilityVerifyingKey,
+    proof: &Groth16Proof,
+    public_inputs: &[[u8; 32]],
+) -> Result<()> {
+    require!(
+        vk.ic.len() == public_inputs.len() + 1,
+        PrivacyError::InvalidZkProof
+    );
+
+    // L = IC_0 + sum(x_i * IC_i)
+    let mut acc = vk.ic[0];
+    for (input, ic) in public_inputs.iter().zip(&vk.ic[1..]) {
+        require!(is_canonical_scalar(input), PrivacyError::InvalidZkProof);
+
+        let mut mul_input = [0u8; G1_LEN + 32];
+        mul_input[..G1_LEN].copy_from_slice(ic);
+        mul_input[G1_LEN..].copy_from_slice(input);
+        let term = alt_bn128_multiplication(&mul_input).map_err(|_| PrivacyError::InvalidZkProof)?;
+
+        let mut add_input = [0u8; 2 * G1_LEN];
+        add_input[..G1_LEN].copy_from_slice(&acc);
+        add_input[G1_LEN..].copy_from_slice(&term);
+        acc = alt_bn128_addition(&add_input)
+            .map_err(|_| PrivacyError::InvalidZkProof)?
+            .try_into()
+            .map_err(|_| PrivacyError::InvalidZkProof)?;
+    }
+
+    let pairs = [
+        (negate_g1(&proof.a), &proof.b),
+        (vk.alpha_g1, &vk.beta_g2),
+        (acc, &vk.gamma_g2),
+        (proof.c, &vk.delta_g2),
+    ];
+    let mut pairing_input = Vec::with_capacity(pairs.len() * (G1_LEN + G2_LEN));
+    for (g1, g2) in &pairs {
+        pairing_input.extend_from_slice(g1);
+        pairing_input.extend_from_slice(*g2);
+    }
+
+    let result = alt_bn128_pairing(&pairing_input).map_err(|_| PrivacyError::InvalidZkProof)?;
+    let mut one = [0u8; 32];
+    one[31] = 1;
+    require!(result == one, PrivacyError::InvalidZkProof);
+
+    Ok(())
+}
+
+/// Public inputs of the eligibility circuit
+///
+/// `signal` is `privacy_crypto::ballot_signal`, which ties the proof to
+/// one ballot. `weight` is the weight in the voter's leaf, which the
+/// circuit must constrain (to 1 in unweighted elections). The Merkle root, election id and signal are reduced modulo
+/// r. The nullifier is the circuit's output and must already be canonical;
+/// otherwise `n` and `n + r` would be two distinct nullifier records backed
+/// by one proof.
+pub fn eligibility_public_inputs(
+    voter_merkle_root: &[u8; 32],
+    nullifier: &[u8; 32],
+    election_id: &[u8; 32],
+    signal: &[u8; 32],
+    weight: u64,
+) -> Result<[[u8; 32]; ELIGIBILITY_PUBLIC_INPUTS]> {
+    let mut weight_bytes = [0u8; 32];
+    weight_bytes[24..].copy_from_slice(&weight.to_be_bytes());
+
+    require!(is_canonical_scalar(nullifier), PrivacyError::InvalidNullifier);
+
+    Ok([
+        reduce_to_scalar(voter_merkle_root),
+        *nullifier,
+        reduce_to_scalar(election_id),
+        reduce_to_scalar(signal),
+        weight_bytes,
+    ])
+}
+
+/// Reduce a big-endian 256-bit value modulo r
+pub fn reduce_to_scalar(bytes: &[u8; 32]) -> [u8; 32] {
+    // 2^256 < 6r, so at most five subtractions are needed
+    let mut value = *bytes;
+    while !is_canonical_scalar(&value) {
+        value = sub_be(&value, &SCALAR_FIELD_MODULUS);
+    }
+    value
+}
+
+/// Whether a big-endian value is below r
+pub fn is_canonical_scalar(bytes: &[u8; 32]) -> bool {
+    *bytes < SCALAR_FIELD_MODULUS
+}
+
+/// Negate a G1 point: (x, y) -> (x, q - y), keeping the point at infinity
+fn negate_g1(point: &[u8; G1_LEN]) -> [u8; G1_LEN] {
+    if point.iter().all(|&b| b == 0) {
+        return *point;
+    }
+
+    let mut y = [0u8; 32];
+    y.copy_from_slice(&point[32..]);
+
+    let mut negated = *point;
+    negated[32..].copy_from_slice(&sub_be(&BASE_FIELD_MODULUS, &y));
+    negated
+}
+
+/// Big-endian `a - b`, wrapping on underflow
+fn sub_be(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let mut borrow = 0u16;
+    for i in (0..32).rev() {
+        let diff = (a[i] as u16).wrapping_sub(b[i] as u16).wrapping_sub(borrow);
+        out[i] = diff as u8;
+        borrow = (diff >> 15) & 1;
+    }
+    out
+}
+
+/// Groth16 proofs for tests, from a toy circuit with the eligibility
+/// circuit's public inputs
+///
+/// Also built with the `test-prover` feature, for tests that run the
+/// built program.
+#[cfg(any(test, feature = "test-prover"))]
+pub mod test_prover {
+    use super::*;
+    use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_groth16::{Groth16, ProvingKey};
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable};
+    use ark_snark::SNARK;
+
+    /// Proves knowledge of `w` equal to the sum of the inputs, so the proof
+    /// holds for any inputs but only the ones it was made for
+    #[derive(Clone)]
+    struct SumCircuit {
+        inputs: [Fr; ELIGIBILITY_PUBLIC_INPUTS],
+        witness: Fr,
+    }
+
+    impl SumCircuit {
+        fn new(public_inputs: &[[u8; 32]; ELIGIBILITY_PUBLIC_INPUTS]) -> Self {
+            let inputs = public_inputs.map(|x| Fr::from_be_bytes_mod_order(&x));
+            SumCircuit {
+                inputs,
+                witness: inputs.iter().sum(),
+            }
+        }
+    }
+
+    impl ConstraintSynthesizer<Fr> for SumCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> core::result::Result<(), SynthesisError> {
+            let mut sum = lc!();
+            for input in self.inputs {
+                sum = sum + cs.new_input_variable(|| Ok(input))?;
+            }
+            let w = cs.new_witness_variable(|| Ok(self.witness))?;
+            cs.enforce_constraint(lc!() + w, lc!() + Variable::One, sum)
+        }
+    }
+
+    fn g1_bytes(point: &G1Affine) -> [u8; G1_LEN] {
+        let mut out = [0u8; G1_LEN];
+        out[..32].copy_from_slice(&point.x.into_bigint().to_bytes_be());
+        out[32..].copy_from_slice(&point.y.into_bigint().to_bytes_be());
+        out
+    }
+
+    fn g2_bytes(point: &G2Affine) -> [u8; G2_LEN] {
+        let mut out = [0u8; G2_LEN];
+        out[..32].copy_from_slice(&point.x.c1.into_bigint().to_bytes_be());
+        out[32..64].copy_from_slice(&point.x.c0.into_bigint().to_bytes_be());
+        out[64..96].copy_from_slice(&point.y.c1.into_bigint().to_bytes_be());
+        out[96..].copy_from_slice(&point.y.c0.into_bigint().to_bytes_be());
+        out
+    }
+
+    /// A setup of the toy circuit, proving for any public inputs under one
+    /// verifying key
+    pub struct TestProver {
+        proving_key: ProvingKey<Bn254>,
+    }
+
+    impl Default for TestProver {
+        fn default() -> Self {
+            let circuit = SumCircuit::new(&[[0; 32]; ELIGIBILITY_PUBLIC_INPUTS]);
+            let (proving_key, _) =
+                Groth16::<Bn254>::circuit_specific_setup(circuit, &mut rand::thread_rng()).unwrap();
+            TestProver { proving_key }
+        }
+    }
+
+    impl TestProver {
+        /// The verifying key, as `set_verifying_key` stores it
+        pub fn verifying_key(&self) -> EligibilityVerifyingKey {
+            let vk = &self.proving_key.vk;
+            EligibilityVerifyingKey {
+                version: EligibilityVerifyingKey::VERSION,
+                bump: 0,
+                election: Pubkey::new_unique(),
+                alpha_g1: g1_bytes(&vk.alpha_g1),
+                beta_g2: g2_bytes(&vk.beta_g2),
+                gamma_g2: g2_bytes(&vk.gamma_g2),
+                delta_g2: g2_bytes(&vk.delta_g2),
+                ic: vk.gamma_abc_g1.iter().map(g1_bytes).collect::<Vec<_>>().try_into().unwrap(),
+            }
+        }
+
+        /// A proof for `public_inputs`
+        pub fn prove(&self, public_inputs: &[[u8; 32]; ELIGIBILITY_PUBLIC_INPUTS]) -> Groth16Proof {
+            let circuit = SumCircuit::new(public_inputs);
+            let proof = Groth16::<Bn254>::prove(&self.proving_key, circuit, &mut rand::thread_rng()).unwrap();
+            Groth16Proof {
+                a: g1_bytes(&proof.a),
+                b: g2_bytes(&proof.b),
+                c: g1_bytes(&proof.c),
+            }
+        }
+    }
+
+    /// A fresh verifying key and a proof under it for `public_inputs`
+    pub fn prove(
+        public_inputs: &[[u8; 32]; ELIGIBILITY_PUBLIC_INPUTS],
+    ) -> (EligibilityVerifyingKey, Groth16Proof) {
+        let prover = TestProver::default();
+        (prover.verifying_key(), prover.prove(public_inputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ff::PrimeField;
+
+    fn setup(
+        root: &[u8; 32],
+        nullifier: &[u8; 32],
+        election_id: &[u8; 32],
+    ) -> (EligibilityVerifyingKey, Groth16Proof, [[u8; 32]; ELIGIBILITY_PUBLIC_INPUTS]) {
+        let public_inputs = eligibility_public_inputs(root, nullifier, election_id, &[0x03; 32], 1).unwrap();
+        let (vk, proof) = test_prover::prove(&public_inputs);
+        (vk, proof, public_inputs)
+    }
+
+    #[test]
+    fn test_valid_proof_verifies() {
+        let (vk, proof, inputs) = setup(&[0xFF; 32], &[0x01; 32], &[0x02; 32]);
+
+        assert!(verify_proof(&vk, &proof, &inputs).is_ok());
+    }
+
+    #[test]
+    fn test_proof_bound_to_public_inputs() {
+        let (vk, proof, inputs) = setup(&[0xAA; 32], &[0x01; 32], &[0x02; 32]);
+
+        for i in 0..ELIGIBILITY_PUBLIC_INPUTS {
+            let mut tampered = inputs;
+            tampered[i][31] ^= 1;
+            assert!(verify_proof(&vk, &proof, &tampered).is_err());
+        }
+        assert!(verify_proof(&vk, &proof, &inputs[..2]).is_err());
+    }
+
+    #[test]
+    fn test_tampered_proof_rejected() {
+        let (vk, proof, inputs) = setup(&[0xAA; 32], &[0x01; 32], &[0x02; 32]);
+
+        // Swapping A and C keeps both points on the curve
+        let swapped = Groth16Proof {
+            a: proof.c,
+            c: proof.a,
+            ..proof
+        };
+        assert!(verify_proof(&vk, &swapped, &inputs).is_err());
+
+        let mut garbage = proof;
+        garbage.b[0] ^= 1;
+        assert!(verify_proof(&vk, &garbage, &inputs).is_err());
+    }
+
+    #[test]
+    fn test_non_canonical_nullifier_rejected() {
+        assert!(eligibility_public_inputs(&[0; 32], &SCALAR_FIELD_MODULUS, &[0; 32], &[0; 32], 1).is_err());
+        assert!(eligibility_public_inputs(&[0xFF; 32], &[0x01; 32], &[0xFF; 32], &[0xFF; 32], u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_reduce_to_scalar() {
+        let reduced = reduce_to_scalar(&[0xFF; 32]);
+        assert!(is_canonical_scalar(&reduced));
+        assert_eq!(
+            Fr::from_be_bytes_mod_order(&reduced),
+            Fr::from_be_bytes_mod_order(&[0xFF; 32])
+        );
+        assert_eq!(reduce_to_scalar(&[0x01; 32]), [0x01; 32]);
+    }
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::events::AuthorityTransferred;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    /// Must be `private_election.pending_authority`
+    pub new_authority: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+    let previous_authority = private_election.authority;
+
+    private_election.accept_authority(ctx.accounts.new_authority.key())?;
+
+    emit!(AuthorityTransferred {
+        private_election: private_election.key(),
+        previous_authority,
+        authority: private_election.authority,
+    });
+
+    Ok(())
+}
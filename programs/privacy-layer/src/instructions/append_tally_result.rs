@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use crate::instructions::submit_tally_result::{record_counts, DecryptionProofArgs};
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct AppendTallyResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        constraint = !private_election.tally_finalized @ PrivacyError::TallyAlreadyFinalized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        seeds = [b"encrypted_tally", private_election.election.as_ref()],
+        bump = encrypted_tally.load()?.bump,
+    )]
+    pub encrypted_tally: AccountLoader<'info, EncryptedTally>,
+
+    /// Started by `submit_tally_result`
+    #[account(
+        mut,
+        seeds = [b"tally_result", private_election.key().as_ref()],
+        bump = tally_result.bump,
+    )]
+    pub tally_result: Account<'info, TallyResult>,
+
+    #[account(address = private_election.tally_submitter @ PrivacyError::Unauthorized)]
+    pub submitter: Signer<'info>,
+}
+
+pub(crate) fn handler(
+    ctx: Context<AppendTallyResult>,
+    counts: Vec<u64>,
+    proofs: Vec<DecryptionProofArgs>,
+) -> Result<()> {
+    let tally_data = ctx.accounts.encrypted_tally.as_ref().try_borrow_data()?;
+    record_counts(
+        &mut ctx.accounts.private_election,
+        &EncryptedTallyRef::from_account_data(&tally_data)?,
+        &mut ctx.accounts.tally_result,
+        counts,
+        proofs,
+    )
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct CancelElection<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = guardian @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    pub guardian: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<CancelElection>) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+
+    // Only before the tally starts; finalized results are never retracted
+    private_election.transition_to(ElectionStatus::Cancelled)?;
+
+    msg!("Election cancelled: {}", private_election.key());
+
+    Ok(())
+}
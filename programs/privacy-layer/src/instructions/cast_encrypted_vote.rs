@@ -1,9 +1,16 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+use privacy_crypto::{
+    ballot_signal, commit_ballot, compute_public_nullifier, weighted_leaf, ElGamalCiphertext,
+    KeccakHasher, MerkleProof,
+};
+use crate::groth16::{self, Groth16Proof, G1_LEN, G2_LEN};
+use crate::events::EncryptedVoteCast;
 use crate::state::*;
 use crate::errors::PrivacyError;
 
 #[derive(Accounts)]
-#[instruction(ciphertext_c1: [u8; 32], ciphertext_c2: [u8; 32], nullifier: [u8; 32])]
+#[instruction(nullifier: [u8; 32])]
 pub struct CastEncryptedVote<'info> {
     #[account(
         mut,
@@ -13,21 +20,51 @@ pub struct CastEncryptedVote<'info> {
     )]
     pub private_election: Account<'info, PrivateElection>,
 
+    /// CHECK: Must still be empty; created by `record_ballot` to mark the
+    /// nullifier as spent
     #[account(
-        init,
-        payer = voter,
-        space = EncryptedVote::LEN,
+        mut,
+        seeds = [b"nullifier", private_election.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: UncheckedAccount<'info>,
+
+    /// CHECK: Created by `record_ballot` alongside the nullifier record
+    #[account(
+        mut,
         seeds = [b"encrypted_vote", private_election.key().as_ref(), nullifier.as_ref()],
         bump
     )]
-    pub encrypted_vote: Account<'info, EncryptedVote>,
+    pub encrypted_vote: UncheckedAccount<'info>,
 
+    /// The ballot, written by the voter with `write_ballot_buffer`; its
+    /// rent goes back to them once it is cast
     #[account(
         mut,
-        seeds = [b"nullifier_set", private_election.election.as_ref()],
-        bump = nullifier_set.bump,
+        close = voter,
+        seeds = [
+            b"ballot_buffer",
+            private_election.key().as_ref(),
+            voter.key().as_ref(),
+            nullifier.as_ref(),
+        ],
+        bump = ballot_buffer.load()?.bump,
+    )]
+    pub ballot_buffer: AccountLoader<'info, BallotBuffer>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", private_election.election.as_ref()],
+        bump = encrypted_tally.load()?.bump,
+    )]
+    pub encrypted_tally: AccountLoader<'info, EncryptedTally>,
+
+    /// Required in `EligibilityMode::Anonymous`
+    #[account(
+        seeds = [b"verifying_key", private_election.key().as_ref()],
+        bump = verifying_key.load()?.bump,
     )]
-    pub nullifier_set: Account<'info, NullifierSet>,
+    pub verifying_key: Option<AccountLoader<'info, EligibilityVerifyingKey>>,
 
     #[account(mut)]
     pub voter: Signer<'info>,
@@ -35,75 +72,572 @@ pub struct CastEncryptedVote<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handler(
     ctx: Context<CastEncryptedVote>,
-    ciphertext_c1: [u8; 32],
-    ciphertext_c2: [u8; 32],
     nullifier: [u8; 32],
     commitment: [u8; 32],
-    // ZK proof components (for future implementation)
-    _zk_proof_a: Option<[u8; 32]>,
-    _zk_proof_b: Option<[u8; 64]>,
-    _zk_proof_c: Option<[u8; 32]>,
+    // Must be 1 unless the election is weighted
+    weight: u64,
+    // Groth16 eligibility proof, required when the election is anonymous
+    zk_proof_a: Option<[u8; G1_LEN]>,
+    zk_proof_b: Option<[u8; G2_LEN]>,
+    zk_proof_c: Option<[u8; G1_LEN]>,
 ) -> Result<()> {
-    let private_election = &mut ctx.accounts.private_election;
-    let encrypted_vote = &mut ctx.accounts.encrypted_vote;
-    let nullifier_set = &mut ctx.accounts.nullifier_set;
-
-    // Check election hasn't ended
     let current_time = Clock::get()?.unix_timestamp;
+    ctx.accounts.private_election.require_voting_open(current_time)?;
+    NullifierRecord::require_unspent(&ctx.accounts.nullifier_record)?;
+    let (ciphertexts, eligibility_proof) = BallotBuffer::ballot(ctx.accounts.ballot_buffer.as_ref())?;
+
+    let verifying_key = ctx
+        .accounts
+        .verifying_key
+        .as_ref()
+        .map(|verifying_key| verifying_key.load())
+        .transpose()?;
+    verify_ballot(
+        &ctx.accounts.private_election,
+        &ctx.accounts.voter.key(),
+        verifying_key.as_deref(),
+        &nullifier,
+        &commitment,
+        0,
+        0,
+        &ciphertexts,
+        weight,
+        Some(&eligibility_proof),
+        Groth16Proof::from_parts(zk_proof_a, zk_proof_b, zk_proof_c),
+    )?;
+    drop(verifying_key);
+
+    let accounts = &mut *ctx.accounts;
+    let mut tally_data = accounts.encrypted_tally.as_ref().try_borrow_mut_data()?;
+    record_ballot(
+        &mut accounts.private_election,
+        &mut EncryptedTallyMut::from_account_data(&mut tally_data)?,
+        NewVoteAccounts {
+            nullifier_record: &accounts.nullifier_record,
+            nullifier_record_bump: ctx.bumps.nullifier_record,
+            encrypted_vote: &accounts.encrypted_vote,
+            encrypted_vote_bump: ctx.bumps.encrypted_vote,
+            payer: &accounts.voter,
+            system_program: &accounts.system_program,
+        },
+        nullifier,
+        commitment,
+        ciphertexts,
+        weight,
+        current_time,
+    )
+}
+
+/// The not yet created accounts of a first ballot and who pays for them
+pub struct NewVoteAccounts<'a, 'info> {
+    pub nullifier_record: &'a AccountInfo<'info>,
+    pub nullifier_record_bump: u8,
+    pub encrypted_vote: &'a AccountInfo<'info>,
+    pub encrypted_vote_bump: u8,
+    pub payer: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+/// Fold a verified first ballot into the tally and store it
+///
+/// Creates the nullifier record and encrypted vote, paid for and refunded
+/// to `accounts.payer`. The caller has already checked the nullifier with
+/// `NullifierRecord::require_unspent`.
+#[allow(clippy::too_many_arguments)]
+pub fn record_ballot(
+    private_election: &mut Account<PrivateElection>,
+    encrypted_tally: &mut EncryptedTallyMut,
+    accounts: NewVoteAccounts,
+    nullifier: [u8; 32],
+    commitment: [u8; 32],
+    ciphertexts: Vec<BallotCiphertext>,
+    weight: u64,
+    current_time: i64,
+) -> Result<()> {
+    // Fold the ballot into the on-chain running tally
+    encrypted_tally.accumulate(&ciphertexts, weight)?;
+
+    let private_election_key = private_election.key();
+    let space = EncryptedVote::space(private_election.num_options);
+
+    // Record the nullifier as spent
+    create_pda_account(
+        accounts.nullifier_record,
+        &[
+            b"nullifier",
+            private_election_key.as_ref(),
+            nullifier.as_ref(),
+            &[accounts.nullifier_record_bump],
+        ],
+        NullifierRecord::LEN,
+        &NullifierRecord {
+            version: NullifierRecord::VERSION,
+            bump: accounts.nullifier_record_bump,
+            election: private_election_key,
+            nullifier,
+            created_at: current_time,
+            rent_payer: accounts.payer.key(),
+        },
+        accounts.payer,
+        accounts.system_program,
+    )?;
+
+    // Store encrypted vote
+    let encrypted_vote = EncryptedVote {
+        version: EncryptedVote::VERSION,
+        bump: accounts.encrypted_vote_bump,
+        election: private_election_key,
+        ciphertexts,
+        nullifier,
+        commitment,
+        revision: 0,
+        weight,
+        timestamp: current_time,
+    };
+    create_pda_account(
+        accounts.encrypted_vote,
+        &[
+            b"encrypted_vote",
+            private_election_key.as_ref(),
+            nullifier.as_ref(),
+            &[accounts.encrypted_vote_bump],
+        ],
+        space,
+        &encrypted_vote,
+        accounts.payer,
+        accounts.system_program,
+    )?;
+
+    let vote_index = private_election.count_ballot()?;
+    emit!(EncryptedVoteCast::new(encrypted_vote, vote_index));
+
+    Ok(())
+}
+
+/// Create a program account at a PDA and write its contents
+///
+/// What `init` does, minus the failure on an address that already holds
+/// lamports: those are topped up to rent exemption instead, so nobody can
+/// block a nullifier by sending it lamports first.
+fn create_pda_account<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    space: usize,
+    contents: &T,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer_seeds = &[seeds];
+
+    if account.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        let shortfall = rent.saturating_sub(account.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    Transfer {
+                        from: payer.clone(),
+                        to: account.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Allocate {
+                    account_to_allocate: account.clone(),
+                },
+                signer_seeds,
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Assign {
+                    account_to_assign: account.clone(),
+                },
+                signer_seeds,
+            ),
+            &crate::ID,
+        )?;
+    }
+
+    contents.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
+
+/// Check a ballot's eligibility and commitment before it touches the tally
+///
+/// `ciphertexts` come from a complete `BallotBuffer`, whose 0/1 and total
+/// proofs were checked as it was written, and so does a public voter's
+/// `eligibility_proof`. `submitter` is the transaction
+/// signer: the voter themself in public mode, or whoever relays the ballot
+/// in anonymous mode. `revision` is the revision the ballot will be stored
+/// under and `relayer_fee` what the submitter is paid. An anonymous voter's
+/// Groth16 proof is bound to all three through `ballot_signal`, so it can't
+/// be replayed over a later re-vote or reused by another submitter or for
+/// another fee.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_ballot(
+    private_election: &PrivateElection,
+    submitter: &Pubkey,
+    verifying_key: Option<&EligibilityVerifyingKey>,
+    nullifier: &[u8; 32],
+    commitment: &[u8; 32],
+    revision: u32,
+    relayer_fee: u64,
+    ciphertexts: &[BallotCiphertext],
+    weight: u64,
+    eligibility_proof: Option<&MerkleProof>,
+    zk_proof: Option<Groth16Proof>,
+) -> Result<()> {
     require!(
-        current_time < private_election.ends_at,
-        PrivacyError::ElectionEnded
+        ciphertexts.len() == private_election.num_options as usize,
+        PrivacyError::InvalidBallotSize
     );
+    private_election.vote_weighting.check_weight(weight)?;
+
+    match private_election.eligibility_mode {
+        // Public eligibility: the signer's wallet (with its weight, if
+        // weighted) must be a leaf of the voter tree and the nullifier
+        // must be the one derived from it
+        EligibilityMode::Public => verify_public_eligibility(
+            private_election,
+            submitter,
+            weight,
+            nullifier,
+            eligibility_proof,
+        )?,
+        // Anonymous eligibility: a Groth16 proof that the voter knows a
+        // leaf of the voter tree carrying this weight and derived this
+        // nullifier from it
+        EligibilityMode::Anonymous => {
+            let proof = zk_proof.ok_or(PrivacyError::InvalidZkProof)?;
+            let verifying_key = verifying_key.ok_or(PrivacyError::InvalidZkProof)?;
+            let public_inputs = groth16::eligibility_public_inputs(
+                &private_election.voter_merkle_root,
+                nullifier,
+                &private_election.election_id,
+                &ballot_signal(commitment, revision, &submitter.to_bytes(), relayer_fee),
+                weight,
+            )?;
+            groth16::verify_proof(verifying_key, &proof, &public_inputs)?;
+        }
+    }
 
-    // Check nullifier hasn't been used (prevent double voting)
+    // The commitment is the ballot's receipt: bound to the exact
+    // ciphertexts, nullifier and election so nobody in between can swap it
+    let ciphertexts: Vec<ElGamalCiphertext> = ciphertexts.iter().map(|&ct| ct.into()).collect();
     require!(
-        !nullifier_set.contains(&nullifier),
-        PrivacyError::NullifierAlreadyUsed
+        *commitment == commit_ballot(&private_election.election_id, nullifier, &ciphertexts),
+        PrivacyError::InvalidCommitment
     );
 
-    // TODO: Verify ZK proof of voter eligibility
-    // For MVP, we skip this and add it in the ZK circuit phase
-    // This would verify:
-    // 1. Voter is in the merkle tree (eligible)
-    // 2. Nullifier is correctly computed from voter secret
-    // 3. Vote is within valid range
-    /*
-    if let (Some(proof_a), Some(proof_b), Some(proof_c)) = (_zk_proof_a, _zk_proof_b, _zk_proof_c) {
-        verify_groth16_proof(
-            &private_election.voter_merkle_root,
-            &nullifier,
-            &private_election.election_id,
-            &proof_a,
-            &proof_b,
-            &proof_c,
-        )?;
-    }
-    */
+    Ok(())
+}
 
-    // Store encrypted vote
-    encrypted_vote.bump = ctx.bumps.encrypted_vote;
-    encrypted_vote.election = private_election.key();
-    encrypted_vote.ciphertext_c1 = ciphertext_c1;
-    encrypted_vote.ciphertext_c2 = ciphertext_c2;
-    encrypted_vote.nullifier = nullifier;
-    encrypted_vote.commitment = commitment;
-    encrypted_vote.timestamp = current_time;
-
-    // Add nullifier to the set
-    nullifier_set.insert(nullifier)?;
-
-    // Increment vote count
-    private_election.total_encrypted_votes = private_election
-        .total_encrypted_votes
-        .checked_add(1)
-        .ok_or(PrivacyError::ArithmeticOverflow)?;
-
-    msg!("Encrypted vote cast successfully");
-    msg!("Election: {}", private_election.key());
-    msg!("Nullifier: {:?}", nullifier);
-    msg!("Total votes: {}", private_election.total_encrypted_votes);
+/// Context the ballot proof is bound to: the private election and nullifier
+///
+/// Prevents copying another voter's ciphertexts and proof into a new ballot.
+pub fn vote_proof_context(private_election: &Pubkey, nullifier: &[u8; 32]) -> [u8; 64] {
+    let mut context = [0u8; 64];
+    context[..32].copy_from_slice(private_election.as_ref());
+    context[32..].copy_from_slice(nullifier);
+    context
+}
+
+/// Check a voter's wallet against the election's voter Merkle root
+///
+/// In weighted elections the leaf is `weighted_leaf(voter, weight)`, so the
+/// inclusion proof also proves the weight.
+pub fn verify_public_eligibility(
+    private_election: &PrivateElection,
+    voter: &Pubkey,
+    weight: u64,
+    nullifier: &[u8; 32],
+    proof: Option<&MerkleProof>,
+) -> Result<()> {
+    let proof = proof.ok_or(PrivacyError::InvalidMerkleProof)?;
+
+    let leaf = if private_election.vote_weighting.is_weighted() {
+        weighted_leaf(&voter.to_bytes(), weight)
+    } else {
+        voter.to_bytes()
+    };
+    let root = proof
+        .compute_root::<KeccakHasher>(&leaf)
+        .map_err(|_| PrivacyError::InvalidMerkleProof)?;
+    require!(
+        root == private_election.voter_merkle_root,
+        PrivacyError::InvalidMerkleProof
+    );
+
+    require!(
+        *nullifier == compute_public_nullifier(&voter.to_bytes(), &private_election.election_id),
+        PrivacyError::InvalidNullifier
+    );
 
     Ok(())
 }
+
+/// Ballots and eligibility proofs for `verify_ballot` tests
+#[cfg(test)]
+pub(crate) mod test_ballots {
+    use super::*;
+    use crate::groth16::test_prover;
+    use anchor_lang::{pubkey, Discriminator};
+    use bytemuck::Pod;
+    use crate::instructions::write_ballot_buffer::BallotProofArgs;
+    use privacy_crypto::{encrypt_ballot, ElGamalKeypair, ElGamalPublicKey, ElGamalSecretKey};
+    use rand::Rng;
+    use solana_sysvar::program_stubs::{set_syscall_stubs, SyscallStubs};
+    use std::sync::Once;
+
+    pub(crate) const NATIVE_LOADER_ID: Pubkey = pubkey!("NativeLoader1111111111111111111111111111111");
+
+    /// Clock and rent for running the entrypoint off-chain, inside the
+    /// window of `election()`
+    struct Sysvars;
+
+    impl SyscallStubs for Sysvars {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock {
+                unix_timestamp: 50,
+                ..Clock::default()
+            };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            0
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+    }
+
+    /// Install `Sysvars`; the stubs are process-wide, so every entrypoint
+    /// test shares them
+    pub(crate) fn stub_sysvars() {
+        static SYSVARS: Once = Once::new();
+        SYSVARS.call_once(|| {
+            set_syscall_stubs(Box::new(Sysvars));
+        });
+    }
+
+    pub(crate) fn zero_copy_data<T: Discriminator + Pod>(account: &T) -> Vec<u8> {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(account));
+        data
+    }
+
+    pub(crate) fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(seeds, &crate::ID)
+    }
+
+    /// An open anonymous election with two options, keyed to `keypair()`
+    pub(crate) fn election() -> PrivateElection {
+        PrivateElection {
+            version: PrivateElection::VERSION,
+            bump: 0,
+            authority: Pubkey::new_unique(),
+            pending_authority: None,
+            tally_submitter: Pubkey::new_unique(),
+            guardian: Pubkey::new_unique(),
+            election: Pubkey::new_unique(),
+            mpc_public_key: keypair().public.point,
+            voter_merkle_root: [0xAA; 32],
+            election_id: [0x02; 32],
+            total_encrypted_votes: 0,
+            open_vote_accounts: 0,
+            tally_requested: false,
+            tally_finalized: false,
+            created_at: 0,
+            starts_at: 0,
+            ends_at: 100,
+            num_options: 2,
+            ballot_rule: BallotRule::SingleChoice,
+            eligibility_mode: EligibilityMode::Anonymous,
+            allow_revoting: true,
+            vote_weighting: VoteWeighting::Equal,
+            audit_period: 0,
+            max_relayer_fee: 10_000,
+            status: ElectionStatus::Active,
+        }
+    }
+
+    pub(crate) fn keypair() -> ElGamalKeypair {
+        ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]))
+    }
+
+    /// A ballot with valid ciphertexts, ballot proof and commitment
+    #[derive(Clone)]
+    pub(crate) struct Ballot {
+        pub nullifier: [u8; 32],
+        pub commitment: [u8; 32],
+        pub ciphertexts: Vec<BallotCiphertext>,
+        pub proof: BallotProofArgs,
+    }
+
+    /// Encrypt and prove `choices` for the election at `key`
+    pub(crate) fn ballot(key: &Pubkey, election: &PrivateElection, nullifier: [u8; 32], choices: &[u64]) -> Ballot {
+        let mut rng = rand::thread_rng();
+        let public_key = ElGamalPublicKey::from_bytes(election.mpc_public_key).unwrap();
+        let randomness: Vec<[u8; 32]> = choices.iter().map(|_| rng.gen()).collect();
+        let (encrypted, proof) = encrypt_ballot(
+            &public_key,
+            choices,
+            &randomness,
+            &rng.gen(),
+            election.ballot_rule.into(),
+            &vote_proof_context(key, &nullifier),
+        )
+        .unwrap();
+
+        Ballot {
+            nullifier,
+            commitment: commit_ballot(&election.election_id, &nullifier, &encrypted.ciphertexts),
+            ciphertexts: encrypted.ciphertexts.into_iter().map(Into::into).collect(),
+            proof: BallotProofArgs {
+                slot_proofs: proof.slot_proofs.iter().map(|proof| proof.to_bytes()).collect(),
+                total_challenges: proof.total_proof.challenges,
+                total_responses: proof.total_proof.responses,
+            },
+        }
+    }
+
+    /// Account data of a ballot buffer holding all of `ballot`, as
+    /// `write_ballot_buffer` leaves it
+    pub(crate) fn ballot_buffer_data(bump: u8, key: &Pubkey, election: &PrivateElection, ballot: &Ballot) -> Vec<u8> {
+        let mut data =
+            BallotBuffer::account_data(bump, *key, ballot.nullifier, election.num_options, election.ballot_rule, 0);
+        let mut buffer = BallotBufferMut::from_account_data(&mut data).unwrap();
+        buffer.write_options(0, &ballot.ciphertexts).unwrap();
+        buffer
+            .write_total_proof(0, &ballot.proof.total_challenges, &ballot.proof.total_responses)
+            .unwrap();
+        data
+    }
+
+    /// A verifying key and the voter's eligibility proof under it, made
+    /// for `signal`
+    pub(crate) fn eligibility(
+        election: &PrivateElection,
+        nullifier: &[u8; 32],
+        signal: &[u8; 32],
+    ) -> (EligibilityVerifyingKey, Groth16Proof) {
+        let public_inputs = groth16::eligibility_public_inputs(
+            &election.voter_merkle_root,
+            nullifier,
+            &election.election_id,
+            signal,
+            1,
+        )
+        .unwrap();
+        test_prover::prove(&public_inputs)
+    }
+
+    /// `verify_ballot` on an anonymous ballot
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn verify(
+        election: &PrivateElection,
+        submitter: &Pubkey,
+        verifying_key: &EligibilityVerifyingKey,
+        ballot: &Ballot,
+        revision: u32,
+        relayer_fee: u64,
+        proof: Groth16Proof,
+    ) -> Result<()> {
+        verify_ballot(
+            election,
+            submitter,
+            Some(verifying_key),
+            &ballot.nullifier,
+            &ballot.commitment,
+            revision,
+            relayer_fee,
+            &ballot.ciphertexts,
+            1,
+            None,
+            Some(proof),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_ballots::*;
+    use super::*;
+
+    #[test]
+    fn test_anonymous_ballot_verifies() {
+        let (key, election, voter) = (Pubkey::new_unique(), election(), Pubkey::new_unique());
+        let ballot = ballot(&key, &election, [0x01; 32], &[1, 0]);
+        let signal = ballot_signal(&ballot.commitment, 0, &voter.to_bytes(), 0);
+        let (vk, proof) = eligibility(&election, &ballot.nullifier, &signal);
+
+        assert!(verify(&election, &voter, &vk, &ballot, 0, 0, proof).is_ok());
+    }
+
+    #[test]
+    fn test_eligibility_proof_bound_to_commitment() {
+        let (key, election, voter) = (Pubkey::new_unique(), election(), Pubkey::new_unique());
+        let original = ballot(&key, &election, [0x01; 32], &[1, 0]);
+        let signal = ballot_signal(&original.commitment, 0, &voter.to_bytes(), 0);
+        let (vk, proof) = eligibility(&election, &original.nullifier, &signal);
+
+        // Whoever holds the proof swaps in their own ballot for the same
+        // nullifier, with a valid ballot proof and a matching commitment;
+        // only the eligibility proof still commits to the original
+        let swapped = ballot(&key, &election, original.nullifier, &[0, 1]);
+        assert_ne!(swapped.commitment, original.commitment);
+        assert_eq!(
+            verify(&election, &voter, &vk, &swapped, 0, 0, proof),
+            Err(PrivacyError::InvalidZkProof.into())
+        );
+
+        // Keeping the original commitment instead gets past the eligibility
+        // proof, but not the commitment check
+        let kept = Ballot {
+            commitment: original.commitment,
+            ..swapped
+        };
+        assert_eq!(
+            verify(&election, &voter, &vk, &kept, 0, 0, proof),
+            Err(PrivacyError::InvalidCommitment.into())
+        );
+    }
+
+    #[test]
+    fn test_eligibility_proof_bound_to_revision() {
+        let (key, election, voter) = (Pubkey::new_unique(), election(), Pubkey::new_unique());
+        let ballot = ballot(&key, &election, [0x01; 32], &[1, 0]);
+        let signal = ballot_signal(&ballot.commitment, 0, &voter.to_bytes(), 0);
+        let (vk, proof) = eligibility(&election, &ballot.nullifier, &signal);
+
+        // Replaying a first ballot as a re-vote
+        assert_eq!(
+            verify(&election, &voter, &vk, &ballot, 1, 0, proof),
+            Err(PrivacyError::InvalidZkProof.into())
+        );
+    }
+}
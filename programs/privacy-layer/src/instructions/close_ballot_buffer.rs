@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CloseBallotBuffer<'info> {
+    /// Found from its own header, so it can be closed even after the
+    /// election's accounts are
+    #[account(
+        mut,
+        close = submitter,
+        seeds = [
+            b"ballot_buffer",
+            ballot_buffer.load()?.private_election.as_ref(),
+            submitter.key().as_ref(),
+            ballot_buffer.load()?.nullifier.as_ref(),
+        ],
+        bump = ballot_buffer.load()?.bump,
+    )]
+    pub ballot_buffer: AccountLoader<'info, BallotBuffer>,
+
+    #[account(mut)]
+    pub submitter: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<CloseBallotBuffer>) -> Result<()> {
+    msg!("Ballot buffer closed: {}", ctx.accounts.ballot_buffer.key());
+    Ok(())
+}
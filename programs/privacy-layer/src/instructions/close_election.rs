@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct CloseElection<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    /// The authority can close early; anyone can close once `ends_at` passes
+    pub closer: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<CloseElection>) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+
+    if ctx.accounts.closer.key() != private_election.authority {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= private_election.ends_at,
+            PrivacyError::ElectionNotEnded
+        );
+    }
+
+    private_election.transition_to(ElectionStatus::Ended)?;
+
+    msg!("Election closed: {}", private_election.key());
+    msg!("Total votes: {}", private_election.total_encrypted_votes);
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use anchor_lang::prelude::*;
+use crate::instructions::relay_encrypted_vote::pay_from_vault;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct CloseElectionAccounts<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"encrypted_tally", private_election.election.as_ref()],
+        bump = encrypted_tally.load()?.bump,
+    )]
+    pub encrypted_tally: AccountLoader<'info, EncryptedTally>,
+
+    /// Only for finalized elections; cancelled ones have no tally
+    #[account(
+        mut,
+        seeds = [b"tally_result", private_election.key().as_ref()],
+        bump = tally_result.bump,
+    )]
+    pub tally_result: Option<Account<'info, TallyResult>>,
+
+    /// CHECK: Required for anonymous elections, even if the key was never
+    /// set, so one can't be left open; must be omitted for public ones
+    #[account(
+        mut,
+        seeds = [b"verifying_key", private_election.key().as_ref()],
+        bump,
+    )]
+    pub verifying_key: Option<UncheckedAccount<'info>>,
+
+    /// Only for finalized elections
+    #[account(
+        init,
+        payer = payer,
+        space = ElectionResults::space(private_election.num_options),
+        seeds = [b"election_results", private_election.election.as_ref()],
+        bump
+    )]
+    pub election_results: Option<Account<'info, ElectionResults>>,
+
+    /// CHECK: Only receives lamports; must be the account that paid for the
+    /// tally result, if there is one
+    #[account(mut)]
+    pub tally_submitter: Option<UncheckedAccount<'info>>,
+
+    /// Drained to the authority
+    #[account(
+        mut,
+        seeds = [b"relayer_fee_vault", private_election.key().as_ref()],
+        bump
+    )]
+    pub relayer_fee_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<CloseElectionAccounts>) -> Result<()> {
+    let accounts = &mut *ctx.accounts;
+    let private_election = &accounts.private_election;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let finalized_at = accounts.tally_result.as_ref().map(|tally_result| tally_result.finalized_at);
+    private_election.require_closable(finalized_at, current_time)?;
+    private_election.require_verifying_key(accounts.verifying_key.is_some())?;
+
+    // Closing the election first would strand the vote accounts' rent
+    require!(
+        private_election.open_vote_accounts == 0,
+        PrivacyError::VoteAccountsOpen
+    );
+
+    // Still owned by the system program if the key was never set
+    if let Some(verifying_key) = accounts.verifying_key.as_ref().filter(|key| key.owner == &crate::ID) {
+        let authority = accounts.authority.to_account_info();
+        let refund = authority
+            .lamports()
+            .checked_add(verifying_key.lamports())
+            .ok_or(PrivacyError::ArithmeticOverflow)?;
+        **authority.try_borrow_mut_lamports()? = refund;
+        **verifying_key.try_borrow_mut_lamports()? = 0;
+        verifying_key.assign(&system_program::ID);
+        verifying_key.resize(0)?;
+    }
+
+    let remaining_fees = accounts.relayer_fee_vault.lamports();
+    if remaining_fees > 0 {
+        pay_from_vault(
+            &private_election.key(),
+            &accounts.relayer_fee_vault,
+            ctx.bumps.relayer_fee_vault,
+            accounts.authority.to_account_info(),
+            &accounts.system_program,
+            remaining_fees,
+        )?;
+    }
+
+    let Some(tally_result) = &accounts.tally_result else {
+        // A cancelled election has no results to record
+        require!(
+            accounts.election_results.is_none(),
+            PrivacyError::InvalidElectionStatus
+        );
+        msg!("Cancelled election accounts closed: {}", private_election.key());
+        return Ok(());
+    };
+    let tally_submitter = accounts
+        .tally_submitter
+        .as_ref()
+        .ok_or(ErrorCode::ConstraintAccountIsNone)?;
+    require!(
+        tally_submitter.key() == tally_result.submitted_by,
+        PrivacyError::Unauthorized
+    );
+
+    let encrypted_tally = accounts.encrypted_tally.load()?;
+    let election_results = accounts
+        .election_results
+        .as_mut()
+        .ok_or(ErrorCode::ConstraintAccountIsNone)?;
+    election_results.version = ElectionResults::VERSION;
+    election_results.bump = ctx.bumps.election_results.ok_or(ErrorCode::ConstraintAccountIsNone)?;
+    election_results.election = private_election.election;
+    election_results.mpc_public_key = private_election.mpc_public_key;
+    election_results.counts = tally_result.counts.clone();
+    election_results.aggregate = tally_result.aggregate.clone();
+    election_results.ballots = encrypted_tally.ballots;
+    election_results.total_weight = encrypted_tally.total_weight;
+    election_results.finalized_at = tally_result.finalized_at;
+
+    msg!("Election accounts closed: {}", private_election.key());
+    msg!("Results: {}", election_results.key());
+
+    tally_result.close(tally_submitter.to_account_info())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::cast_encrypted_vote::test_ballots::*;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::InstructionData;
+
+    /// Close the cancelled `election` through the program entrypoint,
+    /// passing its (unset) verifying key account if `with_verifying_key`
+    ///
+    /// The accounts are only closed on exit, which resizes them in the
+    /// runtime's own buffers, so this can only show where a close is
+    /// rejected.
+    fn close_cancelled(mut election: PrivateElection, with_verifying_key: bool) -> ProgramResult {
+        stub_sysvars();
+
+        let (key, bump) = pda(&[b"private_election", election.election.as_ref()]);
+        election.bump = bump;
+        election.status = ElectionStatus::Cancelled;
+        let (tally_key, tally_bump) = pda(&[b"encrypted_tally", election.election.as_ref()]);
+        let verifying_key = match with_verifying_key {
+            true => pda(&[b"verifying_key", key.as_ref()]).0,
+            false => crate::ID,
+        };
+
+        let mut election_data = Vec::new();
+        election.try_serialize(&mut election_data).unwrap();
+
+        // In `CloseElectionAccounts` order, with the program standing in
+        // for the omitted tally result, results and tally submitter
+        let keys = [
+            key,
+            tally_key,
+            crate::ID,
+            verifying_key,
+            crate::ID,
+            crate::ID,
+            pda(&[b"relayer_fee_vault", key.as_ref()]).0,
+            election.authority,
+            election.authority,
+            system_program::ID,
+        ];
+        let owners = [
+            crate::ID,
+            crate::ID,
+            NATIVE_LOADER_ID,
+            system_program::ID,
+            NATIVE_LOADER_ID,
+            NATIVE_LOADER_ID,
+            system_program::ID,
+            system_program::ID,
+            system_program::ID,
+            NATIVE_LOADER_ID,
+        ];
+        let mut lamports = [1_000_000_000, 1_000_000_000, 1, 0, 1, 1, 0, 1_000_000_000, 1_000_000_000, 1];
+        let mut data = [
+            election_data,
+            EncryptedTally::account_data(tally_bump, key, election.num_options),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        ];
+        let accounts: Vec<AccountInfo> = keys
+            .iter()
+            .zip(&owners)
+            .zip(&mut lamports)
+            .zip(&mut data)
+            .map(|(((key, owner), lamports), data)| {
+                let is_signer = key == &election.authority;
+                let is_program = owner == &NATIVE_LOADER_ID;
+                AccountInfo::new(key, is_signer, !is_program, lamports, data, owner, is_program, 0)
+            })
+            .collect();
+
+        let ix = crate::instruction::CloseElectionAccounts {};
+        crate::entry(&crate::ID, &accounts, &ix.data())
+    }
+
+    fn error(error: PrivacyError) -> ProgramResult {
+        Err(Error::from(error).into())
+    }
+
+    #[test]
+    fn test_anonymous_election_needs_verifying_key() {
+        assert_eq!(
+            close_cancelled(election(), false),
+            error(PrivacyError::VerifyingKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn test_public_election_takes_no_verifying_key() {
+        let mut election = election();
+        election.eligibility_mode = EligibilityMode::Public;
+
+        assert_eq!(
+            close_cancelled(election, true),
+            error(PrivacyError::VerifyingKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn test_cancelled_election_skips_audit_period() {
+        let mut election = election();
+        election.audit_period = i64::MAX;
+        election.open_vote_accounts = 1;
+
+        // Past the status, audit and verifying key checks without a tally
+        // result, up to the vote account still open
+        assert_eq!(
+            close_cancelled(election, true),
+            error(PrivacyError::VoteAccountsOpen)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct CloseVoteAccount<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    /// Only for finalized elections; cancelled ones have no tally
+    #[account(
+        seeds = [b"tally_result", private_election.key().as_ref()],
+        bump = tally_result.bump,
+    )]
+    pub tally_result: Option<Account<'info, TallyResult>>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"nullifier", private_election.key().as_ref(), nullifier_record.nullifier.as_ref()],
+        bump = nullifier_record.bump,
+    )]
+    pub nullifier_record: Account<'info, NullifierRecord>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"encrypted_vote", private_election.key().as_ref(), nullifier_record.nullifier.as_ref()],
+        bump = encrypted_vote.bump,
+    )]
+    pub encrypted_vote: Account<'info, EncryptedVote>,
+
+    /// CHECK: Only receives lamports; must be the account that paid for the vote
+    #[account(
+        mut,
+        address = nullifier_record.rent_payer @ PrivacyError::Unauthorized,
+    )]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<CloseVoteAccount>) -> Result<()> {
+    let finalized_at = ctx.accounts.tally_result.as_ref().map(|tally_result| tally_result.finalized_at);
+    let private_election = &mut ctx.accounts.private_election;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    private_election.require_closable(finalized_at, current_time)?;
+
+    private_election.open_vote_accounts = private_election
+        .open_vote_accounts
+        .checked_sub(1)
+        .ok_or(PrivacyError::ArithmeticOverflow)?;
+
+    msg!("Vote account closed for election: {}", private_election.key());
+
+    Ok(())
+}
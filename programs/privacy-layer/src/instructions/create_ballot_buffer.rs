@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32], tree_depth: u8)]
+pub struct CreateBallotBuffer<'info> {
+    #[account(
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        constraint = private_election.status == ElectionStatus::Active @ PrivacyError::ElectionNotActive,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        init,
+        payer = submitter,
+        space = BallotBuffer::space(private_election.num_options, private_election.ballot_rule, tree_depth),
+        seeds = [
+            b"ballot_buffer",
+            private_election.key().as_ref(),
+            submitter.key().as_ref(),
+            nullifier.as_ref(),
+        ],
+        bump
+    )]
+    pub ballot_buffer: AccountLoader<'info, BallotBuffer>,
+
+    /// Writes the ballot and casts, relays or recasts it; pays rent until then
+    #[account(mut)]
+    pub submitter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<CreateBallotBuffer>, nullifier: [u8; 32], tree_depth: u8) -> Result<()> {
+    let private_election = &ctx.accounts.private_election;
+    let ballot_buffer = &ctx.accounts.ballot_buffer;
+
+    // Only public voters prove eligibility with a Merkle proof
+    require!(
+        tree_depth == 0 || private_election.eligibility_mode == EligibilityMode::Public,
+        PrivacyError::InvalidMerkleProof
+    );
+
+    drop(ballot_buffer.load_init()?);
+    BallotBufferMut::init(
+        &mut ballot_buffer.as_ref().try_borrow_mut_data()?,
+        ctx.bumps.ballot_buffer,
+        private_election.key(),
+        nullifier,
+        private_election.num_options,
+        private_election.ballot_rule,
+        tree_depth,
+    )
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::program::PrivacyLayer;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct InitializeGovConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = GovConfig::LEN,
+        seeds = [b"gov_config"],
+        bump
+    )]
+    pub gov_config: Account<'info, GovConfig>,
+
+    /// Must be this program's upgrade authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, PrivacyLayer>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ PrivacyError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<InitializeGovConfig>, gov_program: Pubkey) -> Result<()> {
+    let gov_config = &mut ctx.accounts.gov_config;
+    gov_config.version = GovConfig::VERSION;
+    gov_config.bump = ctx.bumps.gov_config;
+    gov_config.gov_program = gov_program;
+
+    msg!("Governance program set: {}", gov_program);
+
+    Ok(())
+}
@@ -1,13 +1,22 @@
 use anchor_lang::prelude::*;
+use privacy_crypto::ElGamalPublicKey;
+use crate::events::PrivateElectionCreated;
+use crate::gov;
 use crate::state::*;
 use crate::errors::PrivacyError;
 
 #[derive(Accounts)]
-#[instruction(election_id: [u8; 32])]
+#[instruction(
+    mpc_public_key: [u8; 32],
+    voter_merkle_root: [u8; 32],
+    starts_at: i64,
+    ends_at: i64,
+    num_options: u8,
+)]
 pub struct InitializePrivateElection<'info> {
     #[account(
         init,
-        payer = authority,
+        payer = payer,
         space = PrivateElection::LEN,
         seeds = [b"private_election", election.key().as_ref()],
         bump
@@ -16,57 +25,126 @@ pub struct InitializePrivateElection<'info> {
 
     #[account(
         init,
-        payer = authority,
-        space = 8 + NullifierSet::INIT_LEN + 10000, // Reserve space for nullifiers
-        seeds = [b"nullifier_set", election.key().as_ref()],
+        payer = payer,
+        space = EncryptedTally::space(num_options),
+        seeds = [b"encrypted_tally", election.key().as_ref()],
         bump
     )]
-    pub nullifier_set: Account<'info, NullifierSet>,
+    pub encrypted_tally: AccountLoader<'info, EncryptedTally>,
+
+    #[account(seeds = [b"gov_config"], bump = gov_config.bump)]
+    pub gov_config: Account<'info, GovConfig>,
 
-    /// CHECK: This is the public election account from mpl-gov-micro
-    /// We don't deserialize it here, just reference it
-    pub election: AccountInfo<'info>,
+    /// CHECK: The public election account from mpl-gov-micro; its owner
+    /// and discriminator are checked in the handler
+    pub election: UncheckedAccount<'info>,
+
+    /// The public election's authority, co-signing so nobody else can
+    /// squat the `private_election` PDA for it
+    pub election_authority: Signer<'info>,
+
+    /// CHECK: Only recorded as the election admin, who also starts out
+    /// as tally submitter and guardian; may be a PDA such as a multisig
+    /// vault, since it doesn't need to sign here
+    pub authority: UncheckedAccount<'info>,
 
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub payer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handler(
     ctx: Context<InitializePrivateElection>,
-    election_id: [u8; 32],
     mpc_public_key: [u8; 32],
     voter_merkle_root: [u8; 32],
+    starts_at: i64,
     ends_at: i64,
     num_options: u8,
+    ballot_rule: BallotRule,
+    eligibility_mode: EligibilityMode,
+    allow_revoting: bool,
+    vote_weighting: VoteWeighting,
+    audit_period: i64,
+    max_relayer_fee: u64,
 ) -> Result<()> {
+    let gov_authority = gov::election_authority(
+        &ctx.accounts.election,
+        &ctx.accounts.gov_config.gov_program,
+    )?;
+    require_keys_eq!(
+        gov_authority,
+        ctx.accounts.election_authority.key(),
+        PrivacyError::Unauthorized
+    );
+
+    // Reject configurations that could never produce a usable election
+    ElGamalPublicKey::from_bytes(mpc_public_key).map_err(|_| PrivacyError::InvalidPublicKey)?;
+    PrivateElection::validate_options(num_options, ballot_rule)?;
+    let current_time = Clock::get()?.unix_timestamp;
+    PrivateElection::validate_schedule(starts_at, ends_at, current_time)?;
+    require!(
+        (0..=MAX_AUDIT_PERIOD).contains(&audit_period),
+        PrivacyError::InvalidSchedule
+    );
+
     let private_election = &mut ctx.accounts.private_election;
-    let nullifier_set = &mut ctx.accounts.nullifier_set;
+    let encrypted_tally = &ctx.accounts.encrypted_tally;
+    let election_id = PrivateElection::derive_election_id(&private_election.key());
 
     // Initialize private election
+    private_election.version = PrivateElection::VERSION;
     private_election.bump = ctx.bumps.private_election;
     private_election.authority = ctx.accounts.authority.key();
+    private_election.pending_authority = None;
+    private_election.tally_submitter = ctx.accounts.authority.key();
+    private_election.guardian = ctx.accounts.authority.key();
     private_election.election = ctx.accounts.election.key();
     private_election.mpc_public_key = mpc_public_key;
     private_election.voter_merkle_root = voter_merkle_root;
     private_election.election_id = election_id;
     private_election.total_encrypted_votes = 0;
+    private_election.open_vote_accounts = 0;
     private_election.tally_requested = false;
     private_election.tally_finalized = false;
-    private_election.created_at = Clock::get()?.unix_timestamp;
+    private_election.created_at = current_time;
+    private_election.starts_at = starts_at;
     private_election.ends_at = ends_at;
     private_election.num_options = num_options;
-    private_election.status = ElectionStatus::Active;
+    private_election.ballot_rule = ballot_rule;
+    private_election.eligibility_mode = eligibility_mode;
+    private_election.allow_revoting = allow_revoting;
+    private_election.vote_weighting = vote_weighting;
+    private_election.audit_period = audit_period;
+    private_election.max_relayer_fee = max_relayer_fee;
+    // Voting stays closed until `open_election`
+    private_election.status = ElectionStatus::Pending;
 
-    // Initialize nullifier set
-    nullifier_set.bump = ctx.bumps.nullifier_set;
-    nullifier_set.election = ctx.accounts.election.key();
-    nullifier_set.nullifiers = Vec::new();
+    // Start the running tally at an encryption of zero for every option
+    drop(encrypted_tally.load_init()?);
+    EncryptedTallyMut::init(
+        &mut encrypted_tally.as_ref().try_borrow_mut_data()?,
+        ctx.bumps.encrypted_tally,
+        private_election.key(),
+        num_options,
+    )?;
 
-    msg!("Private election initialized: {}", private_election.key());
-    msg!("Public key: {:?}", mpc_public_key);
-    msg!("Voter merkle root: {:?}", voter_merkle_root);
+    emit!(PrivateElectionCreated {
+        private_election: private_election.key(),
+        election: private_election.election,
+        authority: private_election.authority,
+        election_id,
+        mpc_public_key,
+        voter_merkle_root,
+        starts_at,
+        ends_at,
+        num_options,
+        ballot_rule,
+        eligibility_mode,
+        allow_revoting,
+        vote_weighting,
+    });
 
     Ok(())
 }
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use crate::errors::PrivacyError;
+use crate::migration;
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Any account of this program; its type comes from its
+    /// discriminator and its layout is checked by `migration::migrate`
+    #[account(mut, owner = crate::ID @ PrivacyError::UnsupportedAccountVersion)]
+    pub account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<MigrateAccount>) -> Result<()> {
+    let account = ctx.accounts.account.to_account_info();
+
+    let migrated = migration::migrate(&account.try_borrow_data()?)?;
+    let Some(data) = migrated else {
+        msg!("Account already up to date: {}", account.key());
+        return Ok(());
+    };
+
+    // Keep the account rent-exempt at its new size
+    let rent = Rent::get()?.minimum_balance(data.len());
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    account.resize(data.len())?;
+    account.try_borrow_mut_data()?.copy_from_slice(&data);
+
+    msg!("Account migrated: {}", account.key());
+
+    Ok(())
+}
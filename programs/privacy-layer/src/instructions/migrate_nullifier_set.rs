@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use crate::migration::v1::NullifierSet;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct MigrateNullifierSet<'info> {
+    /// Must already be migrated
+    #[account(
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifier_set", private_election.election.as_ref()],
+        bump = nullifier_set.bump,
+    )]
+    pub nullifier_set: Account<'info, NullifierSet>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = NullifierRecord::LEN,
+        seeds = [b"nullifier", private_election.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: Account<'info, NullifierRecord>,
+
+    /// Paid for the set, so pays for its records and gets its rent back
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<MigrateNullifierSet>, nullifier: [u8; 32]) -> Result<()> {
+    let nullifier_set = &mut ctx.accounts.nullifier_set;
+    nullifier_set.take(&nullifier)?;
+
+    let nullifier_record = &mut ctx.accounts.nullifier_record;
+    nullifier_record.version = NullifierRecord::VERSION;
+    nullifier_record.bump = ctx.bumps.nullifier_record;
+    nullifier_record.election = ctx.accounts.private_election.key();
+    nullifier_record.nullifier = nullifier;
+    nullifier_record.created_at = Clock::get()?.unix_timestamp;
+    // v1 didn't keep the voter, so the record's rent goes back to the
+    // authority
+    nullifier_record.rent_payer = ctx.accounts.authority.key();
+
+    msg!("Nullifier moved to its record: {}", nullifier_record.key());
+
+    if nullifier_set.nullifiers.is_empty() {
+        nullifier_set.close(ctx.accounts.authority.to_account_info())?;
+        msg!("Nullifier set closed: {}", nullifier_set.key());
+    }
+
+    Ok(())
+}
@@ -1,5 +1,49 @@
 pub mod initialize_private_election;
 pub mod cast_encrypted_vote;
+pub mod request_tally;
+pub mod submit_tally_result;
+pub mod set_verifying_key;
+pub mod open_election;
+pub mod close_election;
+pub mod cancel_election;
+pub mod recast_encrypted_vote;
+pub mod close_vote_account;
+pub mod close_election_accounts;
+pub mod relay_encrypted_vote;
+pub mod initialize_gov_config;
+pub mod publish_results;
+pub mod propose_authority;
+pub mod accept_authority;
+pub mod set_roles;
+pub mod migrate_account;
+pub mod migrate_nullifier_set;
+pub mod withdraw_relayer_fees;
+pub mod create_ballot_buffer;
+pub mod write_ballot_buffer;
+pub mod close_ballot_buffer;
+pub mod append_tally_result;
 
 pub use initialize_private_election::*;
 pub use cast_encrypted_vote::*;
+pub use request_tally::*;
+pub use submit_tally_result::*;
+pub use set_verifying_key::*;
+pub use open_election::*;
+pub use close_election::*;
+pub use cancel_election::*;
+pub use recast_encrypted_vote::*;
+pub use close_vote_account::*;
+pub use close_election_accounts::*;
+pub use relay_encrypted_vote::*;
+pub use initialize_gov_config::*;
+pub use publish_results::*;
+pub use propose_authority::*;
+pub use accept_authority::*;
+pub use set_roles::*;
+pub use migrate_account::*;
+pub use migrate_nullifier_set::*;
+pub use withdraw_relayer_fees::*;
+pub use create_ballot_buffer::*;
+pub use write_ballot_buffer::*;
+pub use close_ballot_buffer::*;
+pub use append_tally_result::*;
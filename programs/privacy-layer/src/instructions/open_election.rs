@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct OpenElection<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    pub authority: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<OpenElection>, starts_at: i64) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+
+    // The start may be moved while the election is pending, but the
+    // window must still be valid
+    let current_time = Clock::get()?.unix_timestamp;
+    PrivateElection::validate_schedule(starts_at, private_election.ends_at, current_time)?;
+
+    private_election.transition_to(ElectionStatus::Active)?;
+    private_election.starts_at = starts_at;
+
+    msg!("Election opened: {}", private_election.key());
+    msg!("Voting window: {} - {}", starts_at, private_election.ends_at);
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::events::AuthorityProposed;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    pub authority: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<ProposeAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+
+    // Nothing changes until the new authority signs `accept_authority`, so
+    // a mistyped key can't lock the election
+    private_election.pending_authority = new_authority;
+
+    emit!(AuthorityProposed {
+        private_election: private_election.key(),
+        authority: private_election.authority,
+        pending_authority: new_authority,
+    });
+
+    Ok(())
+}
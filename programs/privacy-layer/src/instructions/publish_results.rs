@@ -0,0 +1,173 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use crate::gov::{self, TallyProof, GOV_AUTHORITY_SEED};
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct PublishResults<'info> {
+    #[account(
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        constraint = private_election.status == ElectionStatus::Finalized @ PrivacyError::InvalidElectionStatus,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        mut,
+        seeds = [b"tally_result", private_election.key().as_ref()],
+        bump = tally_result.bump,
+        constraint = !tally_result.published @ PrivacyError::ResultsAlreadyPublished,
+    )]
+    pub tally_result: Account<'info, TallyResult>,
+
+    #[account(seeds = [b"gov_config"], bump = gov_config.bump)]
+    pub gov_config: Account<'info, GovConfig>,
+
+    /// CHECK: The public election this private election shadows, validated
+    /// at initialization; mpl-gov-micro checks it again
+    #[account(mut, address = private_election.election @ PrivacyError::InvalidGovElection)]
+    pub election: UncheckedAccount<'info>,
+
+    /// CHECK: Signs the CPI so mpl-gov-micro knows the call is from this program
+    #[account(seeds = [GOV_AUTHORITY_SEED], bump)]
+    pub gov_authority: UncheckedAccount<'info>,
+
+    /// This program, which mpl-gov-micro checks is the election's privacy layer
+    pub privacy_program: Program<'info, crate::program::PrivacyLayer>,
+
+    /// CHECK: Must be the configured mpl-gov-micro program
+    #[account(executable, address = gov_config.gov_program @ PrivacyError::InvalidGovElection)]
+    pub gov_program: UncheckedAccount<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<PublishResults>) -> Result<()> {
+    let tally_result = &ctx.accounts.tally_result;
+    let proof = TallyProof::new(
+        tally_result.key(),
+        ctx.accounts.private_election.mpc_public_key,
+        &tally_result.aggregate,
+    );
+    let ix = gov::receive_private_tally(
+        &ctx.accounts.gov_program.key(),
+        &ctx.accounts.election.key(),
+        &ctx.accounts.gov_authority.key(),
+        &tally_result.counts,
+        &proof,
+    )?;
+
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.election.to_account_info(),
+            ctx.accounts.privacy_program.to_account_info(),
+            ctx.accounts.gov_authority.to_account_info(),
+            ctx.accounts.gov_program.to_account_info(),
+        ],
+        &[&[GOV_AUTHORITY_SEED, &[ctx.bumps.gov_authority]]],
+    )?;
+
+    // mpl-gov-micro would take the counts again
+    ctx.accounts.tally_result.published = true;
+
+    msg!("Results published for election: {}", ctx.accounts.election.key());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::cast_encrypted_vote::test_ballots::*;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::InstructionData;
+
+    fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    /// Publish a finalized election's result through the program
+    /// entrypoint
+    ///
+    /// The governance call is a CPI, which only runs on-chain, so this can
+    /// only show where publishing is rejected.
+    fn publish(published: bool) -> ProgramResult {
+        stub_sysvars();
+
+        let mut election = election();
+        election.status = ElectionStatus::Finalized;
+        let (key, bump) = pda(&[b"private_election", election.election.as_ref()]);
+        election.bump = bump;
+        let (tally_result_key, tally_result_bump) = pda(&[b"tally_result", key.as_ref()]);
+        let tally_result = TallyResult {
+            version: TallyResult::VERSION,
+            bump: tally_result_bump,
+            election: key,
+            counts: vec![1, 0],
+            aggregate: vec![BallotCiphertext::default(); 2],
+            submitted_by: Pubkey::new_unique(),
+            finalized_at: 0,
+            published,
+        };
+        let (gov_config_key, gov_config_bump) = pda(&[b"gov_config"]);
+        let gov_program = Pubkey::new_unique();
+        let gov_config = GovConfig {
+            version: GovConfig::VERSION,
+            bump: gov_config_bump,
+            gov_program,
+        };
+
+        // In `PublishResults` order
+        let keys = [
+            key,
+            tally_result_key,
+            gov_config_key,
+            election.election,
+            pda(&[GOV_AUTHORITY_SEED]).0,
+            crate::ID,
+            gov_program,
+        ];
+        let owners = [
+            crate::ID,
+            crate::ID,
+            crate::ID,
+            gov_program,
+            system_program::ID,
+            NATIVE_LOADER_ID,
+            NATIVE_LOADER_ID,
+        ];
+        let mut lamports = [1_000_000_000; 7];
+        let mut data = [
+            serialize(&election),
+            serialize(&tally_result),
+            serialize(&gov_config),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        ];
+        let accounts: Vec<AccountInfo> = keys
+            .iter()
+            .zip(&owners)
+            .zip(&mut lamports)
+            .zip(&mut data)
+            .map(|(((key, owner), lamports), data)| {
+                let is_program = owner == &NATIVE_LOADER_ID;
+                AccountInfo::new(key, false, !is_program, lamports, data, owner, is_program, 0)
+            })
+            .collect();
+
+        let ix = crate::instruction::PublishResults {};
+        crate::entry(&crate::ID, &accounts, &ix.data())
+    }
+
+    #[test]
+    fn test_results_published_once() {
+        assert_eq!(
+            publish(true),
+            Err(Error::from(PrivacyError::ResultsAlreadyPublished).into())
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+use crate::groth16::{Groth16Proof, G1_LEN, G2_LEN};
+use crate::instructions::cast_encrypted_vote::verify_ballot;
+use crate::events::EncryptedVoteReplaced;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct RecastEncryptedVote<'info> {
+    #[account(
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        constraint = private_election.status == ElectionStatus::Active @ PrivacyError::ElectionNotActive,
+        constraint = private_election.allow_revoting @ PrivacyError::RevotingDisabled,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    /// The ballot being replaced; keyed by the voter's nullifier, which is
+    /// the same for every ballot they cast in this election
+    #[account(
+        mut,
+        seeds = [b"encrypted_vote", private_election.key().as_ref(), nullifier.as_ref()],
+        bump = encrypted_vote.bump,
+    )]
+    pub encrypted_vote: Account<'info, EncryptedVote>,
+
+    /// The replacement ballot, written by the voter with
+    /// `write_ballot_buffer`; its rent goes back to them once it is cast
+    #[account(
+        mut,
+        close = voter,
+        seeds = [
+            b"ballot_buffer",
+            private_election.key().as_ref(),
+            voter.key().as_ref(),
+            nullifier.as_ref(),
+        ],
+        bump = ballot_buffer.load()?.bump,
+    )]
+    pub ballot_buffer: AccountLoader<'info, BallotBuffer>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", private_election.election.as_ref()],
+        bump = encrypted_tally.load()?.bump,
+    )]
+    pub encrypted_tally: AccountLoader<'info, EncryptedTally>,
+
+    /// Required in `EligibilityMode::Anonymous`
+    #[account(
+        seeds = [b"verifying_key", private_election.key().as_ref()],
+        bump = verifying_key.load()?.bump,
+    )]
+    pub verifying_key: Option<AccountLoader<'info, EligibilityVerifyingKey>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handler(
+    ctx: Context<RecastEncryptedVote>,
+    nullifier: [u8; 32],
+    commitment: [u8; 32],
+    weight: u64,
+    zk_proof_a: Option<[u8; G1_LEN]>,
+    zk_proof_b: Option<[u8; G2_LEN]>,
+    zk_proof_c: Option<[u8; G1_LEN]>,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    ctx.accounts.private_election.require_voting_open(current_time)?;
+    let (ciphertexts, eligibility_proof) = BallotBuffer::ballot(ctx.accounts.ballot_buffer.as_ref())?;
+
+    // The replacement must pass every check a first ballot does; proving
+    // eligibility again is what stops others overwriting this ballot
+    let revision = ctx
+        .accounts
+        .encrypted_vote
+        .revision
+        .checked_add(1)
+        .ok_or(PrivacyError::ArithmeticOverflow)?;
+    verify_ballot(
+        &ctx.accounts.private_election,
+        &ctx.accounts.voter.key(),
+        ctx.accounts
+            .verifying_key
+            .as_ref()
+            .map(|verifying_key| verifying_key.load())
+            .transpose()?
+            .as_deref(),
+        &nullifier,
+        &commitment,
+        revision,
+        0,
+        &ciphertexts,
+        weight,
+        Some(&eligibility_proof),
+        Groth16Proof::from_parts(zk_proof_a, zk_proof_b, zk_proof_c),
+    )?;
+
+    let encrypted_vote = &mut ctx.accounts.encrypted_vote;
+
+    // Latest vote wins: take the old ballot out of the tally, put the new
+    // one in, and keep only the new one on-chain
+    let mut tally_data = ctx.accounts.encrypted_tally.as_ref().try_borrow_mut_data()?;
+    EncryptedTallyMut::from_account_data(&mut tally_data)?.replace(
+        &encrypted_vote.ciphertexts,
+        encrypted_vote.weight,
+        &ciphertexts,
+        weight,
+    )?;
+
+    encrypted_vote.ciphertexts = ciphertexts;
+    encrypted_vote.weight = weight;
+    encrypted_vote.commitment = commitment;
+    encrypted_vote.revision = revision;
+    encrypted_vote.timestamp = current_time;
+
+    // Only that a ballot changed is public, never what it says
+    emit!(EncryptedVoteReplaced {
+        private_election: ctx.accounts.private_election.key(),
+        nullifier,
+        ciphertexts: encrypted_vote.ciphertexts.clone(),
+        commitment,
+        weight,
+        revision,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::cast_encrypted_vote::test_ballots::*;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::InstructionData;
+    use privacy_crypto::ballot_signal;
+
+    /// Replace `old`, a vote by `voter` in `election`, with a valid
+    /// two-option ballot through the program entrypoint
+    ///
+    /// The buffer is only closed on exit, which resizes it in the
+    /// runtime's own buffers, so this can only show where a recast is
+    /// rejected.
+    fn recast(mut election: PrivateElection, voter: Pubkey, old: EncryptedVote) -> ProgramResult {
+        stub_sysvars();
+
+        let (key, bump) = pda(&[b"private_election", election.election.as_ref()]);
+        election.bump = bump;
+        let ballot = ballot(&key, &election, old.nullifier, &[0, 1]);
+        let revision = old.revision + 1;
+        let signal = ballot_signal(&ballot.commitment, revision, &voter.to_bytes(), 0);
+        let (mut verifying_key, proof) = eligibility(&election, &ballot.nullifier, &signal);
+
+        let (tally_key, tally_bump) = pda(&[b"encrypted_tally", election.election.as_ref()]);
+        let (verifying_key_key, verifying_key_bump) = pda(&[b"verifying_key", key.as_ref()]);
+        verifying_key.bump = verifying_key_bump;
+        let (buffer_key, buffer_bump) =
+            pda(&[b"ballot_buffer", key.as_ref(), voter.as_ref(), ballot.nullifier.as_ref()]);
+        let mut tally_data = EncryptedTally::account_data(tally_bump, key, election.num_options);
+        EncryptedTallyMut::from_account_data(&mut tally_data)
+            .unwrap()
+            .accumulate(&ballot.ciphertexts, 1)
+            .unwrap();
+
+        let mut election_data = Vec::new();
+        election.try_serialize(&mut election_data).unwrap();
+        let mut vote_data = Vec::new();
+        old.try_serialize(&mut vote_data).unwrap();
+
+        // In `RecastEncryptedVote` order
+        let keys = [
+            key,
+            pda(&[b"encrypted_vote", key.as_ref(), ballot.nullifier.as_ref()]).0,
+            buffer_key,
+            tally_key,
+            verifying_key_key,
+            voter,
+        ];
+        let writable = [false, true, true, true, false, true];
+        let mut lamports = [1_000_000_000; 6];
+        let mut data = [
+            election_data,
+            vote_data,
+            ballot_buffer_data(buffer_bump, &key, &election, &ballot),
+            tally_data,
+            zero_copy_data(&verifying_key),
+            Vec::new(),
+        ];
+        let accounts: Vec<AccountInfo> = keys
+            .iter()
+            .zip(&mut lamports)
+            .zip(&mut data)
+            .enumerate()
+            .map(|(i, ((key, lamports), data))| {
+                let owner = if key == &voter { &system_program::ID } else { &crate::ID };
+                AccountInfo::new(key, key == &voter, writable[i], lamports, data, owner, false, 0)
+            })
+            .collect();
+
+        let ix = crate::instruction::RecastEncryptedVote {
+            nullifier: ballot.nullifier,
+            commitment: ballot.commitment,
+            weight: 1,
+            zk_proof_a: Some(proof.a),
+            zk_proof_b: Some(proof.b),
+            zk_proof_c: Some(proof.c),
+        };
+        crate::entry(&crate::ID, &accounts, &ix.data())
+    }
+
+    #[test]
+    fn test_recast_of_migrated_vote_rejected() {
+        let (election, voter) = (election(), Pubkey::new_unique());
+        let (key, _) = pda(&[b"private_election", election.election.as_ref()]);
+        let cast = ballot(&key, &election, [0x01; 32], &[1, 0]);
+
+        // The shape a v1 vote would take: one ciphertext for the whole
+        // ballot, which can't be taken out of a per-option tally
+        let migrated = EncryptedVote {
+            version: EncryptedVote::VERSION,
+            bump: pda(&[b"encrypted_vote", key.as_ref(), cast.nullifier.as_ref()]).1,
+            election: key,
+            ciphertexts: cast.ciphertexts[..1].to_vec(),
+            nullifier: cast.nullifier,
+            commitment: cast.commitment,
+            revision: 0,
+            weight: 1,
+            timestamp: 0,
+        };
+
+        assert_eq!(
+            recast(election, voter, migrated),
+            Err(Error::from(PrivacyError::InvalidBallotSize).into())
+        );
+    }
+}
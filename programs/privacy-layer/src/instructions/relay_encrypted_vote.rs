@@ -0,0 +1,446 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use crate::groth16::{Groth16Proof, G1_LEN, G2_LEN};
+use crate::instructions::cast_encrypted_vote::{record_ballot, verify_ballot, NewVoteAccounts};
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct RelayEncryptedVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        constraint = private_election.status == ElectionStatus::Active @ PrivacyError::ElectionNotActive,
+        constraint = private_election.eligibility_mode == EligibilityMode::Anonymous @ PrivacyError::RelayRequiresAnonymous,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    /// CHECK: Must still be empty; created by `record_ballot` to mark the
+    /// nullifier as spent
+    #[account(
+        mut,
+        seeds = [b"nullifier", private_election.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: UncheckedAccount<'info>,
+
+    /// CHECK: Created by `record_ballot` alongside the nullifier record
+    #[account(
+        mut,
+        seeds = [b"encrypted_vote", private_election.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub encrypted_vote: UncheckedAccount<'info>,
+
+    /// The ballot, written by the relayer with `write_ballot_buffer`; its
+    /// rent goes back to them once it is relayed
+    #[account(
+        mut,
+        close = relayer,
+        seeds = [
+            b"ballot_buffer",
+            private_election.key().as_ref(),
+            relayer.key().as_ref(),
+            nullifier.as_ref(),
+        ],
+        bump = ballot_buffer.load()?.bump,
+    )]
+    pub ballot_buffer: AccountLoader<'info, BallotBuffer>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", private_election.election.as_ref()],
+        bump = encrypted_tally.load()?.bump,
+    )]
+    pub encrypted_tally: AccountLoader<'info, EncryptedTally>,
+
+    #[account(
+        seeds = [b"verifying_key", private_election.key().as_ref()],
+        bump = verifying_key.load()?.bump,
+    )]
+    pub verifying_key: AccountLoader<'info, EligibilityVerifyingKey>,
+
+    /// Holds lamports for relayer fees; anyone can fund it with a transfer
+    #[account(
+        mut,
+        seeds = [b"relayer_fee_vault", private_election.key().as_ref()],
+        bump
+    )]
+    pub relayer_fee_vault: SystemAccount<'info>,
+
+    /// Submits and pays for the ballot; has no say over its contents
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Check the fee vault can pay `relayer_fee` and keep the rent-exempt
+/// minimum of an empty account
+///
+/// Otherwise the transfer would fail with a runtime rent error, or empty
+/// the vault entirely.
+pub(crate) fn require_vault_can_pay(vault_lamports: u64, relayer_fee: u64, rent: &Rent) -> Result<()> {
+    let required = rent
+        .minimum_balance(0)
+        .checked_add(relayer_fee)
+        .ok_or(PrivacyError::ArithmeticOverflow)?;
+    require!(
+        vault_lamports >= required,
+        PrivacyError::RelayerFeeVaultUnderfunded
+    );
+    Ok(())
+}
+
+/// Pay `lamports` out of the election's relayer fee vault, signing for
+/// the vault PDA
+pub(crate) fn pay_from_vault<'info>(
+    private_election: &Pubkey,
+    relayer_fee_vault: &SystemAccount<'info>,
+    vault_bump: u8,
+    to: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    lamports: u64,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"relayer_fee_vault",
+        private_election.as_ref(),
+        &[vault_bump],
+    ]];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Transfer {
+                from: relayer_fee_vault.to_account_info(),
+                to,
+            },
+            signer_seeds,
+        ),
+        lamports,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handler(
+    ctx: Context<RelayEncryptedVote>,
+    nullifier: [u8; 32],
+    commitment: [u8; 32],
+    weight: u64,
+    zk_proof_a: [u8; G1_LEN],
+    zk_proof_b: [u8; G2_LEN],
+    zk_proof_c: [u8; G1_LEN],
+    relayer_fee: u64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    ctx.accounts.private_election.require_voting_open(current_time)?;
+    NullifierRecord::require_unspent(&ctx.accounts.nullifier_record)?;
+    let (ciphertexts, _) = BallotBuffer::ballot(ctx.accounts.ballot_buffer.as_ref())?;
+    require!(
+        relayer_fee <= ctx.accounts.private_election.max_relayer_fee,
+        PrivacyError::RelayerFeeTooHigh
+    );
+    if relayer_fee > 0 {
+        require_vault_can_pay(
+            ctx.accounts.relayer_fee_vault.lamports(),
+            relayer_fee,
+            &Rent::get()?,
+        )?;
+    }
+
+    // The proof's signal commits to this relayer and fee, so neither can
+    // be changed by whoever sees the ballot before it lands
+    verify_ballot(
+        &ctx.accounts.private_election,
+        &ctx.accounts.relayer.key(),
+        Some(&*ctx.accounts.verifying_key.load()?),
+        &nullifier,
+        &commitment,
+        0,
+        relayer_fee,
+        &ciphertexts,
+        weight,
+        None,
+        Some(Groth16Proof {
+            a: zk_proof_a,
+            b: zk_proof_b,
+            c: zk_proof_c,
+        }),
+    )?;
+
+    if relayer_fee > 0 {
+        pay_from_vault(
+            &ctx.accounts.private_election.key(),
+            &ctx.accounts.relayer_fee_vault,
+            ctx.bumps.relayer_fee_vault,
+            ctx.accounts.relayer.to_account_info(),
+            &ctx.accounts.system_program,
+            relayer_fee,
+        )?;
+    }
+
+    // The relayer is recorded only as the rent payer for refunds; nothing
+    // stored links the ballot to the voter's wallet
+    let accounts = &mut *ctx.accounts;
+    let mut tally_data = accounts.encrypted_tally.as_ref().try_borrow_mut_data()?;
+    record_ballot(
+        &mut accounts.private_election,
+        &mut EncryptedTallyMut::from_account_data(&mut tally_data)?,
+        NewVoteAccounts {
+            nullifier_record: &accounts.nullifier_record,
+            nullifier_record_bump: ctx.bumps.nullifier_record,
+            encrypted_vote: &accounts.encrypted_vote,
+            encrypted_vote_bump: ctx.bumps.encrypted_vote,
+            payer: &accounts.relayer,
+            system_program: &accounts.system_program,
+        },
+        nullifier,
+        commitment,
+        ciphertexts,
+        weight,
+        current_time,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::cast_encrypted_vote::test_ballots::*;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::InstructionData;
+    use privacy_crypto::ballot_signal;
+
+    /// A relayed ballot and the accounts it is submitted with
+    struct Relay {
+        key: Pubkey,
+        election: PrivateElection,
+        verifying_key: EligibilityVerifyingKey,
+        relayer: Pubkey,
+        ballot: Ballot,
+        proof: Groth16Proof,
+        relayer_fee: u64,
+        vault_lamports: u64,
+    }
+
+    impl Relay {
+        /// A valid relay of a ballot in `election` for `relayer_fee`, with
+        /// a vault that can pay it
+        fn new(mut election: PrivateElection, relayer_fee: u64) -> Self {
+            let (key, bump) = pda(&[b"private_election", election.election.as_ref()]);
+            election.bump = bump;
+            let relayer = Pubkey::new_unique();
+            let ballot = ballot(&key, &election, [0x01; 32], &[1, 0]);
+            let signal = ballot_signal(&ballot.commitment, 0, &relayer.to_bytes(), relayer_fee);
+            let (verifying_key, proof) = eligibility(&election, &ballot.nullifier, &signal);
+
+            Relay {
+                key,
+                election,
+                verifying_key,
+                relayer,
+                ballot,
+                proof,
+                relayer_fee,
+                vault_lamports: Rent::default().minimum_balance(0) + relayer_fee,
+            }
+        }
+
+        /// Submit it through the program entrypoint
+        ///
+        /// The fee transfer and the new vote accounts are CPIs, which only
+        /// run on-chain, so this can only show where a relay is rejected.
+        fn run(&self) -> ProgramResult {
+            stub_sysvars();
+
+            let (tally_key, tally_bump) = pda(&[b"encrypted_tally", self.election.election.as_ref()]);
+            let (verifying_key_key, verifying_key_bump) = pda(&[b"verifying_key", self.key.as_ref()]);
+            let nullifier = self.ballot.nullifier.as_ref();
+            let (buffer_key, buffer_bump) =
+                pda(&[b"ballot_buffer", self.key.as_ref(), self.relayer.as_ref(), nullifier]);
+
+            let mut election_data = Vec::new();
+            self.election.try_serialize(&mut election_data).unwrap();
+            let mut verifying_key = self.verifying_key;
+            verifying_key.bump = verifying_key_bump;
+
+            // In `RelayEncryptedVote` order
+            let keys = [
+                self.key,
+                pda(&[b"nullifier", self.key.as_ref(), nullifier]).0,
+                pda(&[b"encrypted_vote", self.key.as_ref(), nullifier]).0,
+                buffer_key,
+                tally_key,
+                verifying_key_key,
+                pda(&[b"relayer_fee_vault", self.key.as_ref()]).0,
+                self.relayer,
+                system_program::ID,
+            ];
+            let owners = [
+                crate::ID,
+                system_program::ID,
+                system_program::ID,
+                crate::ID,
+                crate::ID,
+                crate::ID,
+                system_program::ID,
+                system_program::ID,
+                NATIVE_LOADER_ID,
+            ];
+            let writable = [true, true, true, true, true, false, true, true, false];
+            let mut lamports = [
+                1_000_000_000,
+                0,
+                0,
+                1_000_000_000,
+                1_000_000_000,
+                1_000_000_000,
+                self.vault_lamports,
+                1_000_000_000,
+                1,
+            ];
+            let mut data = [
+                election_data,
+                Vec::new(),
+                Vec::new(),
+                ballot_buffer_data(buffer_bump, &self.key, &self.election, &self.ballot),
+                EncryptedTally::account_data(tally_bump, self.key, self.election.num_options),
+                zero_copy_data(&verifying_key),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+            ];
+            let accounts: Vec<AccountInfo> = keys
+                .iter()
+                .zip(&mut lamports)
+                .zip(&mut data)
+                .enumerate()
+                .map(|(i, ((key, lamports), data))| {
+                    let is_relayer = key == &self.relayer;
+                    let is_system_program = i == keys.len() - 1;
+                    AccountInfo::new(key, is_relayer, writable[i], lamports, data, &owners[i], is_system_program, 0)
+                })
+                .collect();
+
+            let ix = crate::instruction::RelayEncryptedVote {
+                nullifier: self.ballot.nullifier,
+                commitment: self.ballot.commitment,
+                weight: 1,
+                zk_proof_a: self.proof.a,
+                zk_proof_b: self.proof.b,
+                zk_proof_c: self.proof.c,
+                relayer_fee: self.relayer_fee,
+            };
+            crate::entry(&crate::ID, &accounts, &ix.data())
+        }
+    }
+
+    fn error(error: PrivacyError) -> ProgramResult {
+        Err(Error::from(error).into())
+    }
+
+    #[test]
+    fn test_relay_proof_bound_to_relayer() {
+        let (key, election, relayer) = (Pubkey::new_unique(), election(), Pubkey::new_unique());
+        let ballot = ballot(&key, &election, [0x01; 32], &[1, 0]);
+        let signal = ballot_signal(&ballot.commitment, 0, &relayer.to_bytes(), 5_000);
+        let (vk, proof) = eligibility(&election, &ballot.nullifier, &signal);
+
+        assert!(verify(&election, &relayer, &vk, &ballot, 0, 5_000, proof).is_ok());
+        // Whoever sees the ballot before it lands resubmits it as their own
+        assert_eq!(
+            verify(&election, &Pubkey::new_unique(), &vk, &ballot, 0, 5_000, proof),
+            Err(PrivacyError::InvalidZkProof.into())
+        );
+    }
+
+    #[test]
+    fn test_relay_proof_bound_to_fee() {
+        let (key, election, relayer) = (Pubkey::new_unique(), election(), Pubkey::new_unique());
+        let ballot = ballot(&key, &election, [0x01; 32], &[1, 0]);
+        let signal = ballot_signal(&ballot.commitment, 0, &relayer.to_bytes(), 5_000);
+        let (vk, proof) = eligibility(&election, &ballot.nullifier, &signal);
+
+        // The relayer raises its own fee, still within the maximum
+        assert_eq!(
+            verify(&election, &relayer, &vk, &ballot, 0, 5_001, proof),
+            Err(PrivacyError::InvalidZkProof.into())
+        );
+        assert_eq!(
+            verify(&election, &relayer, &vk, &ballot, 0, 0, proof),
+            Err(PrivacyError::InvalidZkProof.into())
+        );
+    }
+
+    #[test]
+    fn test_relay_by_another_relayer() {
+        let mut relay = Relay::new(election(), 5_000);
+        relay.relayer = Pubkey::new_unique();
+
+        assert_eq!(relay.run(), error(PrivacyError::InvalidZkProof));
+    }
+
+    #[test]
+    fn test_relay_requires_anonymous() {
+        let mut election = election();
+        election.eligibility_mode = EligibilityMode::Public;
+
+        assert_eq!(
+            Relay::new(election, 0).run(),
+            error(PrivacyError::RelayRequiresAnonymous)
+        );
+    }
+
+    #[test]
+    fn test_relayer_fee_limit() {
+        let max_relayer_fee = election().max_relayer_fee;
+        assert_eq!(
+            Relay::new(election(), max_relayer_fee + 1).run(),
+            error(PrivacyError::RelayerFeeTooHigh)
+        );
+
+        // The maximum itself passes the fee checks and fails only on the
+        // proof, made for another relayer
+        let mut relay = Relay::new(election(), max_relayer_fee);
+        relay.relayer = Pubkey::new_unique();
+        assert_eq!(relay.run(), error(PrivacyError::InvalidZkProof));
+    }
+
+    #[test]
+    fn test_fee_vault_stays_rent_exempt() {
+        let mut relay = Relay::new(election(), 5_000);
+        relay.vault_lamports -= 1;
+        assert_eq!(relay.run(), error(PrivacyError::RelayerFeeVaultUnderfunded));
+
+        // Holding the fee alone isn't enough
+        relay.vault_lamports = 5_000;
+        assert_eq!(relay.run(), error(PrivacyError::RelayerFeeVaultUnderfunded));
+    }
+
+    #[test]
+    fn test_unpaid_relay_needs_no_vault() {
+        let mut relay = Relay::new(election(), 0);
+        relay.vault_lamports = 0;
+        relay.relayer = Pubkey::new_unique();
+
+        // Past the vault check, up to the proof made for another relayer
+        assert_eq!(relay.run(), error(PrivacyError::InvalidZkProof));
+    }
+
+    #[test]
+    fn test_require_vault_can_pay() {
+        let rent = Rent::default();
+        let minimum = rent.minimum_balance(0);
+
+        assert!(require_vault_can_pay(minimum + 5_000, 5_000, &rent).is_ok());
+        assert!(require_vault_can_pay(minimum + 10_000, 5_000, &rent).is_ok());
+        assert_eq!(
+            require_vault_can_pay(minimum + 4_999, 5_000, &rent),
+            Err(PrivacyError::RelayerFeeVaultUnderfunded.into())
+        );
+        assert_eq!(
+            require_vault_can_pay(u64::MAX, u64::MAX, &rent),
+            Err(PrivacyError::ArithmeticOverflow.into())
+        );
+    }
+}
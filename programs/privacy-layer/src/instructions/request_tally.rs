@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use crate::events::TallyRequested;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct RequestTally<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    pub authority: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<RequestTally>) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+
+    require!(
+        !private_election.tally_requested,
+        PrivacyError::TallyAlreadyRequested
+    );
+
+    // Voting must be over before anyone decrypts the aggregate; an
+    // election still marked active closes here once `ends_at` has passed
+    let current_time = Clock::get()?.unix_timestamp;
+    if private_election.status == ElectionStatus::Active {
+        require!(
+            current_time >= private_election.ends_at,
+            PrivacyError::ElectionNotEnded
+        );
+        private_election.transition_to(ElectionStatus::Ended)?;
+    }
+
+    require!(
+        private_election.status == ElectionStatus::Ended,
+        PrivacyError::InvalidElectionStatus
+    );
+
+    private_election.tally_requested = true;
+
+    emit!(TallyRequested {
+        private_election: private_election.key(),
+        total_votes: private_election.total_encrypted_votes,
+        requested_at: current_time,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use crate::events::RolesUpdated;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct SetRoles<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    pub authority: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<SetRoles>, tally_submitter: Pubkey, guardian: Pubkey) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+
+    // Roles only matter until the election is over
+    require!(
+        !private_election.status.is_terminal(),
+        PrivacyError::InvalidElectionStatus
+    );
+
+    private_election.tally_submitter = tally_submitter;
+    private_election.guardian = guardian;
+
+    emit!(RolesUpdated {
+        private_election: private_election.key(),
+        tally_submitter,
+        guardian,
+    });
+
+    Ok(())
+}
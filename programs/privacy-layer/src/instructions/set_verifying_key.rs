@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use crate::groth16::{G1_LEN, G2_LEN};
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct SetVerifyingKey<'info> {
+    #[account(
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    /// Created once; the circuit can't be swapped out later
+    #[account(
+        init,
+        payer = payer,
+        space = EligibilityVerifyingKey::LEN,
+        seeds = [b"verifying_key", private_election.key().as_ref()],
+        bump
+    )]
+    pub verifying_key: AccountLoader<'info, EligibilityVerifyingKey>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(
+    ctx: Context<SetVerifyingKey>,
+    alpha_g1: [u8; G1_LEN],
+    beta_g2: [u8; G2_LEN],
+    gamma_g2: [u8; G2_LEN],
+    delta_g2: [u8; G2_LEN],
+    ic: Vec<[u8; G1_LEN]>,
+) -> Result<()> {
+    let private_election = &ctx.accounts.private_election;
+
+    // Votes cast before the key existed would never have been checked
+    require!(
+        private_election.status == ElectionStatus::Pending,
+        PrivacyError::InvalidElectionStatus
+    );
+    // Public elections check eligibility against the Merkle root alone
+    private_election.require_verifying_key(true)?;
+    let ic = ic
+        .try_into()
+        .map_err(|_| PrivacyError::InvalidZkProof)?;
+
+    let mut verifying_key = ctx.accounts.verifying_key.load_init()?;
+    verifying_key.version = EligibilityVerifyingKey::VERSION;
+    verifying_key.bump = ctx.bumps.verifying_key;
+    verifying_key.election = private_election.key();
+    verifying_key.alpha_g1 = alpha_g1;
+    verifying_key.beta_g2 = beta_g2;
+    verifying_key.gamma_g2 = gamma_g2;
+    verifying_key.delta_g2 = delta_g2;
+    verifying_key.ic = ic;
+
+    msg!("Verifying key set for election: {}", private_election.key());
+
+    Ok(())
+}
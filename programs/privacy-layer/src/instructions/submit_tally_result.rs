@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+use privacy_crypto::DleqProof;
+use crate::events::TallyFinalized;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct SubmitTallyResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        seeds = [b"encrypted_tally", private_election.election.as_ref()],
+        bump = encrypted_tally.load()?.bump,
+    )]
+    pub encrypted_tally: AccountLoader<'info, EncryptedTally>,
+
+    #[account(
+        init,
+        payer = submitter,
+        space = TallyResult::space(private_election.num_options),
+        seeds = [b"tally_result", private_election.key().as_ref()],
+        bump
+    )]
+    pub tally_result: Account<'info, TallyResult>,
+
+    #[account(
+        mut,
+        address = private_election.tally_submitter @ PrivacyError::Unauthorized,
+    )]
+    pub submitter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Chaum-Pedersen proof of correct decryption as passed in instruction data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct DecryptionProofArgs {
+    /// Fiat-Shamir challenge (32 bytes)
+    pub challenge: [u8; 32],
+
+    /// Response (32 bytes)
+    pub response: [u8; 32],
+}
+
+impl From<DecryptionProofArgs> for DleqProof {
+    fn from(args: DecryptionProofArgs) -> Self {
+        DleqProof {
+            challenge: args.challenge,
+            response: args.response,
+        }
+    }
+}
+
+pub(crate) fn handler(
+    ctx: Context<SubmitTallyResult>,
+    counts: Vec<u64>,
+    proofs: Vec<DecryptionProofArgs>,
+) -> Result<()> {
+    let private_election = &mut ctx.accounts.private_election;
+    let tally_data = ctx.accounts.encrypted_tally.as_ref().try_borrow_data()?;
+    let encrypted_tally = EncryptedTallyRef::from_account_data(&tally_data)?;
+    let tally_result = &mut ctx.accounts.tally_result;
+
+    require!(
+        !private_election.tally_finalized,
+        PrivacyError::TallyAlreadyFinalized
+    );
+    require!(
+        private_election.tally_requested,
+        PrivacyError::TallyNotRequested
+    );
+    require!(
+        private_election.status == ElectionStatus::Ended,
+        PrivacyError::InvalidElectionStatus
+    );
+
+    tally_result.version = TallyResult::VERSION;
+    tally_result.bump = ctx.bumps.tally_result;
+    tally_result.election = private_election.key();
+    tally_result.counts = Vec::new();
+    tally_result.aggregate = encrypted_tally.aggregate.to_vec();
+    tally_result.submitted_by = ctx.accounts.submitter.key();
+    tally_result.finalized_at = 0;
+    tally_result.published = false;
+
+    record_counts(private_election, &encrypted_tally, tally_result, counts, proofs)
+}
+
+/// Verify and store the counts of the next options, finalizing the
+/// election once every option has one
+///
+/// Shared by `submit_tally_result` and `append_tally_result`, so the counts
+/// can be split over as many transactions as the election's options need.
+pub(crate) fn record_counts(
+    private_election: &mut PrivateElection,
+    encrypted_tally: &EncryptedTallyRef,
+    tally_result: &mut TallyResult,
+    counts: Vec<u64>,
+    proofs: Vec<DecryptionProofArgs>,
+) -> Result<()> {
+    // Proofs are checked against the aggregate in the tally account, the
+    // only copy of it the submitter can't choose
+    let proofs: Vec<DleqProof> = proofs.into_iter().map(Into::into).collect();
+    encrypted_tally.verify_decryption(
+        &private_election.mpc_public_key,
+        tally_result.counts.len(),
+        &counts,
+        &proofs,
+    )?;
+    tally_result.counts.extend(counts);
+
+    if tally_result.counts.len() < private_election.num_options as usize {
+        return Ok(());
+    }
+    encrypted_tally.verify_total(private_election.ballot_rule, &tally_result.counts)?;
+
+    tally_result.finalized_at = Clock::get()?.unix_timestamp;
+    private_election.tally_finalized = true;
+    private_election.transition_to(ElectionStatus::Finalized)?;
+
+    emit!(TallyFinalized::from(&*tally_result));
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use crate::instructions::relay_encrypted_vote::pay_from_vault;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+pub struct WithdrawRelayerFees<'info> {
+    /// No relays are possible once the election is finalized or cancelled
+    #[account(
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+        constraint = private_election.status.is_terminal() @ PrivacyError::InvalidElectionStatus,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        mut,
+        seeds = [b"relayer_fee_vault", private_election.key().as_ref()],
+        bump
+    )]
+    pub relayer_fee_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<WithdrawRelayerFees>) -> Result<()> {
+    let remaining = ctx.accounts.relayer_fee_vault.lamports();
+    if remaining > 0 {
+        pay_from_vault(
+            &ctx.accounts.private_election.key(),
+            &ctx.accounts.relayer_fee_vault,
+            ctx.bumps.relayer_fee_vault,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program,
+            remaining,
+        )?;
+    }
+
+    msg!("Relayer fees withdrawn: {} lamports", remaining);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::cast_encrypted_vote::test_ballots::*;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::InstructionData;
+
+    /// Withdraw from `election`'s vault through the program entrypoint
+    ///
+    /// The transfer is a CPI, which only runs on-chain, so this can only
+    /// show where a withdrawal is rejected.
+    fn withdraw(mut election: PrivateElection, authority: Pubkey) -> ProgramResult {
+        stub_sysvars();
+
+        let (key, bump) = pda(&[b"private_election", election.election.as_ref()]);
+        election.bump = bump;
+        let mut election_data = Vec::new();
+        election.try_serialize(&mut election_data).unwrap();
+
+        // In `WithdrawRelayerFees` order
+        let keys = [
+            key,
+            pda(&[b"relayer_fee_vault", key.as_ref()]).0,
+            authority,
+            system_program::ID,
+        ];
+        let owners = [crate::ID, system_program::ID, system_program::ID, NATIVE_LOADER_ID];
+        let mut lamports = [1_000_000_000, 5_000_000, 1_000_000_000, 1];
+        let mut data = [election_data, Vec::new(), Vec::new(), Vec::new()];
+        let accounts: Vec<AccountInfo> = keys
+            .iter()
+            .zip(&owners)
+            .zip(&mut lamports)
+            .zip(&mut data)
+            .map(|(((key, owner), lamports), data)| {
+                let is_authority = key == &authority;
+                let is_program = owner == &NATIVE_LOADER_ID;
+                AccountInfo::new(key, is_authority, !is_program, lamports, data, owner, is_program, 0)
+            })
+            .collect();
+
+        let ix = crate::instruction::WithdrawRelayerFees {};
+        crate::entry(&crate::ID, &accounts, &ix.data())
+    }
+
+    fn error(error: PrivacyError) -> ProgramResult {
+        Err(Error::from(error).into())
+    }
+
+    #[test]
+    fn test_withdraw_only_after_election() {
+        for status in [ElectionStatus::Pending, ElectionStatus::Active, ElectionStatus::Ended] {
+            let mut election = election();
+            election.status = status;
+            let authority = election.authority;
+
+            assert_eq!(withdraw(election, authority), error(PrivacyError::InvalidElectionStatus));
+        }
+    }
+
+    #[test]
+    fn test_withdraw_requires_authority() {
+        let mut election = election();
+        election.status = ElectionStatus::Cancelled;
+
+        assert_eq!(
+            withdraw(election, Pubkey::new_unique()),
+            error(PrivacyError::Unauthorized)
+        );
+    }
+}
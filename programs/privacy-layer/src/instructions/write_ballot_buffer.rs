@@ -0,0 +1,301 @@
+use anchor_lang::prelude::*;
+use privacy_crypto::{
+    verify_slot_proof, verify_total_proof, BallotProof, BinaryProof, ElGamalPublicKey,
+    EncryptedBallot, MembershipProof,
+};
+use crate::instructions::cast_encrypted_vote::vote_proof_context;
+use crate::state::*;
+use crate::errors::PrivacyError;
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct WriteBallotBuffer<'info> {
+    #[account(
+        seeds = [b"private_election", private_election.election.as_ref()],
+        bump = private_election.bump,
+        constraint = private_election.status == ElectionStatus::Active @ PrivacyError::ElectionNotActive,
+    )]
+    pub private_election: Account<'info, PrivateElection>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"ballot_buffer",
+            private_election.key().as_ref(),
+            submitter.key().as_ref(),
+            nullifier.as_ref(),
+        ],
+        bump = ballot_buffer.load()?.bump,
+    )]
+    pub ballot_buffer: AccountLoader<'info, BallotBuffer>,
+
+    pub submitter: Signer<'info>,
+}
+
+/// Part of a ballot as passed in instruction data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum BallotChunk {
+    /// Ciphertexts of options `first..`, each with its 0/1 proof (128
+    /// bytes each)
+    Options {
+        first: u8,
+        ciphertexts: Vec<BallotCiphertext>,
+        slot_proofs: Vec<[u8; 128]>,
+    },
+
+    /// Challenges and responses `first..` of the proof that the option
+    /// total follows the ballot rule
+    TotalProof {
+        first: u8,
+        challenges: Vec<[u8; 32]>,
+        responses: Vec<[u8; 32]>,
+    },
+
+    /// Siblings `first..` of the voter's Merkle proof in a public
+    /// election, leaf level first, and the position of their leaf
+    MerkleProof {
+        leaf_index: u32,
+        first: u8,
+        siblings: Vec<[u8; 32]>,
+    },
+}
+
+/// Ballot validity proof, written to a buffer alongside the ciphertexts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BallotProofArgs {
+    /// One 0/1 proof per option (128 bytes each)
+    pub slot_proofs: Vec<[u8; 128]>,
+
+    /// Challenges of the proof that the option total follows the ballot rule
+    pub total_challenges: Vec<[u8; 32]>,
+
+    /// Responses of the proof that the option total follows the ballot rule
+    pub total_responses: Vec<[u8; 32]>,
+}
+
+impl From<BallotProofArgs> for BallotProof {
+    fn from(args: BallotProofArgs) -> Self {
+        BallotProof {
+            slot_proofs: args.slot_proofs.iter().map(BinaryProof::from_bytes).collect(),
+            total_proof: MembershipProof {
+                challenges: args.total_challenges,
+                responses: args.total_responses,
+            },
+        }
+    }
+}
+
+pub(crate) fn handler(ctx: Context<WriteBallotBuffer>, nullifier: [u8; 32], chunk: BallotChunk) -> Result<()> {
+    let private_election = &ctx.accounts.private_election;
+    let public_key = ElGamalPublicKey::from_bytes(private_election.mpc_public_key)
+        .map_err(|_| PrivacyError::InvalidPublicKey)?;
+    let context = vote_proof_context(&private_election.key(), &nullifier);
+
+    let mut buffer_data = ctx.accounts.ballot_buffer.as_ref().try_borrow_mut_data()?;
+    let mut buffer = BallotBufferMut::from_account_data(&mut buffer_data)?;
+
+    match chunk {
+        BallotChunk::Options {
+            first,
+            ciphertexts,
+            slot_proofs,
+        } => {
+            require!(
+                slot_proofs.len() == ciphertexts.len(),
+                PrivacyError::InvalidBallotSize
+            );
+
+            // Reject malformed points, and options encrypting anything but
+            // 0 or 1, before they can reach the running tally
+            for (slot, (ciphertext, proof)) in (first as usize..).zip(ciphertexts.iter().zip(&slot_proofs)) {
+                ciphertext.validate()?;
+                verify_slot_proof(
+                    &public_key,
+                    &(*ciphertext).into(),
+                    &BinaryProof::from_bytes(proof),
+                    slot,
+                    &context,
+                )
+                .map_err(|_| PrivacyError::InvalidVoteProof)?;
+            }
+            buffer.write_options(first, &ciphertexts)
+        }
+        BallotChunk::TotalProof {
+            first,
+            challenges,
+            responses,
+        } => {
+            buffer.write_total_proof(first, &challenges, &responses)?;
+
+            // The last entry completes the ballot; a total proof that fails
+            // reverts the write, so a proven ballot is always a valid one
+            let buffer = buffer.as_ref();
+            if buffer.is_proven() {
+                let ballot = EncryptedBallot {
+                    ciphertexts: buffer.ciphertexts.iter().map(|&ct| ct.into()).collect(),
+                };
+                let proof = MembershipProof {
+                    challenges: buffer.challenges.to_vec(),
+                    responses: buffer.responses.to_vec(),
+                };
+                verify_total_proof(
+                    &public_key,
+                    &ballot,
+                    &proof,
+                    private_election.ballot_rule.into(),
+                    &context,
+                )
+                .map_err(|_| PrivacyError::InvalidVoteProof)?;
+            }
+            Ok(())
+        }
+        // Checked against the voter tree, the voter and their weight when
+        // the ballot is cast
+        BallotChunk::MerkleProof {
+            leaf_index,
+            first,
+            siblings,
+        } => buffer.write_merkle_proof(leaf_index, first, &siblings),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::cast_encrypted_vote::test_ballots::*;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::InstructionData;
+
+    /// An empty buffer for a ballot in an election with `num_options`
+    /// options, and the accounts it is written with
+    struct Write {
+        key: Pubkey,
+        election_data: Vec<u8>,
+        buffer_key: Pubkey,
+        buffer_data: Vec<u8>,
+        submitter: Pubkey,
+    }
+
+    impl Write {
+        fn new(election: &mut PrivateElection, nullifier: [u8; 32]) -> Self {
+            let (key, bump) = pda(&[b"private_election", election.election.as_ref()]);
+            election.bump = bump;
+            let mut election_data = Vec::new();
+            election.try_serialize(&mut election_data).unwrap();
+
+            let submitter = Pubkey::new_unique();
+            let (buffer_key, buffer_bump) =
+                pda(&[b"ballot_buffer", key.as_ref(), submitter.as_ref(), nullifier.as_ref()]);
+            let buffer_data =
+                BallotBuffer::account_data(buffer_bump, key, nullifier, election.num_options, election.ballot_rule, 0);
+
+            Write {
+                key,
+                election_data,
+                buffer_key,
+                buffer_data,
+                submitter,
+            }
+        }
+
+        /// Write `chunk` through the program entrypoint
+        fn run(&mut self, nullifier: [u8; 32], chunk: BallotChunk) -> ProgramResult {
+            let (mut election_lamports, mut buffer_lamports, mut submitter_lamports) =
+                (1_000_000_000, 1_000_000_000, 1_000_000_000);
+            let mut submitter_data = Vec::new();
+            let accounts = [
+                AccountInfo::new(&self.key, false, false, &mut election_lamports, &mut self.election_data, &crate::ID, false, 0),
+                AccountInfo::new(&self.buffer_key, false, true, &mut buffer_lamports, &mut self.buffer_data, &crate::ID, false, 0),
+                AccountInfo::new(&self.submitter, true, false, &mut submitter_lamports, &mut submitter_data, &system_program::ID, false, 0),
+            ];
+
+            let ix = crate::instruction::WriteBallotBuffer { nullifier, chunk };
+            crate::entry(&crate::ID, &accounts, &ix.data())
+        }
+
+        fn buffer(&self) -> BallotBufferRef<'_> {
+            BallotBufferRef::from_account_data(&self.buffer_data).unwrap()
+        }
+    }
+
+    fn options(ballot: &Ballot, first: usize, count: usize) -> BallotChunk {
+        BallotChunk::Options {
+            first: first as u8,
+            ciphertexts: ballot.ciphertexts[first..first + count].to_vec(),
+            slot_proofs: ballot.proof.slot_proofs[first..first + count].to_vec(),
+        }
+    }
+
+    fn total_proof(ballot: &Ballot, first: usize, count: usize) -> BallotChunk {
+        BallotChunk::TotalProof {
+            first: first as u8,
+            challenges: ballot.proof.total_challenges[first..first + count].to_vec(),
+            responses: ballot.proof.total_responses[first..first + count].to_vec(),
+        }
+    }
+
+    fn error(error: PrivacyError) -> ProgramResult {
+        Err(Error::from(error).into())
+    }
+
+    #[test]
+    fn test_ballot_written_in_parts() {
+        stub_sysvars();
+        let mut election = election();
+        election.num_options = 4;
+        election.ballot_rule = BallotRule::Approval { max_choices: 3 };
+        let mut write = Write::new(&mut election, [0x01; 32]);
+        let ballot = ballot(&write.key, &election, [0x01; 32], &[1, 0, 1, 1]);
+
+        write.run(ballot.nullifier, options(&ballot, 0, 3)).unwrap();
+        write.run(ballot.nullifier, options(&ballot, 3, 1)).unwrap();
+        write.run(ballot.nullifier, total_proof(&ballot, 0, 2)).unwrap();
+        assert!(!write.buffer().is_complete());
+        write.run(ballot.nullifier, total_proof(&ballot, 2, 2)).unwrap();
+
+        assert!(write.buffer().ballot().unwrap() == &ballot.ciphertexts[..]);
+    }
+
+    #[test]
+    fn test_options_checked_as_written() {
+        stub_sysvars();
+        let mut election = election();
+        let mut write = Write::new(&mut election, [0x01; 32]);
+        let other = ballot(&write.key, &election, [0x02; 32], &[1, 0]);
+        let ballot = ballot(&write.key, &election, [0x01; 32], &[1, 0]);
+
+        // Each 0/1 proof is bound to its option
+        let swapped = BallotChunk::Options {
+            first: 0,
+            ciphertexts: vec![ballot.ciphertexts[1], ballot.ciphertexts[0]],
+            slot_proofs: vec![ballot.proof.slot_proofs[1], ballot.proof.slot_proofs[0]],
+        };
+        assert_eq!(write.run(ballot.nullifier, swapped), error(PrivacyError::InvalidVoteProof));
+
+        // And to the nullifier the buffer is for
+        assert_eq!(
+            write.run(ballot.nullifier, options(&other, 0, 2)),
+            error(PrivacyError::InvalidVoteProof)
+        );
+    }
+
+    #[test]
+    fn test_total_proof_checked_when_complete() {
+        stub_sysvars();
+        let mut election = election();
+        election.ballot_rule = BallotRule::Approval { max_choices: 1 };
+        let mut write = Write::new(&mut election, [0x01; 32]);
+
+        // Every option is 0 or 1, but the total proof is for two choices
+        let mut approve_both = election.clone();
+        approve_both.ballot_rule = BallotRule::Approval { max_choices: 2 };
+        let ballot = ballot(&write.key, &approve_both, [0x01; 32], &[1, 1]);
+        write.run(ballot.nullifier, options(&ballot, 0, 2)).unwrap();
+        write.run(ballot.nullifier, total_proof(&ballot, 0, 1)).unwrap();
+
+        assert_eq!(
+            write.run(ballot.nullifier, total_proof(&ballot, 1, 1)),
+            error(PrivacyError::InvalidVoteProof)
+        );
+    }
+}
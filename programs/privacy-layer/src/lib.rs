@@ -5,57 +5,346 @@ declare_id!("APdSGnQuogNbYga3CZUZfSRpAz4agdufXbEPbGCCt9by");
 pub mod state;
 pub mod instructions;
 pub mod errors;
+pub mod groth16;
+pub mod events;
+pub mod gov;
+pub mod migration;
 
 use instructions::*;
-use errors::*;
+use groth16::{G1_LEN, G2_LEN};
+use state::{BallotRule, EligibilityMode, VoteWeighting};
 
 #[program]
 pub mod privacy_layer {
     use super::*;
 
+    /// Set the mpl-gov-micro program ID
+    ///
+    /// One-time setup, restricted to this program's upgrade authority
+    pub fn initialize_gov_config(ctx: Context<InitializeGovConfig>, gov_program: Pubkey) -> Result<()> {
+        instructions::initialize_gov_config::handler(ctx, gov_program)
+    }
+
     /// Initialize a new private election
     ///
-    /// Creates a private election with ElGamal encryption for vote privacy
+    /// `election` must be an mpl-gov-micro election whose authority
+    /// co-signs as `election_authority`. `authority` becomes the election
+    /// admin and doesn't sign, so it may be a PDA or multisig vault.
+    ///
+    /// Creates a private election with ElGamal encryption for vote privacy.
+    /// The election starts `Pending` and accepts no votes until opened;
+    /// votes are accepted between `starts_at` and `ends_at`. With
+    /// `allow_revoting`, voters may replace their ballot until `ends_at`.
+    /// `vote_weighting` says whether voter tree leaves carry vote weights.
+    /// `audit_period` is how long after finalization the accounts must
+    /// stay on-chain before their rent can be reclaimed. `max_relayer_fee`
+    /// caps what a relayer may be paid per ballot from the fee vault.
+    /// The election id in nullifiers is derived from the private
+    /// election's address, not chosen by the caller.
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_private_election(
         ctx: Context<InitializePrivateElection>,
-        election_id: [u8; 32],
         mpc_public_key: [u8; 32],
         voter_merkle_root: [u8; 32],
+        starts_at: i64,
         ends_at: i64,
         num_options: u8,
+        ballot_rule: BallotRule,
+        eligibility_mode: EligibilityMode,
+        allow_revoting: bool,
+        vote_weighting: VoteWeighting,
+        audit_period: i64,
+        max_relayer_fee: u64,
     ) -> Result<()> {
         instructions::initialize_private_election::handler(
             ctx,
-            election_id,
             mpc_public_key,
             voter_merkle_root,
+            starts_at,
             ends_at,
             num_options,
+            ballot_rule,
+            eligibility_mode,
+            allow_revoting,
+            vote_weighting,
+            audit_period,
+            max_relayer_fee,
         )
     }
 
+    /// Set the Groth16 verifying key for the eligibility circuit
+    ///
+    /// Must be set while the election is still `Pending`
+    pub fn set_verifying_key(
+        ctx: Context<SetVerifyingKey>,
+        alpha_g1: [u8; G1_LEN],
+        beta_g2: [u8; G2_LEN],
+        gamma_g2: [u8; G2_LEN],
+        delta_g2: [u8; G2_LEN],
+        ic: Vec<[u8; G1_LEN]>,
+    ) -> Result<()> {
+        instructions::set_verifying_key::handler(ctx, alpha_g1, beta_g2, gamma_g2, delta_g2, ic)
+    }
+
+    /// Open a pending election for voting from `starts_at`
+    ///
+    /// `starts_at` may differ from the one given at creation but must still
+    /// be in the future and before `ends_at`
+    pub fn open_election(ctx: Context<OpenElection>, starts_at: i64) -> Result<()> {
+        instructions::open_election::handler(ctx, starts_at)
+    }
+
+    /// Close voting
+    ///
+    /// The authority may close at any time; anyone may close once `ends_at`
+    /// has passed
+    pub fn close_election(ctx: Context<CloseElection>) -> Result<()> {
+        instructions::close_election::handler(ctx)
+    }
+
+    /// Cancel a pending or active election
+    ///
+    /// Only the election's guardian may cancel
+    pub fn cancel_election(ctx: Context<CancelElection>) -> Result<()> {
+        instructions::cancel_election::handler(ctx)
+    }
+
+    /// Propose a new election admin
+    ///
+    /// First step of a two-step transfer; the current authority stays in
+    /// charge until `new_authority` accepts. `None` withdraws a proposal
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+        instructions::propose_authority::handler(ctx, new_authority)
+    }
+
+    /// Accept a proposed transfer and become the election admin
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::accept_authority::handler(ctx)
+    }
+
+    /// Reassign the tally submitter and guardian roles
+    ///
+    /// Admin only; both start out as the authority the election was
+    /// created with
+    pub fn set_roles(ctx: Context<SetRoles>, tally_submitter: Pubkey, guardian: Pubkey) -> Result<()> {
+        instructions::set_roles::handler(ctx, tally_submitter, guardian)
+    }
+
     /// Cast an encrypted vote
     ///
-    /// Allows an eligible voter to cast an encrypted vote with ZK proof
+    /// Allows an eligible voter to cast an encrypted ballot with ZK proof.
+    /// The ballot is taken from the voter's complete `BallotBuffer` for
+    /// `nullifier`, which is closed; in `EligibilityMode::Public` so is the
+    /// voter's Merkle inclusion proof. `zk_proof_a/b/c` is the Groth16
+    /// eligibility proof in
+    /// `EligibilityMode::Anonymous`, with `ballot_signal(commitment, 0,
+    /// voter, 0)` as its signal. `commitment` must equal
+    /// `commit_ballot(election_id, nullifier, ciphertexts)`. `weight` is the
+    /// voter's weight from the voter tree (1 in unweighted elections); each
+    /// option's ciphertext is multiplied by it before it joins the tally.
+    #[allow(clippy::too_many_arguments)]
     pub fn cast_encrypted_vote(
         ctx: Context<CastEncryptedVote>,
-        ciphertext_c1: [u8; 32],
-        ciphertext_c2: [u8; 32],
         nullifier: [u8; 32],
         commitment: [u8; 32],
-        zk_proof_a: Option<[u8; 32]>,
-        zk_proof_b: Option<[u8; 64]>,
-        zk_proof_c: Option<[u8; 32]>,
+        weight: u64,
+        zk_proof_a: Option<[u8; G1_LEN]>,
+        zk_proof_b: Option<[u8; G2_LEN]>,
+        zk_proof_c: Option<[u8; G1_LEN]>,
     ) -> Result<()> {
         instructions::cast_encrypted_vote::handler(
             ctx,
-            ciphertext_c1,
-            ciphertext_c2,
             nullifier,
             commitment,
+            weight,
+            zk_proof_a,
+            zk_proof_b,
+            zk_proof_c,
+        )
+    }
+
+    /// Cast an anonymous ballot submitted by a relayer
+    ///
+    /// The relayer signs and pays rent, so the voter's wallet never
+    /// appears; it writes the ballot to its own `BallotBuffer` first. The
+    /// ballot is authorized by the Groth16 proof alone, whose
+    /// signal is `ballot_signal(commitment, 0, relayer, relayer_fee)`.
+    /// `relayer_fee` lamports are paid to the relayer from the election's
+    /// fee vault, up to `max_relayer_fee` and only while the vault keeps
+    /// its rent-exempt minimum.
+    #[allow(clippy::too_many_arguments)]
+    pub fn relay_encrypted_vote(
+        ctx: Context<RelayEncryptedVote>,
+        nullifier: [u8; 32],
+        commitment: [u8; 32],
+        weight: u64,
+        zk_proof_a: [u8; G1_LEN],
+        zk_proof_b: [u8; G2_LEN],
+        zk_proof_c: [u8; G1_LEN],
+        relayer_fee: u64,
+    ) -> Result<()> {
+        instructions::relay_encrypted_vote::handler(
+            ctx,
+            nullifier,
+            commitment,
+            weight,
+            zk_proof_a,
+            zk_proof_b,
+            zk_proof_c,
+            relayer_fee,
+        )
+    }
+
+    /// Replace a previously cast ballot (latest vote wins)
+    ///
+    /// Only in elections created with `allow_revoting`, and only before
+    /// `ends_at`. Takes the same arguments and proofs as
+    /// `cast_encrypted_vote`, with the new ballot in a fresh `BallotBuffer`; in `EligibilityMode::Anonymous` the Groth16
+    /// proof's signal must use the ballot's next revision and the signer,
+    /// which may be any relayer. The old ballot
+    /// is subtracted from the running tally and the new one added.
+    #[allow(clippy::too_many_arguments)]
+    pub fn recast_encrypted_vote(
+        ctx: Context<RecastEncryptedVote>,
+        nullifier: [u8; 32],
+        commitment: [u8; 32],
+        weight: u64,
+        zk_proof_a: Option<[u8; G1_LEN]>,
+        zk_proof_b: Option<[u8; G2_LEN]>,
+        zk_proof_c: Option<[u8; G1_LEN]>,
+    ) -> Result<()> {
+        instructions::recast_encrypted_vote::handler(
+            ctx,
+            nullifier,
+            commitment,
+            weight,
             zk_proof_a,
             zk_proof_b,
             zk_proof_c,
         )
     }
+
+    /// Request the tally of an election
+    ///
+    /// Closes voting once `ends_at` has passed (if `close_election` wasn't
+    /// called already) so the aggregate can be decrypted
+    pub fn request_tally(ctx: Context<RequestTally>) -> Result<()> {
+        instructions::request_tally::handler(ctx)
+    }
+
+    /// Submit the decrypted tally and finalize the election
+    ///
+    /// Only the election's tally submitter may submit. Each per-option
+    /// count must come with a proof of correct decryption of the on-chain
+    /// aggregate under the election's public key. `counts` may cover only
+    /// the first options, with the rest added by `append_tally_result`;
+    /// the election is finalized once every option has its count
+    pub fn submit_tally_result(
+        ctx: Context<SubmitTallyResult>,
+        counts: Vec<u64>,
+        proofs: Vec<DecryptionProofArgs>,
+    ) -> Result<()> {
+        instructions::submit_tally_result::handler(ctx, counts, proofs)
+    }
+
+    /// Close a vote and its nullifier record, refunding their rent
+    ///
+    /// Permissionless once the election is finalized and its audit period
+    /// is over, or as soon as it is cancelled; the rent always goes to the
+    /// account that paid for the vote
+    pub fn close_vote_account(ctx: Context<CloseVoteAccount>) -> Result<()> {
+        instructions::close_vote_account::handler(ctx)
+    }
+
+    /// Close the election, tally and verifying key accounts
+    ///
+    /// After the audit period and once every vote account is closed. The
+    /// counts and aggregate are kept in a compact `ElectionResults` account;
+    /// the tally result's rent goes back to its submitter and the rest to
+    /// the authority, as does anything left in the relayer fee vault. A
+    /// cancelled election has no tally result or results to record and can
+    /// be closed without waiting.
+    pub fn close_election_accounts(ctx: Context<CloseElectionAccounts>) -> Result<()> {
+        instructions::close_election_accounts::handler(ctx)
+    }
+
+    /// Report the finalized tally to mpl-gov-micro
+    ///
+    /// Permissionless, once per election; CPIs `receive_private_tally`
+    /// signed by this program's `gov_authority` PDA
+    pub fn publish_results(ctx: Context<PublishResults>) -> Result<()> {
+        instructions::publish_results::handler(ctx)
+    }
+
+    /// Upgrade an account of this program to its current layout
+    ///
+    /// Permissionless; the account's data is rewritten field for field in
+    /// the new layout and reallocated to fit, with `payer` topping up its
+    /// rent if it grows. A no-op for accounts that are already current
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate_account::handler(ctx)
+    }
+
+    /// Move one nullifier of a v1 `NullifierSet` into its own
+    /// `NullifierRecord`
+    ///
+    /// The election must be migrated first. Its authority pays for the
+    /// record and is its `rent_payer`; once the last nullifier is moved
+    /// the set is closed and its rent returned to the authority.
+    pub fn migrate_nullifier_set(ctx: Context<MigrateNullifierSet>, nullifier: [u8; 32]) -> Result<()> {
+        instructions::migrate_nullifier_set::handler(ctx, nullifier)
+    }
+
+    /// Return what is left in the relayer fee vault to the authority
+    ///
+    /// Only once the election is finalized or cancelled, when no more
+    /// votes can be relayed
+    pub fn withdraw_relayer_fees(ctx: Context<WithdrawRelayerFees>) -> Result<()> {
+        instructions::withdraw_relayer_fees::handler(ctx)
+    }
+
+    /// Create an empty buffer for a ballot of `nullifier`, sized for the
+    /// election's options and ballot rule
+    ///
+    /// Created by whoever will cast, relay or recast the ballot, who pays
+    /// its rent until then. `tree_depth` is the depth of the voter tree
+    /// the voter's Merkle proof is for in `EligibilityMode::Public`, and
+    /// must be 0 in `EligibilityMode::Anonymous`.
+    pub fn create_ballot_buffer(ctx: Context<CreateBallotBuffer>, nullifier: [u8; 32], tree_depth: u8) -> Result<()> {
+        instructions::create_ballot_buffer::handler(ctx, nullifier, tree_depth)
+    }
+
+    /// Write the next part of a ballot to its buffer
+    ///
+    /// Ciphertexts go in option order, each with its proof that it
+    /// encrypts 0 or 1, followed by the proof that the ballot follows the
+    /// election's `BallotRule`, checked once its last entry is written.
+    /// A public voter's Merkle proof is written from the leaf level up,
+    /// and checked when the ballot is cast. A ballot too large for one
+    /// transaction is written over several.
+    pub fn write_ballot_buffer(
+        ctx: Context<WriteBallotBuffer>,
+        nullifier: [u8; 32],
+        chunk: BallotChunk,
+    ) -> Result<()> {
+        instructions::write_ballot_buffer::handler(ctx, nullifier, chunk)
+    }
+
+    /// Close a ballot buffer that won't be cast, refunding its rent
+    pub fn close_ballot_buffer(ctx: Context<CloseBallotBuffer>) -> Result<()> {
+        instructions::close_ballot_buffer::handler(ctx)
+    }
+
+    /// Add the counts of the next options to a tally result started by
+    /// `submit_tally_result`
+    ///
+    /// Same checks as `submit_tally_result`; finalizes the election once
+    /// every option has its count
+    pub fn append_tally_result(
+        ctx: Context<AppendTallyResult>,
+        counts: Vec<u64>,
+        proofs: Vec<DecryptionProofArgs>,
+    ) -> Result<()> {
+        instructions::append_tally_result::handler(ctx, counts, proofs)
+    }
 }
@@ -0,0 +1,375 @@
+//! Account layout versions and in-place upgrades
+//!
+//! The baseline program's accounts ("v1") start with their PDA bump right
+//! after the discriminator: `PrivateElection`, `EncryptedVote` with a
+//! single `c1`/`c2` ciphertext, and one `NullifierSet` per election. Every
+//! later layout starts with a `version` byte there instead; accounts added
+//! since the baseline were versioned from the start. `migrate` rewrites a
+//! v1 election in the current layout, and the `migrate_account`
+//! instruction then reallocs the account to fit. A nullifier set has no
+//! in-place successor: `migrate_nullifier_set` moves its nullifiers into
+//! `NullifierRecord`s one at a time and closes it once empty.
+//!
+//! A v1 vote is one ciphertext of the chosen option's index, with nothing
+//! proving what it encrypts. Tallies now sum one 0/1 ciphertext per
+//! option, and there is no splitting a v1 vote into those without
+//! decrypting it, so v1 votes are never migrated and never counted. For
+//! the same reason only elections that have stopped taking votes are
+//! migrated: an open one would have no `EncryptedTally` to cast into, and
+//! its v1 votes couldn't be folded into one.
+//!
+//! An account whose first byte isn't the current version is read as v1.
+//! A canonical bump only comes out as low as 2 after 253 failed
+//! derivations, so a v1 account is never mistaken for a current one.
+//! Fixtures of every v1 layout, written by the baseline program, live in
+//! `test-vectors/accounts/v1` at the repository root.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use crate::errors::PrivacyError;
+use crate::state::*;
+
+/// Rewrite an account of this program in its current layout
+///
+/// `data` is the whole account, discriminator included. Returns the new
+/// account data, padded to the account's new size, or `None` if the
+/// account is already current.
+pub fn migrate(data: &[u8]) -> Result<Option<Vec<u8>>> {
+    require!(data.len() > 8, PrivacyError::UnsupportedAccountVersion);
+    let (discriminator, body) = data.split_at(8);
+
+    if discriminator == PrivateElection::DISCRIMINATOR {
+        migrate_borsh::<v1::PrivateElection>(body, PrivateElection::VERSION, |_| PrivateElection::LEN)
+    } else if discriminator == EncryptedVote::DISCRIMINATOR {
+        migrate_borsh::<v1::EncryptedVote>(body, EncryptedVote::VERSION, |vote| {
+            EncryptedVote::space(vote.ciphertexts.len() as u8)
+        })
+    } else if let Some(version) = versioned_since_added(discriminator) {
+        require!(body[0] == version, PrivacyError::UnsupportedAccountVersion);
+        Ok(None)
+    } else {
+        // Including `v1::NullifierSet`, which goes through
+        // `migrate_nullifier_set` instead
+        err!(PrivacyError::UnsupportedAccountVersion)
+    }
+}
+
+/// Current version of an account type added after the baseline, which
+/// has no v1 layout to upgrade from
+fn versioned_since_added(discriminator: &[u8]) -> Option<u8> {
+    [
+        (EncryptedTally::DISCRIMINATOR, EncryptedTally::VERSION),
+        (NullifierRecord::DISCRIMINATOR, NullifierRecord::VERSION),
+        (EligibilityVerifyingKey::DISCRIMINATOR, EligibilityVerifyingKey::VERSION),
+        (TallyResult::DISCRIMINATOR, TallyResult::VERSION),
+        (ElectionResults::DISCRIMINATOR, ElectionResults::VERSION),
+        (GovConfig::DISCRIMINATOR, GovConfig::VERSION),
+        (BallotBuffer::DISCRIMINATOR, BallotBuffer::VERSION),
+    ]
+    .into_iter()
+    .find(|(known, _)| *known == discriminator)
+    .map(|(_, version)| version)
+}
+
+/// A superseded account layout
+trait Upgrade: AnchorDeserialize {
+    /// The current layout of the same account
+    type Current;
+
+    /// Carry every field over to the current layout
+    fn upgrade(self) -> Result<Self::Current>;
+}
+
+fn migrate_borsh<Old>(
+    body: &[u8],
+    version: u8,
+    space: fn(&Old::Current) -> usize,
+) -> Result<Option<Vec<u8>>>
+where
+    Old: Upgrade,
+    Old::Current: AccountSerialize,
+{
+    if body[0] == version {
+        return Ok(None);
+    }
+    let old = Old::deserialize(&mut &body[..]).map_err(|_| PrivacyError::UnsupportedAccountVersion)?;
+    let current = old.upgrade()?;
+
+    let space = space(&current);
+    let mut data = Vec::with_capacity(space);
+    current.try_serialize(&mut data)?;
+    data.resize(space, 0);
+    Ok(Some(data))
+}
+
+/// Layouts of the baseline program, from before accounts carried a
+/// version byte
+pub mod v1 {
+    use anchor_lang::prelude::*;
+    use crate::errors::PrivacyError;
+    use crate::state::ElectionStatus;
+
+    #[derive(AnchorDeserialize)]
+    pub struct PrivateElection {
+        pub bump: u8,
+        pub authority: Pubkey,
+        pub election: Pubkey,
+        pub mpc_public_key: [u8; 32],
+        pub voter_merkle_root: [u8; 32],
+        pub election_id: [u8; 32],
+        pub total_encrypted_votes: u64,
+        pub tally_requested: bool,
+        pub tally_finalized: bool,
+        pub created_at: i64,
+        pub ends_at: i64,
+        pub num_options: u8,
+        pub status: ElectionStatus,
+    }
+
+    #[derive(AnchorDeserialize)]
+    pub struct EncryptedVote {
+        pub bump: u8,
+        pub election: Pubkey,
+        pub ciphertext_c1: [u8; 32],
+        pub ciphertext_c2: [u8; 32],
+        pub nullifier: [u8; 32],
+        pub commitment: [u8; 32],
+        pub timestamp: i64,
+    }
+
+    /// Every nullifier spent in an election, in one growing account
+    ///
+    /// Seeded by `[b"nullifier_set", election]`, with `election` the
+    /// mpl-gov-micro election. Still an account type so that
+    /// `migrate_nullifier_set` can load and close it.
+    #[account]
+    pub struct NullifierSet {
+        pub bump: u8,
+        pub election: Pubkey,
+        pub nullifiers: Vec<[u8; 32]>,
+    }
+
+    impl NullifierSet {
+        /// Remove `nullifier` from the set, to be kept in its own record
+        pub fn take(&mut self, nullifier: &[u8; 32]) -> Result<()> {
+            let index = self
+                .nullifiers
+                .iter()
+                .position(|spent| spent == nullifier)
+                .ok_or(PrivacyError::InvalidNullifier)?;
+            self.nullifiers.swap_remove(index);
+            Ok(())
+        }
+    }
+}
+
+impl Upgrade for v1::PrivateElection {
+    type Current = PrivateElection;
+
+    fn upgrade(self) -> Result<PrivateElection> {
+        require!(
+            matches!(self.status, ElectionStatus::Ended | ElectionStatus::Finalized),
+            PrivacyError::InvalidElectionStatus
+        );
+        PrivateElection::validate_options(self.num_options, BallotRule::SingleChoice)?;
+
+        Ok(PrivateElection {
+            version: PrivateElection::VERSION,
+            bump: self.bump,
+            authority: self.authority,
+            pending_authority: None,
+            // The authority held every role before they were split
+            tally_submitter: self.authority,
+            guardian: self.authority,
+            election: self.election,
+            mpc_public_key: self.mpc_public_key,
+            voter_merkle_root: self.voter_merkle_root,
+            election_id: self.election_id,
+            total_encrypted_votes: self.total_encrypted_votes,
+            // No vote account has been closed yet
+            open_vote_accounts: self.total_encrypted_votes,
+            tally_requested: self.tally_requested,
+            tally_finalized: self.tally_finalized,
+            created_at: self.created_at,
+            // Voting opened at creation
+            starts_at: self.created_at,
+            ends_at: self.ends_at,
+            num_options: self.num_options,
+            // v1 took one unweighted ballot per voter on the voter tree,
+            // with no relayers and nothing to audit
+            ballot_rule: BallotRule::SingleChoice,
+            // The v1 root was built over voter commitments, not keys
+            eligibility_mode: EligibilityMode::Anonymous,
+            allow_revoting: false,
+            vote_weighting: VoteWeighting::Equal,
+            audit_period: 0,
+            max_relayer_fee: 0,
+            status: self.status,
+        })
+    }
+}
+
+impl Upgrade for v1::EncryptedVote {
+    type Current = EncryptedVote;
+
+    fn upgrade(self) -> Result<EncryptedVote> {
+        // Its one ciphertext could only stand in a tally of one option,
+        // and every election has at least `MIN_OPTIONS`
+        err!(PrivacyError::InvalidBallotSize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_bytes!(concat!("../../../test-vectors/accounts/v1/", $name, ".bin")).as_slice()
+        };
+    }
+
+    fn key(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    /// Migrate a v1 fixture, check a second pass is a no-op and decode it
+    fn upgrade<T: AccountDeserialize>(v1: &[u8]) -> (Vec<u8>, T) {
+        let data = migrate(v1).unwrap().expect("v1 fixture needs migrating");
+        assert_eq!(migrate(&data).unwrap(), None);
+        let account = T::try_deserialize(&mut &data[..]).unwrap();
+        (data, account)
+    }
+
+    /// The v1 election fixture, once voting is over
+    fn ended_election() -> Vec<u8> {
+        let mut data = fixture!("private_election").to_vec();
+        *data.last_mut().unwrap() = ElectionStatus::Ended as u8;
+        data
+    }
+
+    #[test]
+    fn test_migrate_private_election() {
+        let (data, election) = upgrade::<PrivateElection>(&ended_election());
+
+        assert_eq!(data.len(), PrivateElection::LEN);
+        assert_eq!(election.version, PrivateElection::VERSION);
+        assert_eq!(election.bump, 254);
+        assert_eq!(election.authority, key(1));
+        assert_eq!(election.pending_authority, None);
+        assert_eq!(election.tally_submitter, key(1));
+        assert_eq!(election.guardian, key(1));
+        assert_eq!(election.election, key(5));
+        assert_eq!(election.voter_merkle_root, [6; 32]);
+        assert_eq!(election.election_id, [7; 32]);
+        assert_eq!(election.total_encrypted_votes, 3);
+        assert_eq!(election.open_vote_accounts, 3);
+        assert!(!election.tally_requested && !election.tally_finalized);
+        assert_eq!(
+            (election.created_at, election.starts_at, election.ends_at),
+            (1_700_000_000, 1_700_000_000, 1_700_086_400)
+        );
+        assert_eq!(election.num_options, 3);
+        assert!(election.ballot_rule == BallotRule::SingleChoice);
+        assert!(election.eligibility_mode == EligibilityMode::Anonymous);
+        assert!(!election.allow_revoting);
+        assert!(election.vote_weighting == VoteWeighting::Equal);
+        assert_eq!((election.audit_period, election.max_relayer_fee), (0, 0));
+        assert_eq!(election.status, ElectionStatus::Ended);
+    }
+
+    #[test]
+    fn test_open_election_not_migrated() {
+        // The fixture is still taking votes, with no tally to count them in
+        let data = fixture!("private_election");
+        assert_eq!(migrate(data), Err(PrivacyError::InvalidElectionStatus.into()));
+
+        let mut data = data.to_vec();
+        *data.last_mut().unwrap() = ElectionStatus::Pending as u8;
+        assert_eq!(migrate(&data), Err(PrivacyError::InvalidElectionStatus.into()));
+
+        let mut data = ended_election();
+        *data.last_mut().unwrap() = ElectionStatus::Finalized as u8;
+        assert!(migrate(&data).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_migrated_election_options_checked() {
+        let mut data = ended_election();
+        let num_options = data.len() - 2;
+        for invalid in [1, MAX_OPTIONS + 1] {
+            data[num_options] = invalid;
+            assert_eq!(migrate(&data), Err(PrivacyError::InvalidNumOptions.into()));
+        }
+    }
+
+    #[test]
+    fn test_encrypted_vote_not_migrated() {
+        // One ciphertext of the chosen option's index, which no tally of
+        // one 0/1 ciphertext per option can take
+        let data = fixture!("encrypted_vote");
+        assert_eq!(migrate(data), Err(PrivacyError::InvalidBallotSize.into()));
+
+        let vote = v1::EncryptedVote::deserialize(&mut &data[8..]).unwrap();
+        assert_eq!(vote.bump, 252);
+        assert_eq!(vote.election, key(9));
+        assert_eq!((vote.ciphertext_c1, vote.ciphertext_c2), ([20; 32], [21; 32]));
+        assert_eq!(vote.nullifier, [10; 32]);
+    }
+
+    #[test]
+    fn test_nullifier_set_moves_to_records() {
+        let data = fixture!("nullifier_set");
+        // Has no in-place successor
+        assert_eq!(migrate(data), Err(PrivacyError::UnsupportedAccountVersion.into()));
+
+        let mut set = v1::NullifierSet::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(set.bump, 253);
+        assert_eq!(set.election, key(5));
+        assert_eq!(set.nullifiers, vec![[10; 32], [11; 32], [12; 32]]);
+
+        set.take(&[10; 32]).unwrap();
+        assert_eq!(set.nullifiers.len(), 2);
+        // Each nullifier moves once
+        assert_eq!(set.take(&[10; 32]), Err(PrivacyError::InvalidNullifier.into()));
+        assert_eq!(set.take(&[13; 32]), Err(PrivacyError::InvalidNullifier.into()));
+
+        set.take(&[12; 32]).unwrap();
+        set.take(&[11; 32]).unwrap();
+        assert!(set.nullifiers.is_empty());
+    }
+
+    #[test]
+    fn test_accounts_added_later_are_current() {
+        let mut data = EncryptedTally::account_data(255, key(9), 2);
+        assert_eq!(migrate(&data).unwrap(), None);
+
+        // There is no earlier tally layout to upgrade from
+        data[8] = 1;
+        assert_eq!(migrate(&data), Err(PrivacyError::UnsupportedAccountVersion.into()));
+
+        let mut data = Vec::new();
+        GovConfig {
+            version: GovConfig::VERSION,
+            bump: 247,
+            gov_program: key(17),
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        assert_eq!(migrate(&data).unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejects_unknown_accounts() {
+        // Unknown discriminator
+        let mut data = fixture!("private_election").to_vec();
+        data[0] ^= 1;
+        assert_eq!(migrate(&data), Err(PrivacyError::UnsupportedAccountVersion.into()));
+
+        // Too short to hold a v1 layout
+        let data = &fixture!("private_election")[..40];
+        assert_eq!(migrate(data), Err(PrivacyError::UnsupportedAccountVersion.into()));
+
+        assert_eq!(migrate(&[0; 8]), Err(PrivacyError::UnsupportedAccountVersion.into()));
+    }
+}
@@ -0,0 +1,425 @@
+use anchor_lang::prelude::*;
+use privacy_crypto::merkle::{MerkleProof, MAX_MERKLE_DEPTH};
+use crate::errors::PrivacyError;
+use crate::state::{BallotCiphertext, BallotRule};
+
+/// Ballot buffer account
+///
+/// A ballot on its way in, written with `write_ballot_buffer` over as many
+/// transactions as it takes: every option's 0/1 proof is checked as its
+/// ciphertext is written, and the total proof once its last entry is in.
+/// In a public election the voter's Merkle proof is written here too, and
+/// checked when the ballot is cast. `cast_encrypted_vote`,
+/// `relay_encrypted_vote` and `recast_encrypted_vote` take the complete
+/// ballot from here and close the buffer. Seeded by
+/// `[b"ballot_buffer", private_election, submitter, nullifier]`, so only
+/// the submitter can write to or spend it.
+///
+/// Zero-copy like `EncryptedTally`: this header is followed by
+/// `num_options` ciphertexts, then the `num_totals` challenges and the
+/// `num_totals` responses of the total proof, then the `num_siblings`
+/// hashes of the Merkle proof. Read and write them through
+/// `BallotBufferRef` and `BallotBufferMut`.
+#[account(zero_copy)]
+pub struct BallotBuffer {
+    /// Layout version (`BallotBuffer::VERSION`)
+    pub version: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Number of ciphertexts after the header
+    pub num_options: u8,
+
+    /// Ciphertexts written so far, in option order
+    pub options_written: u8,
+
+    /// Number of total proof challenges, and of responses, after the
+    /// ciphertexts
+    pub num_totals: u8,
+
+    /// Total proof entries written so far, in order
+    pub totals_written: u8,
+
+    /// Number of Merkle proof siblings after the total proof: the voter
+    /// tree's depth in a public election, 0 in an anonymous one
+    pub num_siblings: u8,
+
+    /// Merkle proof siblings written so far, from the leaf level up
+    pub siblings_written: u8,
+
+    /// Position of the voter's leaf in the voter tree
+    pub leaf_index: u32,
+
+    /// The private election the ballot is for
+    pub private_election: Pubkey,
+
+    /// Nullifier the ballot's proofs are bound to
+    pub nullifier: [u8; 32],
+}
+
+impl BallotBuffer {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    /// Size without the ballot
+    pub const HEADER_LEN: usize = 8 + // discriminator
+        std::mem::size_of::<BallotBuffer>();
+
+    /// Account size for a ballot of `num_options` options under
+    /// `ballot_rule`, with a Merkle proof `num_siblings` deep
+    pub fn space(num_options: u8, ballot_rule: BallotRule, num_siblings: u8) -> usize {
+        Self::HEADER_LEN
+            + num_options as usize * BallotCiphertext::LEN
+            + ballot_rule.num_totals() * 2 * 32
+            + num_siblings as usize * 32
+    }
+
+    /// Account data of an empty buffer, as `create_ballot_buffer` creates it
+    pub fn account_data(
+        bump: u8,
+        private_election: Pubkey,
+        nullifier: [u8; 32],
+        num_options: u8,
+        ballot_rule: BallotRule,
+        num_siblings: u8,
+    ) -> Vec<u8> {
+        let mut data = vec![0; Self::space(num_options, ballot_rule, num_siblings)];
+        data[..8].copy_from_slice(Self::DISCRIMINATOR);
+        BallotBufferMut::init(&mut data, bump, private_election, nullifier, num_options, ballot_rule, num_siblings)
+            .expect("sized for the ballot");
+        data
+    }
+
+    /// The complete ballot in buffer account `buffer`, and the voter's
+    /// Merkle proof
+    pub fn ballot(buffer: &AccountInfo) -> Result<(Vec<BallotCiphertext>, MerkleProof)> {
+        let data = buffer.try_borrow_data()?;
+        let buffer = BallotBufferRef::from_account_data(&data)?;
+        Ok((buffer.ballot()?.to_vec(), buffer.merkle_proof()))
+    }
+}
+
+/// Byte lengths of the ciphertexts, of each half of the total proof and
+/// of the Merkle proof in buffer account data, once its discriminator and
+/// size are checked
+fn section_lens(data: &[u8]) -> Result<(usize, usize, usize)> {
+    require!(
+        data.len() >= BallotBuffer::HEADER_LEN && &data[..8] == BallotBuffer::DISCRIMINATOR,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    let header: &BallotBuffer = bytemuck::from_bytes(&data[8..BallotBuffer::HEADER_LEN]);
+    let ciphertexts_len = header.num_options as usize * BallotCiphertext::LEN;
+    let totals_len = header.num_totals as usize * 32;
+    let siblings_len = header.num_siblings as usize * 32;
+    require!(
+        data.len() >= BallotBuffer::HEADER_LEN + ciphertexts_len + 2 * totals_len + siblings_len,
+        ErrorCode::AccountDidNotDeserialize
+    );
+    Ok((ciphertexts_len, totals_len, siblings_len))
+}
+
+/// A ballot buffer's header and contents, borrowed for reading
+#[derive(Clone, Copy)]
+pub struct BallotBufferRef<'a> {
+    pub header: &'a BallotBuffer,
+    pub ciphertexts: &'a [BallotCiphertext],
+    pub challenges: &'a [[u8; 32]],
+    pub responses: &'a [[u8; 32]],
+    pub siblings: &'a [[u8; 32]],
+}
+
+/// A ballot buffer's header and contents, borrowed for writing
+pub struct BallotBufferMut<'a> {
+    pub header: &'a mut BallotBuffer,
+    pub ciphertexts: &'a mut [BallotCiphertext],
+    pub challenges: &'a mut [[u8; 32]],
+    pub responses: &'a mut [[u8; 32]],
+    pub siblings: &'a mut [[u8; 32]],
+}
+
+impl<'a> BallotBufferRef<'a> {
+    /// Borrow a buffer from its account data, discriminator included
+    pub fn from_account_data(data: &'a [u8]) -> Result<Self> {
+        let (ciphertexts_len, totals_len, siblings_len) = section_lens(data)?;
+        let (header, rest) = data[8..].split_at(std::mem::size_of::<BallotBuffer>());
+        let (ciphertexts, rest) = rest.split_at(ciphertexts_len);
+        let (challenges, rest) = rest.split_at(totals_len);
+        let (responses, rest) = rest.split_at(totals_len);
+        Ok(Self {
+            header: bytemuck::from_bytes(header),
+            ciphertexts: bytemuck::cast_slice(ciphertexts),
+            challenges: bytemuck::cast_slice(challenges),
+            responses: bytemuck::cast_slice(responses),
+            siblings: bytemuck::cast_slice(&rest[..siblings_len]),
+        })
+    }
+
+    /// Whether the ballot's options and total proof have been written, and
+    /// so checked
+    pub fn is_proven(&self) -> bool {
+        self.header.options_written == self.header.num_options
+            && self.header.totals_written == self.header.num_totals
+    }
+
+    /// Whether every part of the ballot has been written
+    pub fn is_complete(&self) -> bool {
+        self.is_proven() && self.header.siblings_written == self.header.num_siblings
+    }
+
+    /// The ballot's ciphertexts, once it is complete
+    pub fn ballot(&self) -> Result<&'a [BallotCiphertext]> {
+        require!(self.is_complete(), PrivacyError::BallotIncomplete);
+        Ok(self.ciphertexts)
+    }
+
+    /// The voter's Merkle proof as written so far
+    pub fn merkle_proof(&self) -> MerkleProof {
+        MerkleProof {
+            leaf_index: self.header.leaf_index,
+            siblings: self.siblings.to_vec(),
+        }
+    }
+}
+
+impl<'a> BallotBufferMut<'a> {
+    /// Borrow a buffer from its account data, discriminator included
+    pub fn from_account_data(data: &'a mut [u8]) -> Result<Self> {
+        let (ciphertexts_len, totals_len, siblings_len) = section_lens(data)?;
+        let (header, rest) = data[8..].split_at_mut(std::mem::size_of::<BallotBuffer>());
+        let (ciphertexts, rest) = rest.split_at_mut(ciphertexts_len);
+        let (challenges, rest) = rest.split_at_mut(totals_len);
+        let (responses, rest) = rest.split_at_mut(totals_len);
+        Ok(Self {
+            header: bytemuck::from_bytes_mut(header),
+            ciphertexts: bytemuck::cast_slice_mut(ciphertexts),
+            challenges: bytemuck::cast_slice_mut(challenges),
+            responses: bytemuck::cast_slice_mut(responses),
+            siblings: bytemuck::cast_slice_mut(&mut rest[..siblings_len]),
+        })
+    }
+
+    /// Set up an empty buffer over account data created with
+    /// `BallotBuffer::space(num_options, ballot_rule, num_siblings)`
+    ///
+    /// Only the header is written; Anchor's `load_init` leaves the
+    /// discriminator to be set when the instruction exits.
+    pub fn init(
+        data: &'a mut [u8],
+        bump: u8,
+        private_election: Pubkey,
+        nullifier: [u8; 32],
+        num_options: u8,
+        ballot_rule: BallotRule,
+        num_siblings: u8,
+    ) -> Result<()> {
+        require!(
+            data.len() >= BallotBuffer::space(num_options, ballot_rule, num_siblings),
+            ErrorCode::AccountDidNotDeserialize
+        );
+        require!(
+            num_siblings as usize <= MAX_MERKLE_DEPTH,
+            PrivacyError::InvalidMerkleProof
+        );
+        let header: &mut BallotBuffer = bytemuck::from_bytes_mut(&mut data[8..BallotBuffer::HEADER_LEN]);
+        header.version = BallotBuffer::VERSION;
+        header.bump = bump;
+        header.num_options = num_options;
+        header.options_written = 0;
+        header.num_totals = u8::try_from(ballot_rule.num_totals()).map_err(|_| PrivacyError::InvalidBallotRule)?;
+        header.totals_written = 0;
+        header.num_siblings = num_siblings;
+        header.siblings_written = 0;
+        header.leaf_index = 0;
+        header.private_election = private_election;
+        header.nullifier = nullifier;
+        Ok(())
+    }
+
+    pub fn as_ref(&self) -> BallotBufferRef<'_> {
+        BallotBufferRef {
+            header: self.header,
+            ciphertexts: self.ciphertexts,
+            challenges: self.challenges,
+            responses: self.responses,
+            siblings: self.siblings,
+        }
+    }
+
+    /// Store the ciphertexts of options `first..`, which must pick up
+    /// where the last write left off
+    pub fn write_options(&mut self, first: u8, ciphertexts: &[BallotCiphertext]) -> Result<()> {
+        require!(
+            first == self.header.options_written,
+            PrivacyError::BallotWrittenOutOfOrder
+        );
+        let start = first as usize;
+        let end = start + ciphertexts.len();
+        require!(end <= self.ciphertexts.len(), PrivacyError::InvalidBallotSize);
+
+        self.ciphertexts[start..end].copy_from_slice(ciphertexts);
+        self.header.options_written = end as u8;
+        Ok(())
+    }
+
+    /// Store total proof entries `first..`, once every option is written
+    /// and picking up where the last write left off
+    pub fn write_total_proof(&mut self, first: u8, challenges: &[[u8; 32]], responses: &[[u8; 32]]) -> Result<()> {
+        require!(
+            self.header.options_written == self.header.num_options && first == self.header.totals_written,
+            PrivacyError::BallotWrittenOutOfOrder
+        );
+        let start = first as usize;
+        let end = start + challenges.len();
+        require!(
+            responses.len() == challenges.len() && end <= self.challenges.len(),
+            PrivacyError::InvalidBallotSize
+        );
+
+        self.challenges[start..end].copy_from_slice(challenges);
+        self.responses[start..end].copy_from_slice(responses);
+        self.header.totals_written = end as u8;
+        Ok(())
+    }
+
+    /// Store Merkle proof siblings `first..`, picking up where the last
+    /// write left off, and the leaf index they are for
+    pub fn write_merkle_proof(&mut self, leaf_index: u32, first: u8, siblings: &[[u8; 32]]) -> Result<()> {
+        require!(
+            first == self.header.siblings_written,
+            PrivacyError::BallotWrittenOutOfOrder
+        );
+        let start = first as usize;
+        let end = start + siblings.len();
+        require!(end <= self.siblings.len(), PrivacyError::InvalidMerkleProof);
+
+        self.siblings[start..end].copy_from_slice(siblings);
+        self.header.siblings_written = end as u8;
+        self.header.leaf_index = leaf_index;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ciphertexts(count: usize, fill: u8) -> Vec<BallotCiphertext> {
+        vec![BallotCiphertext { c1: [fill; 32], c2: [fill; 32] }; count]
+    }
+
+    #[test]
+    fn test_written_in_order() {
+        let rule = BallotRule::Approval { max_choices: 2 };
+        let mut data = BallotBuffer::account_data(254, Pubkey::new_unique(), [1; 32], 3, rule, 0);
+        let mut buffer = BallotBufferMut::from_account_data(&mut data).unwrap();
+        assert_eq!((buffer.ciphertexts.len(), buffer.challenges.len()), (3, 3));
+
+        // The total proof comes after every option
+        assert_eq!(
+            buffer.write_total_proof(0, &[[4; 32]], &[[5; 32]]),
+            Err(PrivacyError::BallotWrittenOutOfOrder.into())
+        );
+        buffer.write_options(0, &ciphertexts(2, 2)).unwrap();
+        // No gaps or overwrites
+        for first in [0, 1, 3] {
+            assert_eq!(
+                buffer.write_options(first, &ciphertexts(1, 3)),
+                Err(PrivacyError::BallotWrittenOutOfOrder.into())
+            );
+        }
+        assert_eq!(
+            buffer.write_options(2, &ciphertexts(2, 3)),
+            Err(PrivacyError::InvalidBallotSize.into())
+        );
+        buffer.write_options(2, &ciphertexts(1, 3)).unwrap();
+
+        assert!(!buffer.as_ref().is_complete());
+        assert_eq!(
+            buffer.as_ref().ballot().err(),
+            Some(PrivacyError::BallotIncomplete.into())
+        );
+        buffer.write_total_proof(0, &[[4; 32]; 2], &[[5; 32]; 2]).unwrap();
+        assert_eq!(
+            buffer.write_total_proof(2, &[[4; 32]], &[]),
+            Err(PrivacyError::InvalidBallotSize.into())
+        );
+        buffer.write_total_proof(2, &[[6; 32]], &[[7; 32]]).unwrap();
+
+        let buffer = BallotBufferRef::from_account_data(&data).unwrap();
+        assert!(buffer.is_complete());
+        let mut expected = ciphertexts(2, 2);
+        expected.extend(ciphertexts(1, 3));
+        assert!(buffer.ballot().unwrap() == &expected[..]);
+        assert_eq!(buffer.challenges, &[[4; 32], [4; 32], [6; 32]]);
+        assert_eq!(buffer.responses, &[[5; 32], [5; 32], [7; 32]]);
+    }
+
+    #[test]
+    fn test_merkle_proof_written_in_order() {
+        let rule = BallotRule::SingleChoice;
+        let mut data = BallotBuffer::account_data(254, Pubkey::new_unique(), [1; 32], 2, rule, 3);
+        let mut buffer = BallotBufferMut::from_account_data(&mut data).unwrap();
+        buffer.write_options(0, &ciphertexts(2, 2)).unwrap();
+        buffer.write_total_proof(0, &[[4; 32]], &[[5; 32]]).unwrap();
+
+        // The ballot is proven, but can't be cast without the Merkle proof
+        assert!(buffer.as_ref().is_proven());
+        assert!(!buffer.as_ref().is_complete());
+        assert_eq!(
+            buffer.write_merkle_proof(7, 1, &[[8; 32]]),
+            Err(PrivacyError::BallotWrittenOutOfOrder.into())
+        );
+        buffer.write_merkle_proof(7, 0, &[[8; 32]; 2]).unwrap();
+        assert_eq!(
+            buffer.write_merkle_proof(7, 2, &[[9; 32]; 2]),
+            Err(PrivacyError::InvalidMerkleProof.into())
+        );
+        buffer.write_merkle_proof(7, 2, &[[9; 32]]).unwrap();
+
+        let buffer = BallotBufferRef::from_account_data(&data).unwrap();
+        assert!(buffer.is_complete());
+        assert_eq!(
+            buffer.merkle_proof(),
+            MerkleProof {
+                leaf_index: 7,
+                siblings: vec![[8; 32], [8; 32], [9; 32]],
+            }
+        );
+    }
+
+    #[test]
+    fn test_account_sized_from_ballot() {
+        let (private_election, nullifier) = (Pubkey::new_unique(), [1; 32]);
+        for (num_options, rule, num_siblings) in [
+            (2, BallotRule::SingleChoice, 0),
+            (7, BallotRule::Approval { max_choices: 7 }, MAX_MERKLE_DEPTH as u8),
+        ] {
+            let data = BallotBuffer::account_data(254, private_election, nullifier, num_options, rule, num_siblings);
+            assert_eq!(data.len(), BallotBuffer::space(num_options, rule, num_siblings));
+
+            let buffer = BallotBufferRef::from_account_data(&data).unwrap();
+            assert_eq!(buffer.header.private_election, private_election);
+            assert_eq!(buffer.header.nullifier, nullifier);
+            assert_eq!(buffer.ciphertexts.len(), num_options as usize);
+            assert_eq!(buffer.challenges.len(), rule.num_totals());
+            assert_eq!(buffer.responses.len(), rule.num_totals());
+            assert_eq!(buffer.siblings.len(), num_siblings as usize);
+        }
+
+        // Deeper than any voter tree
+        let depth = MAX_MERKLE_DEPTH as u8 + 1;
+        let mut data = vec![0; BallotBuffer::space(2, BallotRule::SingleChoice, depth)];
+        assert_eq!(
+            BallotBufferMut::init(&mut data, 254, private_election, nullifier, 2, BallotRule::SingleChoice, depth),
+            Err(PrivacyError::InvalidMerkleProof.into())
+        );
+
+        // Cut short
+        let data = BallotBuffer::account_data(254, private_election, nullifier, 2, BallotRule::SingleChoice, 1);
+        assert_eq!(
+            BallotBufferRef::from_account_data(&data[..data.len() - 1]).err(),
+            Some(ErrorCode::AccountDidNotDeserialize.into())
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::state::BallotCiphertext;
+
+/// Election results account
+///
+/// Compact record of a closed election, created by
+/// `close_election_accounts` once the audit period is over. Outlives the
+/// election, tally and vote accounts so the outcome stays verifiable: the
+/// counts are the decryption of `aggregate` under `mpc_public_key`.
+#[account]
+pub struct ElectionResults {
+    /// Layout version (`ElectionResults::VERSION`)
+    pub version: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Reference to the public election in mpl-gov-micro
+    pub election: Pubkey,
+
+    /// ElGamal public key the ballots were encrypted under (32 bytes)
+    pub mpc_public_key: [u8; 32],
+
+    /// Decrypted vote count per option
+    pub counts: Vec<u64>,
+
+    /// Homomorphic sum of all ballots, one ciphertext per option
+    pub aggregate: Vec<BallotCiphertext>,
+
+    /// Number of ballots in the aggregate
+    pub ballots: u64,
+
+    /// Sum of the weights of those ballots
+    pub total_weight: u64,
+
+    /// Timestamp when the tally was finalized
+    pub finalized_at: i64,
+}
+
+impl ElectionResults {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    /// Size without the per-option entries
+    pub const BASE_LEN: usize = 8 + // discriminator
+        1 + // version
+        1 + // bump
+        32 + // election
+        32 + // mpc_public_key
+        4 + // counts vec length prefix
+        4 + // aggregate vec length prefix
+        8 + // ballots
+        8 + // total_weight
+        8; // finalized_at
+
+    /// Account size for an election with `num_options` options
+    pub fn space(num_options: u8) -> usize {
+        Self::BASE_LEN + num_options as usize * (8 + BallotCiphertext::LEN)
+    }
+}
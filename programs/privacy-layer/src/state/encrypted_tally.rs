@@ -0,0 +1,470 @@
+use anchor_lang::prelude::*;
+use privacy_crypto::{DleqProof, ElGamalCiphertext, ElGamalPublicKey};
+use crate::errors::PrivacyError;
+use crate::state::{BallotCiphertext, BallotRule};
+
+/// Encrypted tally account
+///
+/// Running homomorphic sum of every ballot cast in an election, one
+/// ciphertext per option. Updated on each vote so the final tally
+/// ciphertext is always available on-chain. Zero-copy, since every cast
+/// rewrites it: this header is followed by `num_options` aggregate
+/// ciphertexts, sized when the account is created. Read and update the
+/// two together through `EncryptedTallyRef` and `EncryptedTallyMut`.
+#[account(zero_copy)]
+pub struct EncryptedTally {
+    /// Layout version (`EncryptedTally::VERSION`)
+    pub version: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Number of aggregate ciphertexts after the header
+    pub num_options: u8,
+
+    /// Keeps the following fields 8-byte aligned
+    pub padding: [u8; 5],
+
+    /// The private election this tally belongs to
+    pub election: Pubkey,
+
+    /// Number of ballots folded into the aggregate
+    pub ballots: u64,
+
+    /// Sum of the weights of those ballots
+    pub total_weight: u64,
+}
+
+impl EncryptedTally {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    /// Size without the aggregate
+    pub const HEADER_LEN: usize = 8 + // discriminator
+        std::mem::size_of::<EncryptedTally>();
+
+    /// Account size for an election with `num_options` options
+    pub fn space(num_options: u8) -> usize {
+        Self::HEADER_LEN + num_options as usize * BallotCiphertext::LEN
+    }
+}
+
+impl EncryptedTally {
+    /// Account data of an empty tally, as `initialize_private_election`
+    /// creates it
+    pub fn account_data(bump: u8, election: Pubkey, num_options: u8) -> Vec<u8> {
+        let mut data = vec![0; Self::space(num_options)];
+        data[..8].copy_from_slice(Self::DISCRIMINATOR);
+        EncryptedTallyMut::init(&mut data, bump, election, num_options).expect("sized for the options");
+        data
+    }
+}
+
+/// Split tally account data into its header and the aggregate it sizes
+fn split_account_data(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    require!(
+        data.len() >= EncryptedTally::HEADER_LEN && &data[..8] == EncryptedTally::DISCRIMINATOR,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    let (header, aggregate) = data[8..].split_at(std::mem::size_of::<EncryptedTally>());
+    // `num_options` is the header's third byte
+    let aggregate_len = header[2] as usize * BallotCiphertext::LEN;
+    let aggregate = aggregate
+        .get(..aggregate_len)
+        .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+    Ok((header, aggregate))
+}
+
+/// An encrypted tally's header and aggregate, borrowed for reading
+#[derive(Clone, Copy)]
+pub struct EncryptedTallyRef<'a> {
+    pub header: &'a EncryptedTally,
+    pub aggregate: &'a [BallotCiphertext],
+}
+
+/// An encrypted tally's header and aggregate, borrowed for updating
+pub struct EncryptedTallyMut<'a> {
+    pub header: &'a mut EncryptedTally,
+    pub aggregate: &'a mut [BallotCiphertext],
+}
+
+impl<'a> EncryptedTallyRef<'a> {
+    /// Borrow a tally from its account data, discriminator included
+    pub fn from_account_data(data: &'a [u8]) -> Result<Self> {
+        let (header, aggregate) = split_account_data(data)?;
+        Ok(Self {
+            header: bytemuck::from_bytes(header),
+            aggregate: bytemuck::cast_slice(aggregate),
+        })
+    }
+
+    /// Check claimed counts of options `first..` against this tally's
+    /// aggregate
+    ///
+    /// Each count must come with a proof that it is the decryption of
+    /// the stored aggregate under `public_key`, so only the key holder(s)
+    /// can finalize and nobody can substitute another aggregate.
+    pub fn verify_decryption(
+        &self,
+        public_key: &[u8; 32],
+        first: usize,
+        counts: &[u64],
+        proofs: &[DleqProof],
+    ) -> Result<()> {
+        let aggregate = self
+            .aggregate
+            .get(first..first + counts.len())
+            .ok_or(PrivacyError::InvalidBallotSize)?;
+        require!(proofs.len() == counts.len(), PrivacyError::InvalidBallotSize);
+
+        let public_key = ElGamalPublicKey::from_bytes(*public_key)
+            .map_err(|_| PrivacyError::InvalidPublicKey)?;
+        for ((&count, &ciphertext), proof) in counts.iter().zip(aggregate).zip(proofs) {
+            public_key
+                .verify_decryption(&ElGamalCiphertext::from(ciphertext), count, proof)
+                .map_err(|_| PrivacyError::InvalidMpcSignature)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check every option's verified count together
+    ///
+    /// A single-choice ballot adds exactly its weight to one option, so
+    /// those counts must add up to `total_weight`.
+    pub fn verify_total(&self, ballot_rule: BallotRule, counts: &[u64]) -> Result<()> {
+        require!(
+            counts.len() == self.aggregate.len(),
+            PrivacyError::InvalidBallotSize
+        );
+
+        if ballot_rule == BallotRule::SingleChoice {
+            let total = counts
+                .iter()
+                .try_fold(0u64, |acc, &count| acc.checked_add(count))
+                .ok_or(PrivacyError::ArithmeticOverflow)?;
+            require!(total == self.header.total_weight, PrivacyError::TallyMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> EncryptedTallyMut<'a> {
+    /// Borrow a tally from its account data, discriminator included
+    pub fn from_account_data(data: &'a mut [u8]) -> Result<Self> {
+        let (header_len, aggregate_len) = {
+            let (header, aggregate) = split_account_data(data)?;
+            (header.len(), aggregate.len())
+        };
+        let (header, aggregate) = data[8..].split_at_mut(header_len);
+        Ok(Self {
+            header: bytemuck::from_bytes_mut(header),
+            aggregate: bytemuck::cast_slice_mut(&mut aggregate[..aggregate_len]),
+        })
+    }
+
+    /// Set up an empty tally over account data created with
+    /// `EncryptedTally::space(num_options)`: an encryption of zero for
+    /// every option
+    ///
+    /// Only the header and aggregate are written; Anchor's `load_init`
+    /// leaves the discriminator to be set when the instruction exits.
+    pub fn init(data: &'a mut [u8], bump: u8, election: Pubkey, num_options: u8) -> Result<Self> {
+        require!(
+            data.len() >= EncryptedTally::space(num_options),
+            ErrorCode::AccountDidNotDeserialize
+        );
+        let (header, aggregate) = data[8..].split_at_mut(std::mem::size_of::<EncryptedTally>());
+        let header: &mut EncryptedTally = bytemuck::from_bytes_mut(header);
+        header.version = EncryptedTally::VERSION;
+        header.bump = bump;
+        header.num_options = num_options;
+        header.election = election;
+        header.ballots = 0;
+        header.total_weight = 0;
+
+        let aggregate: &mut [BallotCiphertext] =
+            bytemuck::cast_slice_mut(&mut aggregate[..num_options as usize * BallotCiphertext::LEN]);
+        aggregate.fill(ElGamalCiphertext::zero().into());
+        Ok(Self { header, aggregate })
+    }
+
+    pub fn as_ref(&self) -> EncryptedTallyRef<'_> {
+        EncryptedTallyRef {
+            header: self.header,
+            aggregate: self.aggregate,
+        }
+    }
+
+    /// Add a ballot to the aggregate option by option, scaled by `weight`
+    pub fn accumulate(&mut self, ballot: &[BallotCiphertext], weight: u64) -> Result<()> {
+        require!(
+            ballot.len() == self.aggregate.len(),
+            PrivacyError::InvalidBallotSize
+        );
+
+        for (sum, &ciphertext) in self.aggregate.iter_mut().zip(ballot) {
+            let updated = ElGamalCiphertext::from(*sum)
+                .add(&weighted(ciphertext, weight)?)
+                .map_err(|_| PrivacyError::InvalidCiphertext)?;
+            *sum = updated.into();
+        }
+
+        self.header.ballots = self
+            .header
+            .ballots
+            .checked_add(1)
+            .ok_or(PrivacyError::ArithmeticOverflow)?;
+        self.header.total_weight = self
+            .header
+            .total_weight
+            .checked_add(weight)
+            .ok_or(PrivacyError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    /// Swap a previously accumulated ballot for its replacement
+    ///
+    /// Subtracts `old` scaled by `old_weight` and adds `new` scaled by
+    /// `new_weight`, option by option; the ballot count is unchanged since
+    /// the voter still has exactly one ballot.
+    pub fn replace(
+        &mut self,
+        old: &[BallotCiphertext],
+        old_weight: u64,
+        new: &[BallotCiphertext],
+        new_weight: u64,
+    ) -> Result<()> {
+        require!(
+            old.len() == self.aggregate.len() && new.len() == self.aggregate.len(),
+            PrivacyError::InvalidBallotSize
+        );
+
+        for ((sum, &old), &new) in self.aggregate.iter_mut().zip(old).zip(new) {
+            let old = weighted(old, old_weight)?;
+            let new = weighted(new, new_weight)?;
+            let updated = ElGamalCiphertext::from(*sum)
+                .sub(&old)
+                .and_then(|sum| sum.add(&new))
+                .map_err(|_| PrivacyError::InvalidCiphertext)?;
+            *sum = updated.into();
+        }
+
+        self.header.total_weight = self
+            .header
+            .total_weight
+            .checked_sub(old_weight)
+            .and_then(|total| total.checked_add(new_weight))
+            .ok_or(PrivacyError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+}
+
+/// Scale a ciphertext by a vote weight: `weight * E(m) = E(weight * m)`
+fn weighted(ciphertext: BallotCiphertext, weight: u64) -> Result<ElGamalCiphertext> {
+    let ciphertext = ElGamalCiphertext::from(ciphertext);
+    // Skip the scalar multiplication for unweighted ballots
+    if weight == 1 {
+        return Ok(ciphertext);
+    }
+    ciphertext
+        .mul_scalar(weight)
+        .map_err(|_| error!(PrivacyError::InvalidCiphertext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use privacy_crypto::{DiscreteLog, ElGamalKeypair, ElGamalSecretKey};
+
+    fn ballot(keypair: &ElGamalKeypair, votes: &[u64], seed: u8) -> Vec<BallotCiphertext> {
+        votes
+            .iter()
+            .map(|&vote| {
+                keypair
+                    .public
+                    .encrypt_with_randomness(vote, &[seed; 32])
+                    .unwrap()
+                    .into()
+            })
+            .collect()
+    }
+
+    fn tally(data: &mut [u8]) -> EncryptedTallyMut<'_> {
+        EncryptedTallyMut::from_account_data(data).unwrap()
+    }
+
+    fn decrypt(keypair: &ElGamalKeypair, tally: &EncryptedTallyMut) -> Vec<u64> {
+        tally
+            .aggregate
+            .iter()
+            .map(|&ct| keypair.secret.decrypt(&ct.into()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_replace_swaps_ballot_in_aggregate() {
+        let keypair = ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]));
+        let mut data = EncryptedTally::account_data(0, Pubkey::new_unique(), 2);
+        let mut tally = tally(&mut data);
+
+        let first = ballot(&keypair, &[1, 0], 1);
+        tally.accumulate(&first, 1).unwrap();
+        tally.accumulate(&ballot(&keypair, &[1, 0], 2), 1).unwrap();
+
+        tally.replace(&first, 1, &ballot(&keypair, &[0, 1], 3), 1).unwrap();
+
+        assert_eq!(decrypt(&keypair, &tally), vec![1, 1]);
+        assert_eq!(tally.header.ballots, 2);
+        assert_eq!(tally.header.total_weight, 2);
+    }
+
+    #[test]
+    fn test_accumulate_applies_weight() {
+        let keypair = ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]));
+        let mut data = EncryptedTally::account_data(0, Pubkey::new_unique(), 2);
+        let mut tally = tally(&mut data);
+
+        let first = ballot(&keypair, &[1, 0], 1);
+        tally.accumulate(&first, 250).unwrap();
+        tally.accumulate(&ballot(&keypair, &[0, 1], 2), 40).unwrap();
+        assert_eq!(decrypt(&keypair, &tally), vec![250, 40]);
+
+        // A re-vote removes the old ballot at the weight it was added with
+        tally.replace(&first, 250, &ballot(&keypair, &[0, 1], 3), 250).unwrap();
+        assert_eq!(decrypt(&keypair, &tally), vec![0, 290]);
+        assert_eq!(tally.header.ballots, 2);
+        assert_eq!(tally.header.total_weight, 290);
+    }
+
+    #[test]
+    fn test_replace_rejects_wrong_size() {
+        let keypair = ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]));
+        let mut data = EncryptedTally::account_data(0, Pubkey::new_unique(), 2);
+        let mut tally = tally(&mut data);
+
+        assert_eq!(
+            tally.replace(&ballot(&keypair, &[1, 0], 1), 1, &ballot(&keypair, &[1], 2), 1),
+            Err(PrivacyError::InvalidBallotSize.into())
+        );
+    }
+
+    fn decrypt_with_proofs(keypair: &ElGamalKeypair, tally: &EncryptedTallyMut) -> (Vec<u64>, Vec<DleqProof>) {
+        let table = DiscreteLog::new(1 << 10);
+        tally
+            .aggregate
+            .iter()
+            .map(|&ct| keypair.secret.decrypt_with_proof(&ct.into(), &table, &[7u8; 32]).unwrap())
+            .unzip()
+    }
+
+    #[test]
+    fn test_verify_decryption_of_stored_aggregate() {
+        let keypair = ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]));
+        let public_key = keypair.public.point;
+        let mut data = EncryptedTally::account_data(0, Pubkey::new_unique(), 2);
+        let mut stored = tally(&mut data);
+        stored.accumulate(&ballot(&keypair, &[1, 0], 1), 3).unwrap();
+        stored.accumulate(&ballot(&keypair, &[0, 1], 2), 2).unwrap();
+
+        let (counts, proofs) = decrypt_with_proofs(&keypair, &stored);
+        assert_eq!(counts, vec![3, 2]);
+        let stored = stored.as_ref();
+        stored.verify_decryption(&public_key, 0, &counts, &proofs).unwrap();
+        // A few options at a time
+        stored.verify_decryption(&public_key, 1, &counts[1..], &proofs[1..]).unwrap();
+        stored.verify_total(BallotRule::SingleChoice, &counts).unwrap();
+
+        // Wrong counts don't match the proofs
+        assert_eq!(
+            stored.verify_decryption(&public_key, 0, &[2, 3], &proofs),
+            Err(PrivacyError::InvalidMpcSignature.into())
+        );
+
+        // Neither do honest proofs for a different aggregate
+        let mut other_data = EncryptedTally::account_data(0, Pubkey::new_unique(), 2);
+        let mut other = tally(&mut other_data);
+        other.accumulate(&ballot(&keypair, &[0, 1], 3), 5).unwrap();
+        let (other_counts, other_proofs) = decrypt_with_proofs(&keypair, &other);
+        assert_eq!(
+            stored.verify_decryption(&public_key, 0, &other_counts, &other_proofs),
+            Err(PrivacyError::InvalidMpcSignature.into())
+        );
+
+        // Nor proofs for another option
+        assert_eq!(
+            stored.verify_decryption(&public_key, 1, &counts[..1], &proofs[..1]),
+            Err(PrivacyError::InvalidMpcSignature.into())
+        );
+
+        assert_eq!(
+            stored.verify_decryption(&public_key, 1, &counts, &proofs),
+            Err(PrivacyError::InvalidBallotSize.into())
+        );
+        assert_eq!(
+            stored.verify_total(BallotRule::SingleChoice, &counts[..1]),
+            Err(PrivacyError::InvalidBallotSize.into())
+        );
+    }
+
+    #[test]
+    fn test_verify_decryption_checks_single_choice_total() {
+        let keypair = ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]));
+        let public_key = keypair.public.point;
+        let mut data = EncryptedTally::account_data(0, Pubkey::new_unique(), 2);
+        let mut tally = tally(&mut data);
+        tally.accumulate(&ballot(&keypair, &[1, 1], 1), 1).unwrap();
+
+        // Counts that decrypt correctly but exceed the ballots' weight can
+        // only come from an approval ballot
+        let (counts, proofs) = decrypt_with_proofs(&keypair, &tally);
+        let tally = tally.as_ref();
+        tally.verify_decryption(&public_key, 0, &counts, &proofs).unwrap();
+        assert_eq!(
+            tally.verify_total(BallotRule::SingleChoice, &counts),
+            Err(PrivacyError::TallyMismatch.into())
+        );
+        tally
+            .verify_total(BallotRule::Approval { max_choices: 2 }, &counts)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_account_sized_from_options() {
+        let election = Pubkey::new_unique();
+        for num_options in [1, 2, 7] {
+            let mut data = EncryptedTally::account_data(254, election, num_options);
+            assert_eq!(data.len(), EncryptedTally::space(num_options));
+
+            let tally = tally(&mut data);
+            assert_eq!(tally.header.num_options, num_options);
+            assert_eq!(tally.header.election, election);
+            assert_eq!(tally.aggregate.len(), num_options as usize);
+            assert!(tally
+                .aggregate
+                .iter()
+                .all(|&ciphertext| ciphertext == ElGamalCiphertext::zero().into()));
+        }
+    }
+
+    #[test]
+    fn test_account_data_checked() {
+        let data = EncryptedTally::account_data(254, Pubkey::new_unique(), 3);
+        assert!(EncryptedTallyRef::from_account_data(&data).is_ok());
+
+        // The aggregate is cut short
+        assert_eq!(
+            EncryptedTallyRef::from_account_data(&data[..data.len() - 1]).err(),
+            Some(ErrorCode::AccountDidNotDeserialize.into())
+        );
+        let mut other = data.clone();
+        other[0] ^= 1;
+        assert_eq!(
+            EncryptedTallyRef::from_account_data(&other).err(),
+            Some(ErrorCode::AccountDiscriminatorMismatch.into())
+        );
+    }
+}
@@ -1,38 +1,128 @@
 use anchor_lang::prelude::*;
+use bytemuck::{Pod, Zeroable};
+use privacy_crypto::ElGamalCiphertext;
+use crate::errors::PrivacyError;
 
 /// Encrypted vote account
 ///
-/// Stores an individual encrypted vote
+/// Stores an individual encrypted ballot, one ciphertext per option
 #[account]
 pub struct EncryptedVote {
+    /// Layout version (`EncryptedVote::VERSION`)
+    pub version: u8,
+
     /// Bump seed for PDA
     pub bump: u8,
 
     /// The private election this vote belongs to
     pub election: Pubkey,
 
-    /// The encrypted vote C1 component (32 bytes)
-    pub ciphertext_c1: [u8; 32],
-
-    /// The encrypted vote C2 component (32 bytes)
-    pub ciphertext_c2: [u8; 32],
+    /// One ElGamal ciphertext per option (each encrypts 0 or 1)
+    pub ciphertexts: Vec<BallotCiphertext>,
 
     /// Nullifier to prevent double voting (32 bytes)
     pub nullifier: [u8; 32],
 
-    /// Commitment to the vote (32 bytes)
+    /// Receipt commitment from `privacy_crypto::commit_ballot` (32 bytes)
     pub commitment: [u8; 32],
 
-    /// Timestamp when vote was cast
+    /// Number of times the ballot has been replaced by a re-vote
+    pub revision: u32,
+
+    /// Weight the ballot was counted with
+    pub weight: u64,
+
+    /// Timestamp when the current ballot was cast
     pub timestamp: i64,
 }
 
 impl EncryptedVote {
-    pub const LEN: usize = 8 + // discriminator
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    /// Size without the ciphertext entries
+    pub const BASE_LEN: usize = 8 + // discriminator
+        1 + // version
         1 + // bump
         32 + // election
-        64 + // ciphertext (c1 + c2)
+        4 + // ciphertexts vec length prefix
         32 + // nullifier
         32 + // commitment
+        4 + // revision
+        8 + // weight
         8; // timestamp
+
+    /// Account size for a ballot with `num_options` options
+    pub fn space(num_options: u8) -> usize {
+        Self::BASE_LEN + num_options as usize * BallotCiphertext::LEN
+    }
+}
+
+/// A single ElGamal ciphertext (C1, C2) as stored on-chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+#[repr(C)]
+pub struct BallotCiphertext {
+    /// C1 component (32 bytes)
+    pub c1: [u8; 32],
+
+    /// C2 component (32 bytes)
+    pub c2: [u8; 32],
+}
+
+impl BallotCiphertext {
+    pub const LEN: usize = 32 + 32;
+
+    /// Reject non-canonical points and an identity `C1`
+    pub fn validate(&self) -> Result<()> {
+        ElGamalCiphertext::from(*self)
+            .validate()
+            .map_err(|_| error!(PrivacyError::InvalidCiphertext))
+    }
+}
+
+impl From<BallotCiphertext> for ElGamalCiphertext {
+    fn from(ciphertext: BallotCiphertext) -> Self {
+        ElGamalCiphertext {
+            c1: ciphertext.c1,
+            c2: ciphertext.c2,
+        }
+    }
+}
+
+impl From<ElGamalCiphertext> for BallotCiphertext {
+    fn from(ciphertext: ElGamalCiphertext) -> Self {
+        BallotCiphertext {
+            c1: ciphertext.c1,
+            c2: ciphertext.c2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use privacy_crypto::{ElGamalKeypair, ElGamalSecretKey};
+
+    #[test]
+    fn test_validate_rejects_malformed_ciphertexts() {
+        let keypair = ElGamalKeypair::from_secret(ElGamalSecretKey::from_bytes([3u8; 32]));
+        let valid: BallotCiphertext = keypair
+            .public
+            .encrypt_with_randomness(1, &[9u8; 32])
+            .unwrap()
+            .into();
+        assert!(valid.validate().is_ok());
+
+        let invalid = [
+            BallotCiphertext { c1: [0u8; 32], ..valid },
+            BallotCiphertext { c1: [0xFF; 32], ..valid },
+            BallotCiphertext { c2: [0xFF; 32], ..valid },
+        ];
+        for ciphertext in invalid {
+            assert_eq!(
+                ciphertext.validate(),
+                Err(PrivacyError::InvalidCiphertext.into())
+            );
+        }
+    }
 }
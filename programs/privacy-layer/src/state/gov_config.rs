@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+/// Governance configuration account
+///
+/// Singleton PDA seeded by `[b"gov_config"]` naming the mpl-gov-micro
+/// program whose elections this program may shadow and report results to
+#[account]
+pub struct GovConfig {
+    /// Layout version (`GovConfig::VERSION`)
+    pub version: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// The mpl-gov-micro program ID
+    pub gov_program: Pubkey,
+}
+
+impl GovConfig {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        1 + // bump
+        32; // gov_program
+}
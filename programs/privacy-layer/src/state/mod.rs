@@ -1,7 +1,19 @@
 pub mod private_election;
 pub mod encrypted_vote;
-pub mod nullifier_set;
+pub mod nullifier_record;
+pub mod tally_result;
+pub mod encrypted_tally;
+pub mod verifying_key;
+pub mod election_results;
+pub mod gov_config;
+pub mod ballot_buffer;
 
 pub use private_election::*;
 pub use encrypted_vote::*;
-pub use nullifier_set::*;
+pub use nullifier_record::*;
+pub use tally_result::*;
+pub use encrypted_tally::*;
+pub use verifying_key::*;
+pub use election_results::*;
+pub use gov_config::*;
+pub use ballot_buffer::*;
@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::errors::PrivacyError;
+
+/// Nullifier record account
+///
+/// One PDA per used nullifier, seeded by `[b"nullifier", private_election,
+/// nullifier]`. The account existing is the set membership test: casting
+/// checks the address is still empty before creating it, so a second cast
+/// with the same nullifier fails with `NullifierAlreadyUsed`. Lookup and
+/// insert cost the same whether an election has ten voters or a million,
+/// and no single account grows with the electorate.
+#[account]
+pub struct NullifierRecord {
+    /// Layout version (`NullifierRecord::VERSION`)
+    pub version: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// The private election this nullifier was used in
+    pub election: Pubkey,
+
+    /// The spent nullifier (32 bytes)
+    pub nullifier: [u8; 32],
+
+    /// Timestamp when the nullifier was spent
+    pub created_at: i64,
+
+    /// Account that paid rent for this record and its vote, refunded by
+    /// `close_vote_account`; the relayer for relayed ballots
+    pub rent_payer: Pubkey,
+}
+
+impl NullifierRecord {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        1 + // bump
+        32 + // election
+        32 + // nullifier
+        8 + // created_at
+        32; // rent_payer
+
+    /// Derive the record address for a nullifier
+    pub fn find_address(private_election: &Pubkey, nullifier: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"nullifier", private_election.as_ref(), nullifier.as_ref()],
+            &crate::ID,
+        )
+    }
+
+    /// Fail with `NullifierAlreadyUsed` if `record` has been created
+    ///
+    /// Lamports sent to the unused address don't count: it stays a data-less
+    /// system account until a cast creates the record.
+    pub fn require_unspent(record: &AccountInfo) -> Result<()> {
+        require!(
+            record.owner == &system_program::ID && record.data_is_empty(),
+            PrivacyError::NullifierAlreadyUsed
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_size_is_independent_of_voter_count() {
+        // Every cast allocates exactly one fixed-size record; nothing scales
+        // with the number of nullifiers already spent
+        let election = Pubkey::new_unique();
+        let addresses: Vec<Pubkey> = (0..1000u32)
+            .map(|i| {
+                let mut nullifier = [0u8; 32];
+                nullifier[..4].copy_from_slice(&i.to_le_bytes());
+                NullifierRecord::find_address(&election, &nullifier).0
+            })
+            .collect();
+
+        let mut unique = addresses.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), addresses.len());
+        assert_eq!(NullifierRecord::LEN, 114);
+    }
+
+    fn require_unspent(owner: &Pubkey, lamports: u64, data: &mut [u8]) -> Result<()> {
+        let key = Pubkey::new_unique();
+        let mut lamports = lamports;
+        let record = AccountInfo::new(&key, false, true, &mut lamports, data, owner, false, 0);
+        NullifierRecord::require_unspent(&record)
+    }
+
+    #[test]
+    fn test_unused_nullifier_is_unspent() {
+        assert!(require_unspent(&system_program::ID, 0, &mut []).is_ok());
+        // Lamports sent ahead of the cast don't spend it
+        assert!(require_unspent(&system_program::ID, 1_000_000, &mut []).is_ok());
+    }
+
+    #[test]
+    fn test_existing_record_is_spent() {
+        let mut data = vec![0u8; NullifierRecord::LEN];
+
+        assert_eq!(
+            require_unspent(&crate::ID, 1_000_000, &mut data),
+            Err(PrivacyError::NullifierAlreadyUsed.into())
+        );
+    }
+
+    #[test]
+    fn test_same_nullifier_maps_to_same_record() {
+        let election = Pubkey::new_unique();
+        let nullifier = [7u8; 32];
+
+        assert_eq!(
+            NullifierRecord::find_address(&election, &nullifier),
+            NullifierRecord::find_address(&election, &nullifier)
+        );
+    }
+
+    #[test]
+    fn test_nullifier_scoped_to_election() {
+        let nullifier = [7u8; 32];
+
+        assert_ne!(
+            NullifierRecord::find_address(&Pubkey::new_unique(), &nullifier).0,
+            NullifierRecord::find_address(&Pubkey::new_unique(), &nullifier).0
+        );
+    }
+}
@@ -1,16 +1,57 @@
 use anchor_lang::prelude::*;
+use crate::errors::PrivacyError;
+use privacy_crypto::ballot::BallotRule as CryptoBallotRule;
+
+/// Longest allowed voting window (90 days)
+pub const MAX_ELECTION_DURATION: i64 = 90 * 24 * 60 * 60;
+
+/// Fewest options a ballot may have
+pub const MIN_OPTIONS: u8 = 2;
+
+/// Most options a ballot may have
+///
+/// Ballots are written to a `BallotBuffer` over as many transactions as
+/// they take and tallies are submitted in parts, so no transaction has to
+/// carry every option. What bounds it is the work a cast still does in
+/// one instruction, folding every option into the tally, which the
+/// client's `bench_max_option_cast_and_recast` checks against the compute
+/// limit. Every account sized by the options fits `MAX_ACCOUNT_SIZE`.
+pub const MAX_OPTIONS: u8 = 16;
+
+/// Largest account an instruction can create (`MAX_PERMITTED_DATA_INCREASE`)
+pub const MAX_ACCOUNT_SIZE: usize = 10 * 1024;
+
+/// Largest transaction the cluster accepts (`PACKET_DATA_SIZE`)
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+/// Longest allowed audit period after finalization (365 days)
+pub const MAX_AUDIT_PERIOD: i64 = 365 * 24 * 60 * 60;
 
 /// Private election state account
 ///
 /// This account stores the configuration and state of a private election
 #[account]
 pub struct PrivateElection {
+    /// Layout version (`PrivateElection::VERSION`)
+    pub version: u8,
+
     /// Bump seed for PDA
     pub bump: u8,
 
-    /// Authority that can manage the election
+    /// Election admin: opens, closes early, requests the tally and
+    /// manages roles. May be a PDA, e.g. a multisig vault
     pub authority: Pubkey,
 
+    /// Proposed new admin, which takes over once it signs
+    /// `accept_authority`
+    pub pending_authority: Option<Pubkey>,
+
+    /// The only signer allowed to submit the decrypted tally
+    pub tally_submitter: Pubkey,
+
+    /// The only signer allowed to cancel the election
+    pub guardian: Pubkey,
+
     /// Reference to the public election in mpl-gov-micro
     pub election: Pubkey,
 
@@ -20,12 +61,16 @@ pub struct PrivateElection {
     /// Root of the voter merkle tree for ZK proofs
     pub voter_merkle_root: [u8; 32],
 
-    /// Election identifier for nullifier computation
+    /// Election identifier for nullifier computation; see
+    /// `PrivateElection::derive_election_id`
     pub election_id: [u8; 32],
 
     /// Total number of encrypted votes cast
     pub total_encrypted_votes: u64,
 
+    /// Vote accounts not yet closed with `close_vote_account`
+    pub open_vote_accounts: u64,
+
     /// Whether tally has been requested from MPC network
     pub tally_requested: bool,
 
@@ -35,34 +80,186 @@ pub struct PrivateElection {
     /// Timestamp when election was created
     pub created_at: i64,
 
+    /// Timestamp when voting opens
+    pub starts_at: i64,
+
     /// Timestamp when election ends
     pub ends_at: i64,
 
     /// Number of vote options
     pub num_options: u8,
 
+    /// How many options a ballot may select
+    pub ballot_rule: BallotRule,
+
+    /// How voters prove they are on `voter_merkle_root`
+    pub eligibility_mode: EligibilityMode,
+
+    /// Whether a voter may replace their ballot until `ends_at`; the
+    /// latest ballot is the one counted
+    pub allow_revoting: bool,
+
+    /// Where each voter's ballot weight comes from
+    pub vote_weighting: VoteWeighting,
+
+    /// Seconds after finalization during which every account stays
+    /// on-chain for audit before rent can be reclaimed
+    pub audit_period: i64,
+
+    /// Most lamports a relayer may be paid per relayed ballot from the
+    /// election's fee vault; 0 means relayers are unpaid
+    pub max_relayer_fee: u64,
+
     /// Status of the election
     pub status: ElectionStatus,
 }
 
 impl PrivateElection {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
     pub const LEN: usize = 8 + // discriminator
+        1 + // version
         1 + // bump
         32 + // authority
+        1 + 32 + // pending_authority
+        32 + // tally_submitter
+        32 + // guardian
         32 + // election
         32 + // mpc_public_key
         32 + // voter_merkle_root
         32 + // election_id
         8 + // total_encrypted_votes
+        8 + // open_vote_accounts
         1 + // tally_requested
         1 + // tally_finalized
         8 + // created_at
+        8 + // starts_at
         8 + // ends_at
         1 + // num_options
+        BallotRule::LEN + // ballot_rule
+        1 + // eligibility_mode
+        1 + // allow_revoting
+        VoteWeighting::LEN + // vote_weighting
+        8 + // audit_period
+        8 + // max_relayer_fee
         1; // status
+
+    /// Election identifier of the private election at `private_election`
+    ///
+    /// Its own address, so no two elections can share nullifiers or
+    /// ballot commitments, whoever creates them.
+    pub fn derive_election_id(private_election: &Pubkey) -> [u8; 32] {
+        private_election.to_bytes()
+    }
+
+    /// Check a voting window: `now < starts_at < ends_at`, and no longer
+    /// than `MAX_ELECTION_DURATION`
+    pub fn validate_schedule(starts_at: i64, ends_at: i64, now: i64) -> Result<()> {
+        require!(starts_at > now, PrivacyError::InvalidSchedule);
+        require!(ends_at > starts_at, PrivacyError::InvalidSchedule);
+        require!(
+            ends_at - starts_at <= MAX_ELECTION_DURATION,
+            PrivacyError::InvalidSchedule
+        );
+        Ok(())
+    }
+
+    /// Check a ballot shape: `MIN_OPTIONS..=MAX_OPTIONS` options, under a
+    /// rule that makes sense for them
+    pub fn validate_options(num_options: u8, ballot_rule: BallotRule) -> Result<()> {
+        require!(
+            (MIN_OPTIONS..=MAX_OPTIONS).contains(&num_options),
+            PrivacyError::InvalidNumOptions
+        );
+        require!(
+            ballot_rule.is_valid_for(num_options),
+            PrivacyError::InvalidBallotRule
+        );
+        Ok(())
+    }
+
+    /// Count a newly stored ballot; returns its zero-based index in cast
+    /// order
+    pub fn count_ballot(&mut self) -> Result<u64> {
+        let vote_index = self.total_encrypted_votes;
+        self.total_encrypted_votes = vote_index
+            .checked_add(1)
+            .ok_or(PrivacyError::ArithmeticOverflow)?;
+        self.open_vote_accounts = self
+            .open_vote_accounts
+            .checked_add(1)
+            .ok_or(PrivacyError::ArithmeticOverflow)?;
+        Ok(vote_index)
+    }
+
+    /// Check `now` falls in the voting window `[starts_at, ends_at)`
+    pub fn require_voting_open(&self, now: i64) -> Result<()> {
+        require!(now >= self.starts_at, PrivacyError::VotingNotStarted);
+        require!(now < self.ends_at, PrivacyError::ElectionEnded);
+        Ok(())
+    }
+
+    /// Check a verifying key is `given` exactly when eligibility is proven
+    /// anonymously
+    pub fn require_verifying_key(&self, given: bool) -> Result<()> {
+        require!(
+            given == (self.eligibility_mode == EligibilityMode::Anonymous),
+            PrivacyError::VerifyingKeyMismatch
+        );
+        Ok(())
+    }
+
+    /// Check the election's accounts may be closed at `now`: once a
+    /// finalized election's audit period, counted from its tally result's
+    /// `finalized_at`, is over, or any time after it is cancelled
+    pub fn require_closable(&self, finalized_at: Option<i64>, now: i64) -> Result<()> {
+        match (self.status, finalized_at) {
+            // Nothing was decrypted, so there is nothing to audit
+            (ElectionStatus::Cancelled, None) => Ok(()),
+            (ElectionStatus::Finalized, Some(finalized_at)) => {
+                let audit_ends_at = finalized_at
+                    .checked_add(self.audit_period)
+                    .ok_or(PrivacyError::ArithmeticOverflow)?;
+                require!(now >= audit_ends_at, PrivacyError::AuditPeriodNotOver);
+                Ok(())
+            }
+            (ElectionStatus::Finalized, None) => err!(ErrorCode::ConstraintAccountIsNone),
+            _ => err!(PrivacyError::InvalidElectionStatus),
+        }
+    }
+
+    /// Complete a two-step authority transfer; `signer` must be the
+    /// proposed authority
+    pub fn accept_authority(&mut self, signer: Pubkey) -> Result<()> {
+        require!(
+            self.pending_authority == Some(signer),
+            PrivacyError::Unauthorized
+        );
+        self.authority = signer;
+        self.pending_authority = None;
+        Ok(())
+    }
+
+    /// Move to `next`, enforcing `ElectionStatus::can_transition_to`
+    pub fn transition_to(&mut self, next: ElectionStatus) -> Result<()> {
+        require!(
+            self.status.can_transition_to(next),
+            PrivacyError::InvalidElectionStatus
+        );
+        self.status = next;
+        Ok(())
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Election lifecycle
+///
+/// ```text
+/// Pending --open--> Active --close--> Ended --submit tally--> Finalized
+///    |                 |
+///    +----cancel-------+------------> Cancelled
+/// ```
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ElectionStatus {
     /// Election is being set up
     Pending,
@@ -72,4 +269,427 @@ pub enum ElectionStatus {
     Ended,
     /// Election is finalized with results
     Finalized,
+    /// Election was called off before the tally; no results
+    Cancelled,
+}
+
+impl ElectionStatus {
+    pub const ALL: [ElectionStatus; 5] = [
+        ElectionStatus::Pending,
+        ElectionStatus::Active,
+        ElectionStatus::Ended,
+        ElectionStatus::Finalized,
+        ElectionStatus::Cancelled,
+    ];
+
+    /// The transition table: every legal status change, and nothing else
+    pub fn can_transition_to(self, next: ElectionStatus) -> bool {
+        use ElectionStatus::*;
+
+        matches!(
+            (self, next),
+            (Pending, Active) | (Pending, Cancelled) | (Active, Ended) | (Active, Cancelled) | (Ended, Finalized)
+        )
+    }
+
+    /// Whether no further transitions are possible
+    pub fn is_terminal(self) -> bool {
+        ElectionStatus::ALL
+            .iter()
+            .all(|&next| !self.can_transition_to(next))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BallotRule {
+    /// Exactly one option per ballot
+    SingleChoice,
+    /// Up to `max_choices` options per ballot
+    Approval { max_choices: u8 },
+}
+
+impl BallotRule {
+    pub const LEN: usize = 1 + // variant
+        1; // max_choices
+
+    /// Check the rule makes sense for `num_options` options
+    pub fn is_valid_for(&self, num_options: u8) -> bool {
+        match *self {
+            BallotRule::SingleChoice => true,
+            BallotRule::Approval { max_choices } => max_choices >= 1 && max_choices <= num_options,
+        }
+    }
+
+    /// Number of values a ballot's option total may take, each of which
+    /// adds a branch to its total proof
+    pub const fn num_totals(&self) -> usize {
+        match *self {
+            BallotRule::SingleChoice => 1,
+            BallotRule::Approval { max_choices } => max_choices as usize + 1,
+        }
+    }
+}
+
+impl From<BallotRule> for CryptoBallotRule {
+    fn from(rule: BallotRule) -> Self {
+        match rule {
+            BallotRule::SingleChoice => CryptoBallotRule::SingleChoice,
+            BallotRule::Approval { max_choices } => CryptoBallotRule::Approval { max_choices },
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum EligibilityMode {
+    /// Voters prove membership in zero knowledge; their wallet is not revealed
+    Anonymous,
+    /// The voter's wallet is a leaf of `voter_merkle_root` and is checked
+    /// on-chain with a Merkle inclusion proof
+    Public,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VoteWeighting {
+    /// One voter, one vote; every ballot has weight 1
+    Equal,
+    /// Each voter's weight is committed in their Merkle leaf with
+    /// `privacy_crypto::weighted_leaf`
+    MerkleLeaf,
+    /// As `MerkleLeaf`, with each weight being the voter's balance of
+    /// `mint` at `slot`; recorded so anyone can rebuild and audit the tree
+    TokenSnapshot { mint: Pubkey, slot: u64 },
+}
+
+impl VoteWeighting {
+    pub const LEN: usize = 1 + // variant
+        32 + // mint
+        8; // slot
+
+    /// Whether weights are committed in the voter tree leaves
+    pub fn is_weighted(&self) -> bool {
+        !matches!(self, VoteWeighting::Equal)
+    }
+
+    /// Check a claimed weight is allowed; whether it is the voter's
+    /// weight is checked against the voter tree
+    pub fn check_weight(&self, weight: u64) -> Result<()> {
+        match self {
+            VoteWeighting::Equal => require!(weight == 1, PrivacyError::InvalidWeight),
+            _ => require!(weight > 0, PrivacyError::InvalidWeight),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{BallotBuffer, ElectionResults, EncryptedTally, EncryptedVote, TallyResult};
+    use privacy_crypto::merkle::MAX_MERKLE_DEPTH;
+    use ElectionStatus::*;
+
+    fn election(status: ElectionStatus) -> PrivateElection {
+        PrivateElection {
+            version: PrivateElection::VERSION,
+            bump: 0,
+            authority: Pubkey::new_unique(),
+            pending_authority: None,
+            tally_submitter: Pubkey::new_unique(),
+            guardian: Pubkey::new_unique(),
+            election: Pubkey::new_unique(),
+            mpc_public_key: [0; 32],
+            voter_merkle_root: [0; 32],
+            election_id: [0; 32],
+            total_encrypted_votes: 0,
+            open_vote_accounts: 0,
+            tally_requested: false,
+            tally_finalized: false,
+            created_at: 0,
+            starts_at: 0,
+            ends_at: 0,
+            num_options: 2,
+            ballot_rule: BallotRule::SingleChoice,
+            eligibility_mode: EligibilityMode::Anonymous,
+            allow_revoting: false,
+            vote_weighting: VoteWeighting::Equal,
+            audit_period: 0,
+            max_relayer_fee: 0,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_voting_window() {
+        let mut private_election = election(Active);
+        private_election.starts_at = 100;
+        private_election.ends_at = 200;
+
+        assert_eq!(
+            private_election.require_voting_open(99),
+            Err(PrivacyError::VotingNotStarted.into())
+        );
+        assert!(private_election.require_voting_open(100).is_ok());
+        assert!(private_election.require_voting_open(199).is_ok());
+        assert_eq!(
+            private_election.require_voting_open(200),
+            Err(PrivacyError::ElectionEnded.into())
+        );
+    }
+
+    #[test]
+    fn test_count_ballot() {
+        let mut private_election = election(Active);
+        private_election.open_vote_accounts = 5;
+
+        assert_eq!(private_election.count_ballot(), Ok(0));
+        assert_eq!(private_election.count_ballot(), Ok(1));
+        assert_eq!(private_election.total_encrypted_votes, 2);
+        assert_eq!(private_election.open_vote_accounts, 7);
+
+        private_election.total_encrypted_votes = u64::MAX;
+        assert_eq!(
+            private_election.count_ballot(),
+            Err(PrivacyError::ArithmeticOverflow.into())
+        );
+    }
+
+    #[test]
+    fn test_audit_period() {
+        let mut private_election = election(Ended);
+        private_election.audit_period = 100;
+
+        assert_eq!(
+            private_election.require_closable(Some(1_000), 2_000),
+            Err(PrivacyError::InvalidElectionStatus.into())
+        );
+
+        private_election.status = Finalized;
+        assert_eq!(
+            private_election.require_closable(Some(1_000), 1_099),
+            Err(PrivacyError::AuditPeriodNotOver.into())
+        );
+        assert!(private_election.require_closable(Some(1_000), 1_100).is_ok());
+        assert_eq!(
+            private_election.require_closable(None, 2_000),
+            Err(ErrorCode::ConstraintAccountIsNone.into())
+        );
+    }
+
+    #[test]
+    fn test_cancelled_election_closable_at_once() {
+        let mut private_election = election(Cancelled);
+        private_election.audit_period = 100;
+
+        assert!(private_election.require_closable(None, 0).is_ok());
+        // A cancelled election never has a tally result
+        assert_eq!(
+            private_election.require_closable(Some(0), 2_000),
+            Err(PrivacyError::InvalidElectionStatus.into())
+        );
+    }
+
+    #[test]
+    fn test_verifying_key_only_for_anonymous() {
+        let mut private_election = election(Pending);
+        assert!(private_election.require_verifying_key(true).is_ok());
+        assert_eq!(
+            private_election.require_verifying_key(false),
+            Err(PrivacyError::VerifyingKeyMismatch.into())
+        );
+
+        private_election.eligibility_mode = EligibilityMode::Public;
+        assert!(private_election.require_verifying_key(false).is_ok());
+        assert_eq!(
+            private_election.require_verifying_key(true),
+            Err(PrivacyError::VerifyingKeyMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_authority_transfer() {
+        let mut private_election = election(Pending);
+        let original = private_election.authority;
+        let proposed = Pubkey::new_unique();
+
+        // Nothing proposed yet
+        assert_eq!(
+            private_election.accept_authority(proposed),
+            Err(PrivacyError::Unauthorized.into())
+        );
+
+        private_election.pending_authority = Some(proposed);
+        assert_eq!(
+            private_election.accept_authority(Pubkey::new_unique()),
+            Err(PrivacyError::Unauthorized.into())
+        );
+        assert_eq!(private_election.authority, original);
+
+        private_election.accept_authority(proposed).unwrap();
+        assert_eq!(private_election.authority, proposed);
+        assert_eq!(private_election.pending_authority, None);
+
+        // The proposal is consumed
+        assert!(private_election.accept_authority(proposed).is_err());
+    }
+
+    #[test]
+    fn test_vote_weighting_checks() {
+        let snapshot = VoteWeighting::TokenSnapshot {
+            mint: Pubkey::new_unique(),
+            slot: 1,
+        };
+
+        assert!(VoteWeighting::Equal.check_weight(1).is_ok());
+        assert!(VoteWeighting::Equal.check_weight(2).is_err());
+        for weighting in [VoteWeighting::MerkleLeaf, snapshot] {
+            assert!(weighting.is_weighted());
+            assert!(weighting.check_weight(1_000_000).is_ok());
+            assert_eq!(weighting.check_weight(0), Err(PrivacyError::InvalidWeight.into()));
+        }
+    }
+
+    #[test]
+    fn test_transition_table_is_exhaustive() {
+        let legal = [
+            (Pending, Active),
+            (Pending, Cancelled),
+            (Active, Ended),
+            (Active, Cancelled),
+            (Ended, Finalized),
+        ];
+
+        for from in ElectionStatus::ALL {
+            for to in ElectionStatus::ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    legal.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_illegal_transitions_leave_status_unchanged() {
+        for from in ElectionStatus::ALL {
+            for to in ElectionStatus::ALL {
+                let mut private_election = election(from);
+                let result = private_election.transition_to(to);
+
+                if from.can_transition_to(to) {
+                    assert!(result.is_ok());
+                    assert_eq!(private_election.status, to);
+                } else {
+                    assert_eq!(result, Err(PrivacyError::InvalidElectionStatus.into()));
+                    assert_eq!(private_election.status, from, "{:?} -> {:?}", from, to);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_self_transitions() {
+        for status in ElectionStatus::ALL {
+            assert!(!status.can_transition_to(status));
+        }
+    }
+
+    #[test]
+    fn test_terminal_states() {
+        assert!(Finalized.is_terminal());
+        assert!(Cancelled.is_terminal());
+        assert!(!Pending.is_terminal());
+        assert!(!Active.is_terminal());
+        assert!(!Ended.is_terminal());
+    }
+
+    #[test]
+    fn test_election_id_derived_from_address() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(PrivateElection::derive_election_id(&first), first.to_bytes());
+
+        // One voter gets unrelated nullifiers in different elections
+        let voter = Pubkey::new_unique().to_bytes();
+        assert_ne!(
+            privacy_crypto::compute_public_nullifier(&voter, &PrivateElection::derive_election_id(&first)),
+            privacy_crypto::compute_public_nullifier(&voter, &PrivateElection::derive_election_id(&second))
+        );
+    }
+
+    #[test]
+    fn test_options_validation() {
+        use BallotRule::*;
+
+        for num_options in MIN_OPTIONS..=MAX_OPTIONS {
+            assert!(PrivateElection::validate_options(num_options, SingleChoice).is_ok());
+            assert!(PrivateElection::validate_options(num_options, Approval { max_choices: num_options }).is_ok());
+        }
+        assert!(PrivateElection::validate_options(2, Approval { max_choices: 1 }).is_ok());
+
+        // Outside MIN_OPTIONS..=MAX_OPTIONS
+        for num_options in [0, MIN_OPTIONS - 1, MAX_OPTIONS + 1, u8::MAX] {
+            assert_eq!(
+                PrivateElection::validate_options(num_options, SingleChoice),
+                Err(PrivacyError::InvalidNumOptions.into())
+            );
+        }
+
+        // More choices than options, or none
+        assert_eq!(
+            PrivateElection::validate_options(2, Approval { max_choices: 3 }),
+            Err(PrivacyError::InvalidBallotRule.into())
+        );
+        assert_eq!(
+            PrivateElection::validate_options(2, Approval { max_choices: 0 }),
+            Err(PrivacyError::InvalidBallotRule.into())
+        );
+    }
+
+    #[test]
+    fn test_accounts_fit_at_max_options() {
+        // The largest ballot buffer is for approval of every option, whose
+        // total proof has the most branches, by a voter in the deepest tree
+        let rule = BallotRule::Approval { max_choices: MAX_OPTIONS };
+        for space in [
+            BallotBuffer::space(MAX_OPTIONS, rule, MAX_MERKLE_DEPTH as u8),
+            EncryptedTally::space(MAX_OPTIONS),
+            EncryptedVote::space(MAX_OPTIONS),
+            TallyResult::space(MAX_OPTIONS),
+            ElectionResults::space(MAX_OPTIONS),
+        ] {
+            assert!(space <= MAX_ACCOUNT_SIZE, "{space}");
+        }
+    }
+
+    #[test]
+    fn test_schedule_validation() {
+        let now = 1_000_000;
+
+        assert!(PrivateElection::validate_schedule(now + 1, now + 2, now).is_ok());
+        assert!(PrivateElection::validate_schedule(now + 10, now + 10 + MAX_ELECTION_DURATION, now).is_ok());
+
+        // Start in the past or right now
+        assert!(PrivateElection::validate_schedule(now - 1, now + 100, now).is_err());
+        assert!(PrivateElection::validate_schedule(now, now + 100, now).is_err());
+        // End at or before the start
+        assert!(PrivateElection::validate_schedule(now + 100, now + 100, now).is_err());
+        assert!(PrivateElection::validate_schedule(now + 100, now + 50, now).is_err());
+        // Too long
+        assert!(PrivateElection::validate_schedule(now + 10, now + 11 + MAX_ELECTION_DURATION, now).is_err());
+    }
+
+    #[test]
+    fn test_full_lifecycle() {
+        let mut private_election = election(Pending);
+
+        private_election.transition_to(Active).unwrap();
+        private_election.transition_to(Ended).unwrap();
+        private_election.transition_to(Finalized).unwrap();
+
+        // Finalized results can't be reopened or cancelled
+        assert!(private_election.transition_to(Active).is_err());
+        assert!(private_election.transition_to(Cancelled).is_err());
+    }
 }
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use crate::state::BallotCiphertext;
+
+/// Tally result account
+///
+/// Stores the decrypted per-option counts of a finalized election together
+/// with the aggregate ciphertexts they were decrypted from
+#[account]
+pub struct TallyResult {
+    /// Layout version (`TallyResult::VERSION`)
+    pub version: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// The private election this result belongs to
+    pub election: Pubkey,
+
+    /// Decrypted vote count per option
+    pub counts: Vec<u64>,
+
+    /// Homomorphic sum of all ballots, one ciphertext per option
+    pub aggregate: Vec<BallotCiphertext>,
+
+    /// Account that submitted the verified result
+    pub submitted_by: Pubkey,
+
+    /// Timestamp when the result was submitted
+    pub finalized_at: i64,
+
+    /// Whether the counts have been reported to mpl-gov-micro
+    pub published: bool,
+}
+
+impl TallyResult {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    /// Size without the per-option entries
+    pub const BASE_LEN: usize = 8 + // discriminator
+        1 + // version
+        1 + // bump
+        32 + // election
+        4 + // counts vec length prefix
+        4 + // aggregate vec length prefix
+        32 + // submitted_by
+        8 + // finalized_at
+        1; // published
+
+    /// Account size for an election with `num_options` options
+    pub fn space(num_options: u8) -> usize {
+        Self::BASE_LEN + num_options as usize * (8 + BallotCiphertext::LEN)
+    }
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::groth16::{ELIGIBILITY_PUBLIC_INPUTS, G1_LEN, G2_LEN};
+
+/// Groth16 verifying key for an election's voter eligibility circuit
+///
+/// One PDA per private election, seeded by `[b"verifying_key",
+/// private_election]`. Points use the EIP-197 big-endian encoding expected
+/// by the alt_bn128 syscalls. Zero-copy, since every anonymous ballot
+/// reads it.
+#[account(zero_copy)]
+pub struct EligibilityVerifyingKey {
+    /// Layout version (`EligibilityVerifyingKey::VERSION`)
+    pub version: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// The private election this key verifies proofs for
+    pub election: Pubkey,
+
+    /// alpha in G1 (64 bytes)
+    pub alpha_g1: [u8; G1_LEN],
+
+    /// beta in G2 (128 bytes)
+    pub beta_g2: [u8; G2_LEN],
+
+    /// gamma in G2 (128 bytes)
+    pub gamma_g2: [u8; G2_LEN],
+
+    /// delta in G2 (128 bytes)
+    pub delta_g2: [u8; G2_LEN],
+
+    /// Public input commitments in G1, one more than the number of inputs
+    pub ic: [[u8; G1_LEN]; ELIGIBILITY_PUBLIC_INPUTS + 1],
+}
+
+impl EligibilityVerifyingKey {
+    /// Current layout version; see `crate::migration`
+    pub const VERSION: u8 = 2;
+
+    pub const LEN: usize = 8 + // discriminator
+        std::mem::size_of::<EligibilityVerifyingKey>();
+}
@@ -29,7 +29,6 @@ async function benchmark() {
   console.log("🔧 Benchmarking: Initialize Private Election");
 
   const mockElection = anchor.web3.Keypair.generate();
-  const electionId = Array.from(new Uint8Array(32));
   const mpcPublicKey = Array.from(new Uint8Array(32));
   const merkleRoot = Array.from(new Uint8Array(32));
   const endsAt = new anchor.BN(Date.now() / 1000 + 86400);
@@ -46,7 +45,7 @@ async function benchmark() {
 
   try {
     const tx = await program.methods
-      .initializePrivateElection(electionId, mpcPublicKey, merkleRoot, endsAt, 3)
+      .initializePrivateElection(mpcPublicKey, merkleRoot, endsAt, 2)
       .accounts({
         privateElection,
         nullifierSet,
@@ -26,58 +26,65 @@ async function setupElection() {
   const keys = JSON.parse(fs.readFileSync(keysPath, "utf-8"));
   console.log("📋 Loaded test keys");
 
-  // Create a mock election account
-  const mockElection = anchor.web3.Keypair.generate();
-
-  // Generate election ID
-  const electionId = new Uint8Array(32);
-  crypto.getRandomValues(electionId);
+  // The mpl-gov-micro election and its authority, who must co-sign
+  const election = new anchor.web3.PublicKey(process.env.ELECTION!);
+  const electionAuthority = anchor.web3.Keypair.fromSecretKey(
+    Uint8Array.from(JSON.parse(fs.readFileSync(process.env.ELECTION_AUTHORITY!, "utf-8")))
+  );
 
   // Election config
-  const endsAt = new anchor.BN(Date.now() / 1000 + 86400); // 24 hours from now
-  const numOptions = 3; // 3 voting options
+  const startsAt = new anchor.BN(Math.floor(Date.now() / 1000) + 600); // 10 minutes from now
+  const endsAt = new anchor.BN(Math.floor(Date.now() / 1000) + 86400); // 24 hours from now
+  const numOptions = 2; // 2 voting options
 
   console.log("\n⚙️  Election Configuration:");
   console.log("   Options:", numOptions);
   console.log("   Duration: 24 hours");
-  console.log("   Mock Election Account:", mockElection.publicKey.toString());
+  console.log("   Election Account:", election.toString());
 
   // Find PDAs
   const [privateElection, privateElectionBump] =
     anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("private_election"), mockElection.publicKey.toBuffer()],
+      [Buffer.from("private_election"), election.toBuffer()],
       program.programId
     );
 
-  const [nullifierSet, nullifierSetBump] =
-    anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("nullifier_set"), mockElection.publicKey.toBuffer()],
-      program.programId
-    );
+  const [encryptedTally] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("encrypted_tally"), election.toBuffer()],
+    program.programId
+  );
 
   console.log("\n📍 PDAs:");
   console.log("   Private Election:", privateElection.toString());
-  console.log("   Nullifier Set:", nullifierSet.toString());
+  console.log("   Encrypted Tally:", encryptedTally.toString());
 
   try {
     console.log("\n🚀 Initializing private election...");
 
     const tx = await program.methods
       .initializePrivateElection(
-        Array.from(electionId),
         keys.election.mpcPublicKey,
         keys.voters.merkleRoot,
+        startsAt,
         endsAt,
-        numOptions
+        numOptions,
+        { singleChoice: {} },
+        { public: {} },
+        false,
+        { equal: {} },
+        new anchor.BN(7 * 86400),
+        new anchor.BN(0)
       )
-      .accounts({
+      .accountsPartial({
         privateElection,
-        nullifierSet,
-        election: mockElection.publicKey,
+        encryptedTally,
+        election,
+        electionAuthority: electionAuthority.publicKey,
         authority: provider.wallet.publicKey,
+        payer: provider.wallet.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
-      .signers([mockElection])
+      .signers([electionAuthority])
       .rpc();
 
     console.log("✅ Transaction successful!");
@@ -97,9 +104,10 @@ async function setupElection() {
     const electionInfo = {
       network: "devnet",
       privateElection: privateElection.toString(),
-      mockElection: mockElection.publicKey.toString(),
-      nullifierSet: nullifierSet.toString(),
-      electionId: Array.from(electionId),
+      election: election.toString(),
+      encryptedTally: encryptedTally.toString(),
+      // Derived by the program from the private election's address
+      electionId: electionData.electionId,
       createdAt: new Date().toISOString(),
       endsAt: new Date(electionData.endsAt.toNumber() * 1000).toISOString(),
       numOptions,
@@ -1,13 +1,37 @@
-import { Connection, PublicKey, TransactionInstruction, SystemProgram } from "@solana/web3.js";
+import {
+  Connection,
+  PublicKey,
+  Signer,
+  SystemProgram,
+} from "@solana/web3.js";
 import { Program, AnchorProvider, BN } from "@coral-xyz/anchor";
-import { EncryptedVoteData, PrivateElectionConfig, VoterSecret } from "./types";
-import { prepareVoteData } from "./crypto";
-import { computeNullifier } from "./nullifier";
+import {
+  DecryptionProof,
+  Eligibility,
+  EncryptedBallot,
+  Groth16Proof,
+  MerkleProof,
+  PrivateElectionConfig,
+} from "./types";
+
+/** Options written per `writeBallotBuffer` transaction, each with its 0/1 proof */
+export const OPTIONS_PER_WRITE = 4;
+
+/** Total proof entries written per `writeBallotBuffer` transaction */
+export const TOTALS_PER_WRITE = 14;
+
+/** Merkle proof siblings written per `writeBallotBuffer` transaction */
+export const SIBLINGS_PER_WRITE = 28;
+
+/** Counts submitted per tally transaction, each with its decryption proof */
+export const COUNTS_PER_SUBMIT = 13;
 
 /**
  * Privacy Layer SDK Client
  *
- * High-level interface for interacting with the privacy layer program
+ * High-level interface for interacting with the privacy layer program.
+ * Ballots are encrypted and proven off-chain (see `privacy-layer-client`);
+ * this client only derives accounts and submits them.
  */
 export class PrivacyLayerClient {
   constructor(
@@ -34,11 +58,24 @@ export class PrivacyLayerClient {
   }
 
   /**
-   * Find the nullifier set PDA
+   * Find the encrypted tally PDA
+   */
+  async findEncryptedTallyPda(electionPubkey: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("encrypted_tally"), electionPubkey.toBuffer()],
+      this.programId
+    );
+  }
+
+  /**
+   * Find the nullifier record PDA
    */
-  async findNullifierSetPda(electionPubkey: PublicKey): Promise<[PublicKey, number]> {
+  async findNullifierRecordPda(
+    privateElectionPubkey: PublicKey,
+    nullifier: Uint8Array
+  ): Promise<[PublicKey, number]> {
     return PublicKey.findProgramAddressSync(
-      [Buffer.from("nullifier_set"), electionPubkey.toBuffer()],
+      [Buffer.from("nullifier"), privateElectionPubkey.toBuffer(), Buffer.from(nullifier)],
       this.programId
     );
   }
@@ -56,68 +93,205 @@ export class PrivacyLayerClient {
     );
   }
 
+  /**
+   * Find the ballot buffer PDA `submitter` writes the ballot of
+   * `nullifier` to
+   */
+  async findBallotBufferPda(
+    privateElectionPubkey: PublicKey,
+    submitter: PublicKey,
+    nullifier: Uint8Array
+  ): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("ballot_buffer"),
+        privateElectionPubkey.toBuffer(),
+        submitter.toBuffer(),
+        Buffer.from(nullifier),
+      ],
+      this.programId
+    );
+  }
+
+  /**
+   * Find the eligibility verifying key PDA
+   */
+  async findVerifyingKeyPda(privateElectionPubkey: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("verifying_key"), privateElectionPubkey.toBuffer()],
+      this.programId
+    );
+  }
+
+  /**
+   * Find the tally result PDA
+   */
+  async findTallyResultPda(privateElectionPubkey: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("tally_result"), privateElectionPubkey.toBuffer()],
+      this.programId
+    );
+  }
+
+  /**
+   * Find the relayer fee vault PDA
+   */
+  async findRelayerFeeVaultPda(privateElectionPubkey: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("relayer_fee_vault"), privateElectionPubkey.toBuffer()],
+      this.programId
+    );
+  }
+
+  /**
+   * Find the governance config PDA
+   */
+  async findGovConfigPda(): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddressSync([Buffer.from("gov_config")], this.programId);
+  }
+
   /**
    * Initialize a new private election
+   *
+   * `electionAuthority` is the mpl-gov-micro election's authority and
+   * must co-sign. The provider wallet pays and becomes the election admin.
    */
   async initializePrivateElection(
     election: PublicKey,
+    electionAuthority: Signer,
     config: PrivateElectionConfig
   ): Promise<PublicKey> {
     const [privateElection] = await this.findPrivateElectionPda(election);
-    const [nullifierSet] = await this.findNullifierSetPda(election);
+    const [encryptedTally] = await this.findEncryptedTallyPda(election);
+    const [govConfig] = await this.findGovConfigPda();
 
     await this.program.methods
       .initializePrivateElection(
-        Array.from(config.electionId),
         Array.from(config.mpcPublicKey),
         Array.from(config.voterMerkleRoot),
+        new BN(config.startsAt),
         new BN(config.endsAt),
-        config.numOptions
+        config.numOptions,
+        config.ballotRule,
+        config.eligibilityMode,
+        config.allowRevoting,
+        config.voteWeighting,
+        new BN(config.auditPeriod),
+        new BN(config.maxRelayerFee)
       )
-      .accounts({
+      .accountsPartial({
         privateElection,
-        nullifierSet,
+        encryptedTally,
+        govConfig,
         election,
+        electionAuthority: electionAuthority.publicKey,
         authority: this.provider.publicKey,
+        payer: this.provider.publicKey,
         systemProgram: SystemProgram.programId,
       })
+      .signers([electionAuthority])
       .rpc();
 
     return privateElection;
   }
 
+  /**
+   * Open a pending election for voting from `startsAt`
+   */
+  async openElection(privateElection: PublicKey, startsAt: number): Promise<string> {
+    return this.program.methods
+      .openElection(new BN(startsAt))
+      .accountsPartial({
+        privateElection,
+        authority: this.provider.publicKey,
+      })
+      .rpc();
+  }
+
+  /**
+   * Write a ballot to the provider wallet's buffer for its nullifier
+   *
+   * Creates the buffer, then writes the options `OPTIONS_PER_WRITE` at a
+   * time, the total proof `TOTALS_PER_WRITE` entries at a time and a
+   * public voter's `merkleProof` `SIBLINGS_PER_WRITE` siblings at a time,
+   * one transaction each. Cast, relay and recast take the ballot from here.
+   */
+  async writeBallotBuffer(
+    privateElection: PublicKey,
+    ballot: EncryptedBallot,
+    merkleProof?: MerkleProof
+  ): Promise<PublicKey> {
+    const submitter = this.provider.publicKey;
+    const [ballotBuffer] = await this.findBallotBufferPda(privateElection, submitter, ballot.nullifier);
+    const nullifier = Array.from(ballot.nullifier);
+
+    await this.program.methods
+      .createBallotBuffer(nullifier, merkleProof ? merkleProof.siblings.length : 0)
+      .accountsPartial({
+        privateElection,
+        ballotBuffer,
+        submitter,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    for (const chunk of ballotChunks(ballot, merkleProof)) {
+      await this.program.methods
+        .writeBallotBuffer(nullifier, chunk)
+        .accountsPartial({ privateElection, ballotBuffer, submitter })
+        .rpc();
+    }
+
+    return ballotBuffer;
+  }
+
+  /**
+   * Close the provider wallet's ballot buffer without casting it,
+   * refunding its rent
+   */
+  async closeBallotBuffer(privateElection: PublicKey, nullifier: Uint8Array): Promise<string> {
+    const submitter = this.provider.publicKey;
+    const [ballotBuffer] = await this.findBallotBufferPda(privateElection, submitter, nullifier);
+
+    return this.program.methods
+      .closeBallotBuffer()
+      .accountsPartial({ ballotBuffer, submitter })
+      .rpc();
+  }
+
   /**
    * Cast an encrypted vote
+   *
+   * The ballot is written to the voter's buffer first, along with their
+   * Merkle proof in a public election.
    */
   async castEncryptedVote(
     privateElection: PublicKey,
-    voteData: EncryptedVoteData
+    ballot: EncryptedBallot,
+    eligibility: Eligibility
   ): Promise<string> {
     const election = await this.program.account.privateElection.fetch(privateElection);
-    const [nullifierSet] = await this.findNullifierSetPda(election.election);
-    const [encryptedVote] = await this.findEncryptedVotePda(
-      privateElection,
-      voteData.nullifier
-    );
-
-    const zkProofA = voteData.zkProof?.a ? Array.from(voteData.zkProof.a) : null;
-    const zkProofB = voteData.zkProof?.b ? Array.from(voteData.zkProof.b) : null;
-    const zkProofC = voteData.zkProof?.c ? Array.from(voteData.zkProof.c) : null;
+    const [nullifierRecord] = await this.findNullifierRecordPda(privateElection, ballot.nullifier);
+    const [encryptedVote] = await this.findEncryptedVotePda(privateElection, ballot.nullifier);
+    const [encryptedTally] = await this.findEncryptedTallyPda(election.election);
+    const [verifyingKey] = await this.findVerifyingKeyPda(privateElection);
+    const ballotBuffer = await this.writeBallotBuffer(privateElection, ballot, eligibility.merkleProof);
 
+    const zkProof = eligibility.zkProof;
     const signature = await this.program.methods
       .castEncryptedVote(
-        Array.from(voteData.ciphertext.c1),
-        Array.from(voteData.ciphertext.c2),
-        Array.from(voteData.nullifier),
-        Array.from(voteData.commitment),
-        zkProofA,
-        zkProofB,
-        zkProofC
+        ...ballotArgs(ballot),
+        zkProof ? Array.from(zkProof.a) : null,
+        zkProof ? Array.from(zkProof.b) : null,
+        zkProof ? Array.from(zkProof.c) : null
       )
-      .accounts({
+      .accountsPartial({
         privateElection,
+        nullifierRecord,
         encryptedVote,
-        nullifierSet,
+        ballotBuffer,
+        encryptedTally,
+        verifyingKey: zkProof ? verifyingKey : null,
         voter: this.provider.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -127,32 +301,153 @@ export class PrivacyLayerClient {
   }
 
   /**
-   * Prepare and cast a vote (convenience method)
+   * Submit an anonymous voter's ballot as a relayer
    *
-   * This combines vote preparation and submission
+   * The provider wallet is the relayer and writes the ballot to its own
+   * buffer first; `zkProof` must be over the ballot's signal with the
+   * relayer and `relayerFee` bound in.
    */
-  async prepareAndCastVote(
+  async relayEncryptedVote(
     privateElection: PublicKey,
-    vote: number,
-    voterSecret: VoterSecret
+    ballot: EncryptedBallot,
+    zkProof: Groth16Proof,
+    relayerFee: number
   ): Promise<string> {
-    // Fetch election data
-    const electionData = await this.program.account.privateElection.fetch(privateElection);
-
-    // Prepare vote data
-    const voteData = prepareVoteData(
-      vote,
-      new Uint8Array(electionData.mpcPublicKey),
-      voterSecret,
-      new Uint8Array(electionData.electionId)
-    );
+    const election = await this.program.account.privateElection.fetch(privateElection);
+    const [nullifierRecord] = await this.findNullifierRecordPda(privateElection, ballot.nullifier);
+    const [encryptedVote] = await this.findEncryptedVotePda(privateElection, ballot.nullifier);
+    const [encryptedTally] = await this.findEncryptedTallyPda(election.election);
+    const [verifyingKey] = await this.findVerifyingKeyPda(privateElection);
+    const [relayerFeeVault] = await this.findRelayerFeeVaultPda(privateElection);
+    const ballotBuffer = await this.writeBallotBuffer(privateElection, ballot);
 
-    // Cast the vote
-    return this.castEncryptedVote(privateElection, {
-      ciphertext: voteData.ciphertext,
-      nullifier: voteData.nullifier,
-      commitment: voteData.commitment,
-    });
+    return this.program.methods
+      .relayEncryptedVote(
+        ...ballotArgs(ballot),
+        Array.from(zkProof.a),
+        Array.from(zkProof.b),
+        Array.from(zkProof.c),
+        new BN(relayerFee)
+      )
+      .accountsPartial({
+        privateElection,
+        nullifierRecord,
+        encryptedVote,
+        ballotBuffer,
+        encryptedTally,
+        verifyingKey,
+        relayerFeeVault,
+        relayer: this.provider.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  /**
+   * Replace a previously cast ballot, in elections that allow re-voting
+   *
+   * The new ballot is written to a fresh buffer first.
+   */
+  async recastEncryptedVote(
+    privateElection: PublicKey,
+    ballot: EncryptedBallot,
+    eligibility: Eligibility
+  ): Promise<string> {
+    const election = await this.program.account.privateElection.fetch(privateElection);
+    const [encryptedVote] = await this.findEncryptedVotePda(privateElection, ballot.nullifier);
+    const [encryptedTally] = await this.findEncryptedTallyPda(election.election);
+    const [verifyingKey] = await this.findVerifyingKeyPda(privateElection);
+    const ballotBuffer = await this.writeBallotBuffer(privateElection, ballot, eligibility.merkleProof);
+
+    const zkProof = eligibility.zkProof;
+    return this.program.methods
+      .recastEncryptedVote(
+        ...ballotArgs(ballot),
+        zkProof ? Array.from(zkProof.a) : null,
+        zkProof ? Array.from(zkProof.b) : null,
+        zkProof ? Array.from(zkProof.c) : null
+      )
+      .accountsPartial({
+        privateElection,
+        encryptedVote,
+        ballotBuffer,
+        encryptedTally,
+        verifyingKey: zkProof ? verifyingKey : null,
+        voter: this.provider.publicKey,
+      })
+      .rpc();
+  }
+
+  /**
+   * Close voting; the authority may close at any time
+   */
+  async closeElection(privateElection: PublicKey): Promise<string> {
+    return this.program.methods
+      .closeElection()
+      .accountsPartial({
+        privateElection,
+        closer: this.provider.publicKey,
+      })
+      .rpc();
+  }
+
+  /**
+   * Request the tally once voting is over
+   */
+  async requestTally(privateElection: PublicKey): Promise<string> {
+    return this.program.methods
+      .requestTally()
+      .accountsPartial({
+        privateElection,
+        authority: this.provider.publicKey,
+      })
+      .rpc();
+  }
+
+  /**
+   * Submit the decrypted per-option counts with their decryption proofs
+   *
+   * `COUNTS_PER_SUBMIT` counts per transaction: `submitTallyResult` with
+   * the first, then `appendTallyResult` with the rest. Returns the
+   * signature of the last, which finalizes the election.
+   */
+  async submitTallyResult(
+    privateElection: PublicKey,
+    counts: number[],
+    proofs: DecryptionProof[]
+  ): Promise<string> {
+    const election = await this.program.account.privateElection.fetch(privateElection);
+    const [encryptedTally] = await this.findEncryptedTallyPda(election.election);
+    const [tallyResult] = await this.findTallyResultPda(privateElection);
+    const submitter = this.provider.publicKey;
+
+    let signature = "";
+    for (let first = 0; first < counts.length; first += COUNTS_PER_SUBMIT) {
+      const chunkCounts = counts.slice(first, first + COUNTS_PER_SUBMIT).map((count) => new BN(count));
+      const chunkProofs = proofs.slice(first, first + COUNTS_PER_SUBMIT).map((proof) => ({
+        challenge: Array.from(proof.challenge),
+        response: Array.from(proof.response),
+      }));
+
+      signature =
+        first === 0
+          ? await this.program.methods
+              .submitTallyResult(chunkCounts, chunkProofs)
+              .accountsPartial({
+                privateElection,
+                encryptedTally,
+                tallyResult,
+                submitter,
+                systemProgram: SystemProgram.programId,
+              })
+              .rpc()
+          : await this.program.methods
+              .appendTallyResult(chunkCounts, chunkProofs)
+              .accountsPartial({ privateElection, encryptedTally, tallyResult, submitter })
+              .rpc();
+    }
+
+    return signature;
   }
 
   /**
@@ -162,6 +457,14 @@ export class PrivacyLayerClient {
     return this.program.account.privateElection.fetch(privateElection);
   }
 
+  /**
+   * Get the running encrypted tally of an election
+   */
+  async getEncryptedTally(election: PublicKey): Promise<any> {
+    const [encryptedTally] = await this.findEncryptedTallyPda(election);
+    return this.program.account.encryptedTally.fetch(encryptedTally);
+  }
+
   /**
    * Get all encrypted votes for an election
    */
@@ -169,7 +472,7 @@ export class PrivacyLayerClient {
     return this.program.account.encryptedVote.all([
       {
         memcmp: {
-          offset: 8 + 1, // discriminator + bump
+          offset: 8 + 1 + 1, // discriminator + version + bump
           bytes: privateElection.toBase58(),
         },
       },
@@ -178,24 +481,68 @@ export class PrivacyLayerClient {
 
   /**
    * Check if a nullifier has been used
+   *
+   * Every spent nullifier has its own record account
    */
   async isNullifierUsed(
-    election: PublicKey,
+    privateElection: PublicKey,
     nullifier: Uint8Array
   ): Promise<boolean> {
-    const [nullifierSet] = await this.findNullifierSetPda(election);
-    const nullifierSetData = await this.program.account.nullifierSet.fetch(nullifierSet);
-
-    // Check if nullifier exists in the set
-    for (const usedNullifier of nullifierSetData.nullifiers) {
-      const match = usedNullifier.every((byte: number, index: number) => byte === nullifier[index]);
-      if (match) {
-        return true;
-      }
-    }
+    const [nullifierRecord] = await this.findNullifierRecordPda(privateElection, nullifier);
+    const accountInfo = await this.connection.getAccountInfo(nullifierRecord);
+
+    return accountInfo !== null;
+  }
+}
+
+/**
+ * Instruction arguments shared by cast, relay and recast, up to the
+ * Groth16 proof; the ballot and any Merkle proof are in its buffer
+ */
+function ballotArgs(ballot: EncryptedBallot): [number[], number[], BN] {
+  return [Array.from(ballot.nullifier), Array.from(ballot.commitment), new BN(ballot.weight)];
+}
 
-    return false;
+/**
+ * `write_ballot_buffer` chunks of a ballot and a public voter's Merkle
+ * proof, in the order they are written
+ */
+function ballotChunks(ballot: EncryptedBallot, merkleProof?: MerkleProof): any[] {
+  const chunks: any[] = [];
+  for (let first = 0; first < ballot.ciphertexts.length; first += OPTIONS_PER_WRITE) {
+    const end = first + OPTIONS_PER_WRITE;
+    chunks.push({
+      options: {
+        first,
+        ciphertexts: ballot.ciphertexts.slice(first, end).map((ciphertext) => ({
+          c1: Array.from(ciphertext.c1),
+          c2: Array.from(ciphertext.c2),
+        })),
+        slotProofs: ballot.proof.slotProofs.slice(first, end).map((proof) => Array.from(proof)),
+      },
+    });
+  }
+  for (let first = 0; first < ballot.proof.totalChallenges.length; first += TOTALS_PER_WRITE) {
+    const end = first + TOTALS_PER_WRITE;
+    chunks.push({
+      totalProof: {
+        first,
+        challenges: ballot.proof.totalChallenges.slice(first, end).map((challenge) => Array.from(challenge)),
+        responses: ballot.proof.totalResponses.slice(first, end).map((response) => Array.from(response)),
+      },
+    });
+  }
+  const siblings = merkleProof ? merkleProof.siblings : [];
+  for (let first = 0; first < siblings.length; first += SIBLINGS_PER_WRITE) {
+    chunks.push({
+      merkleProof: {
+        leafIndex: merkleProof!.leafIndex,
+        first,
+        siblings: siblings.slice(first, first + SIBLINGS_PER_WRITE).map((sibling) => Array.from(sibling)),
+      },
+    });
   }
+  return chunks;
 }
 
 /**
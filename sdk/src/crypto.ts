@@ -122,7 +122,7 @@ export function prepareVoteData(
 
   // Compute nullifier
   const { computeNullifier } = require("./nullifier");
-  const nullifier = computeNullifier(voterSecret, electionId, 0);
+  const nullifier = computeNullifier(voterSecret, electionId);
 
   // Create commitment
   const commitment = commitEncryptedVote(ciphertext, commitmentRandomness);
@@ -13,6 +13,8 @@ export * from "./client";
 // Re-export commonly used functions
 export {
   computeNullifier,
+  computePublicNullifier,
+  computePoseidonNullifier,
   generateVoterSecret,
   verifyNullifier,
 } from "./nullifier";
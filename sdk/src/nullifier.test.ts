@@ -0,0 +1,58 @@
+import { PublicKey } from "@solana/web3.js";
+import * as fs from "fs";
+import * as path from "path";
+import {
+  computeNullifier,
+  computePoseidonNullifier,
+  computePublicNullifier,
+  deriveElectionId,
+  verifyNullifier,
+} from "./nullifier";
+import { bytesToHex, hexToBytes } from "./utils";
+
+// Shared with `privacy_crypto::nullifier`, so both sides must agree byte for byte
+const vectors = JSON.parse(
+  fs.readFileSync(path.join(__dirname, "../../test-vectors/nullifier.json"), "utf8")
+);
+
+describe("nullifier vectors", () => {
+  it("matches keccak_v1", () => {
+    for (const v of vectors.keccak_v1) {
+      const nullifier = computeNullifier(hexToBytes(v.voter_secret), hexToBytes(v.election_id));
+      expect(bytesToHex(nullifier)).toBe(v.nullifier);
+      expect(verifyNullifier(nullifier, hexToBytes(v.voter_secret), hexToBytes(v.election_id))).toBe(true);
+    }
+  });
+
+  it("matches public_v1", () => {
+    for (const v of vectors.public_v1) {
+      const nullifier = computePublicNullifier(hexToBytes(v.voter), hexToBytes(v.election_id));
+      expect(bytesToHex(nullifier)).toBe(v.nullifier);
+    }
+  });
+
+  it("matches poseidon_v1", () => {
+    for (const v of vectors.poseidon_v1) {
+      const nullifier = computePoseidonNullifier(hexToBytes(v.voter_secret), hexToBytes(v.election_id));
+      expect(bytesToHex(nullifier)).toBe(v.nullifier);
+    }
+  });
+});
+
+describe("election ids", () => {
+  // The TypeScript test fixtures record each election's id
+  const ballots = JSON.parse(
+    fs.readFileSync(path.join(__dirname, "../../test-vectors/ballots.json"), "utf8")
+  );
+
+  it("matches the fixture elections", () => {
+    const programId = new PublicKey(ballots.program_id);
+    for (const fixture of Object.values<any>(ballots.elections)) {
+      const [privateElection] = PublicKey.findProgramAddressSync(
+        [Buffer.from("private_election"), new PublicKey(fixture.election).toBuffer()],
+        programId
+      );
+      expect(bytesToHex(deriveElectionId(privateElection))).toBe(fixture.election_id);
+    }
+  });
+});
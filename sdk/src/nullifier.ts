@@ -1,37 +1,100 @@
+import { PublicKey } from "@solana/web3.js";
 import { keccak256 } from "js-sha3";
+import { poseidon3 } from "poseidon-lite";
 import { Nullifier, VoterSecret } from "./types";
-import { numberToLeBytes, to32Bytes } from "./utils";
+import { bytesToHex, hexToBytes, to32Bytes } from "./utils";
+
+/**
+ * Domain tags, matching `privacy_crypto::nullifier`
+ *
+ * Every scheme is versioned; a change to a derivation gets a new tag.
+ * Shared vectors live in `test-vectors/nullifier.json`.
+ */
+export const NULLIFIER_DOMAIN = "privacy-layer/nullifier/v1";
+export const PUBLIC_NULLIFIER_DOMAIN = "privacy-layer/public-nullifier/v1";
+export const POSEIDON_NULLIFIER_DOMAIN = "nullifier-v1";
+
+/** BN254 scalar field modulus */
+const BN254_FIELD_MODULUS = BigInt(
+  "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+);
+
+function taggedKeccak(domain: string, first: Uint8Array, second: Uint8Array): Uint8Array {
+  const tag = new TextEncoder().encode(domain);
+  const input = new Uint8Array(tag.length + first.length + second.length);
+  input.set(tag, 0);
+  input.set(first, tag.length);
+  input.set(second, tag.length + first.length);
+
+  return new Uint8Array(keccak256.array(input));
+}
+
+function toField(bytes: Uint8Array): bigint {
+  const hex = bytesToHex(bytes);
+  return hex.length === 0 ? BigInt(0) : BigInt("0x" + hex) % BN254_FIELD_MODULUS;
+}
+
+/**
+ * Election id of the private election at `privateElection`
+ *
+ * The program derives it from the address, as in
+ * `PrivateElection::derive_election_id`.
+ */
+export function deriveElectionId(privateElection: PublicKey): Uint8Array {
+  return privateElection.toBytes();
+}
 
 /**
  * Compute a nullifier for vote uniqueness
  *
- * Nullifier = H(voter_secret || election_id || nonce)
+ * Nullifier = Keccak256(NULLIFIER_DOMAIN || election_id || voter_secret)
  *
  * @param voterSecret - Voter's secret key (32 bytes)
  * @param electionId - Election identifier (32 bytes)
- * @param nonce - Nonce value (default: 0)
  * @returns Nullifier (32 bytes)
  */
 export function computeNullifier(
   voterSecret: VoterSecret,
-  electionId: Uint8Array,
-  nonce: number = 0
+  electionId: Uint8Array
 ): Nullifier {
-  // Ensure inputs are correct size
-  const secret = to32Bytes(voterSecret);
-  const election = to32Bytes(electionId);
-  const nonceBytes = numberToLeBytes(nonce, 8);
+  return taggedKeccak(NULLIFIER_DOMAIN, to32Bytes(electionId), to32Bytes(voterSecret));
+}
 
-  // Concatenate all inputs
-  const input = new Uint8Array(secret.length + election.length + nonceBytes.length);
-  input.set(secret, 0);
-  input.set(election, secret.length);
-  input.set(nonceBytes, secret.length + election.length);
+/**
+ * Compute the nullifier for a public-eligibility election
+ *
+ * Nullifier = Keccak256(PUBLIC_NULLIFIER_DOMAIN || election_id || voter)
+ *
+ * @param voter - Voter's wallet public key (32 bytes)
+ * @param electionId - Election identifier (32 bytes)
+ * @returns Nullifier (32 bytes)
+ */
+export function computePublicNullifier(
+  voter: Uint8Array,
+  electionId: Uint8Array
+): Nullifier {
+  return taggedKeccak(PUBLIC_NULLIFIER_DOMAIN, to32Bytes(electionId), to32Bytes(voter));
+}
 
-  // Hash with Keccak256
-  const hash = keccak256.array(input);
+/**
+ * Compute the circuit-friendly nullifier used by anonymous elections
+ *
+ * Nullifier = Poseidon(POSEIDON_NULLIFIER_DOMAIN, voter_secret, election_id)
+ * with circom parameters over BN254; inputs are big-endian and reduced
+ * modulo the field order, the output is a big-endian field element.
+ *
+ * @param voterSecret - Voter's secret key (32 bytes)
+ * @param electionId - Election identifier (32 bytes)
+ * @returns Nullifier (32 bytes)
+ */
+export function computePoseidonNullifier(
+  voterSecret: VoterSecret,
+  electionId: Uint8Array
+): Nullifier {
+  const domain = toField(new TextEncoder().encode(POSEIDON_NULLIFIER_DOMAIN));
+  const hash = poseidon3([domain, toField(to32Bytes(voterSecret)), toField(to32Bytes(electionId))]);
 
-  return new Uint8Array(hash);
+  return hexToBytes(hash.toString(16).padStart(64, "0"));
 }
 
 /**
@@ -40,16 +103,14 @@ export function computeNullifier(
  * @param nullifier - Nullifier to verify
  * @param voterSecret - Voter's secret key
  * @param electionId - Election identifier
- * @param nonce - Nonce value
  * @returns True if nullifier matches
  */
 export function verifyNullifier(
   nullifier: Nullifier,
   voterSecret: VoterSecret,
-  electionId: Uint8Array,
-  nonce: number = 0
+  electionId: Uint8Array
 ): boolean {
-  const computed = computeNullifier(voterSecret, electionId, nonce);
+  const computed = computeNullifier(voterSecret, electionId);
 
   if (nullifier.length !== computed.length) {
     return false;
@@ -1,4 +1,5 @@
 import { PublicKey } from "@solana/web3.js";
+import { BN } from "@coral-xyz/anchor";
 
 /**
  * ElGamal public key (32 bytes)
@@ -30,28 +31,93 @@ export type Nullifier = Uint8Array;
  */
 export type VoteCommitment = Uint8Array;
 
+/**
+ * How many options a ballot may select, as the program's `BallotRule`
+ */
+export type BallotRule =
+  | { singleChoice: {} }
+  | { approval: { maxChoices: number } };
+
+/**
+ * How voters prove they are on the voter tree, as `EligibilityMode`
+ */
+export type EligibilityMode = { anonymous: {} } | { public: {} };
+
+/**
+ * Where ballot weights come from, as `VoteWeighting`
+ */
+export type VoteWeighting =
+  | { equal: {} }
+  | { merkleLeaf: {} }
+  | { tokenSnapshot: { mint: PublicKey; slot: BN } };
+
 /**
  * Private election configuration
  */
 export interface PrivateElectionConfig {
-  electionId: Uint8Array; // 32 bytes
   mpcPublicKey: ElGamalPublicKey;
   voterMerkleRoot: Uint8Array; // 32 bytes
+  startsAt: number; // Unix timestamp, must be in the future
   endsAt: number; // Unix timestamp
   numOptions: number;
+  ballotRule: BallotRule;
+  eligibilityMode: EligibilityMode;
+  allowRevoting: boolean;
+  voteWeighting: VoteWeighting;
+  auditPeriod: number; // Seconds after finalization
+  maxRelayerFee: number; // Lamports per relayed ballot
 }
 
 /**
- * Encrypted vote data
+ * Proof that every option encrypts 0 or 1 and the ballot follows the
+ * election's rule
  */
-export interface EncryptedVoteData {
-  ciphertext: ElGamalCiphertext;
+export interface BallotProof {
+  slotProofs: Uint8Array[]; // 128 bytes per option
+  totalChallenges: Uint8Array[]; // 32 bytes each
+  totalResponses: Uint8Array[]; // 32 bytes each
+}
+
+/**
+ * An encrypted ballot ready to cast, as prepared by `privacy-layer-client`
+ */
+export interface EncryptedBallot {
   nullifier: Nullifier;
-  commitment: VoteCommitment;
-  // ZK proof components (optional for MVP)
-  zkProof?: {
-    a: Uint8Array; // 32 bytes
-    b: Uint8Array; // 64 bytes
-    c: Uint8Array; // 32 bytes
-  };
+  commitment: VoteCommitment; // commit_ballot(election_id, nullifier, ciphertexts)
+  ciphertexts: ElGamalCiphertext[]; // One per option
+  weight: number; // 1 unless the election is weighted
+  proof: BallotProof;
+}
+
+/**
+ * Merkle inclusion proof of the voter's wallet (public eligibility)
+ */
+export interface MerkleProof {
+  leafIndex: number;
+  siblings: Uint8Array[]; // 32 bytes each, leaf level first
+}
+
+/**
+ * Groth16 eligibility proof (anonymous eligibility)
+ */
+export interface Groth16Proof {
+  a: Uint8Array; // 64 bytes
+  b: Uint8Array; // 128 bytes
+  c: Uint8Array; // 64 bytes
+}
+
+/**
+ * How a voter proves eligibility; exactly one is set
+ */
+export interface Eligibility {
+  merkleProof?: MerkleProof;
+  zkProof?: Groth16Proof;
+}
+
+/**
+ * Proof that a tally count decrypts the on-chain aggregate
+ */
+export interface DecryptionProof {
+  challenge: Uint8Array; // 32 bytes
+  response: Uint8Array; // 32 bytes
 }